@@ -0,0 +1,124 @@
+use std::path::PathBuf;
+
+use clap::{Args, Parser, Subcommand};
+
+pub const DEFAULT_BIND: &str = "0.0.0.0";
+pub const DEFAULT_PORT: u16 = 8080;
+
+/// Records efforts and visualizes them as a calendar heatmap.
+#[derive(Parser)]
+#[command(version)]
+pub struct Cli {
+    /// Runs `serve` with the default options when omitted.
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Starts the HTTP server.
+    Serve(ServeArgs),
+    /// Writes generated artifacts.
+    #[command(subcommand)]
+    Emit(EmitCommand),
+    /// Manages the database schema.
+    #[command(subcommand)]
+    Migrate(MigrateCommand),
+    /// Validates the environment variables and checks that the database is reachable.
+    CheckConfig,
+    /// Administers users.
+    #[command(subcommand)]
+    User(UserCommand),
+}
+
+#[derive(Args)]
+pub struct ServeArgs {
+    /// Address to listen on.
+    #[arg(long, default_value = DEFAULT_BIND)]
+    pub bind: String,
+    /// Port to listen on.
+    #[arg(long, default_value_t = DEFAULT_PORT)]
+    pub port: u16,
+}
+
+impl Default for ServeArgs {
+    fn default() -> Self {
+        Self {
+            bind: DEFAULT_BIND.to_owned(),
+            port: DEFAULT_PORT,
+        }
+    }
+}
+
+#[derive(Subcommand)]
+pub enum EmitCommand {
+    /// Writes the OpenAPI document the api-client is generated from.
+    Openapi(EmitOpenApiArgs),
+}
+
+#[derive(Args)]
+pub struct EmitOpenApiArgs {
+    /// Writes YAML instead of JSON.
+    #[arg(long)]
+    pub yaml: bool,
+    /// Writes to this file instead of the standard output.
+    #[arg(long, value_name = "FILE")]
+    pub out: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, Subcommand)]
+pub enum MigrateCommand {
+    /// Applies every pending migration.
+    Up,
+    /// Reverts the latest applied migration.
+    Down,
+    /// Lists the migrations and whether they are applied.
+    Status,
+}
+
+#[derive(Subcommand)]
+pub enum UserCommand {
+    /// Lists the registered users.
+    List,
+    /// Shows a user.
+    Show {
+        /// Email address of the user.
+        email: String,
+    },
+    /// Deletes a user together with their efforts and sessions.
+    Delete {
+        /// Email address of the user.
+        email: String,
+    },
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Command, EmitCommand};
+    use clap::{CommandFactory, Parser};
+
+    #[test]
+    fn コマンド定義が正しい() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn openapiをyamlでファイルに出力できる() {
+        let cli = Cli::parse_from([
+            "effort_visualizer",
+            "emit",
+            "openapi",
+            "--yaml",
+            "--out",
+            "open-api.yaml",
+        ]);
+
+        match cli.command {
+            Some(Command::Emit(EmitCommand::Openapi(args))) => {
+                assert!(args.yaml);
+                assert_eq!(Some("open-api.yaml".into()), args.out);
+            }
+            _ => panic!("emit openapi is expected."),
+        }
+    }
+}
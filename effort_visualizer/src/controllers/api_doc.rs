@@ -1,22 +1,181 @@
 use utoipa::OpenApi;
 
+use super::super::domain::{
+    categories::Category,
+    efforts::{Effort, FilterMatch},
+    events::{EventKind, UserEvent},
+    goals::{Goal, GoalMetric, GoalPeriod, GoalPeriodProgress},
+    heatmap::HeatmapBucket,
+    identities::UserIdentity,
+    pomodoros::{PomodoroDay, PomodoroPhase, PomodoroPhaseKind, PomodoroSettings},
+    sessions::UserSession,
+    streaks::Streak,
+    tags::Tag,
+    timers::Timer,
+    users::User,
+};
 use super::super::dto::{
-    LoginRequest, LoginResult, LoginSituation, SignupRequest, SignupResult, SignupSituation,
+    CategoryRequest, CategoryResult, CategorySituation, ColumnMapping, EffortExportFormat,
+    EffortRequest, EffortResult, EffortSituation, ErrorCode, GoalProgress, GoalProgressResult,
+    GoalRequest, GoalResult, GoalSituation, Heatmap, HeatmapCell, HeatmapResult, HeatmapSituation,
+    ImportPreset, ImportReport, ImportResult, ImportRow, ImportRowStatus, ImportSituation,
+    ImportUpload, LinkIdentityResult, LinkIdentitySituation, LoginRequest, LoginResult,
+    LoginSituation, PomodoroStatsResult, PomodoroStatsSituation, ProblemDetails, ProfileRequest,
+    ProfileResult, ProfileSituation, PublicCalendar, SignupRequest, SignupResult, SignupSituation,
+    StreakResult, StreakSituation, Streaks, TagMergeRequest, TagRequest, TagResult, TagSituation,
+    TimerRequest, TimerResult, TimerSituation, TimerState, UnlinkIdentityResult,
+    UnlinkIdentitySituation,
 };
 
 #[derive(OpenApi)]
 #[openapi(
     paths(
         crate::controllers::authentication_controllers::login,
-        crate::controllers::authentication_controllers::signup
+        crate::controllers::authentication_controllers::signup,
+        crate::controllers::authentication_controllers::me,
+        crate::controllers::authentication_controllers::logout,
+        crate::controllers::user_controllers::update_profile,
+        crate::controllers::user_controllers::delete_account,
+        crate::controllers::user_controllers::export_data,
+        crate::controllers::user_controllers::publish_calendar,
+        crate::controllers::user_controllers::unpublish_calendar,
+        crate::controllers::effort_controllers::get_efforts,
+        crate::controllers::effort_controllers::get_effort,
+        crate::controllers::effort_controllers::add_effort,
+        crate::controllers::effort_controllers::update_effort,
+        crate::controllers::effort_controllers::delete_effort,
+        crate::controllers::effort_controllers::import_efforts,
+        crate::controllers::effort_controllers::export_efforts,
+        crate::controllers::category_controllers::get_categories,
+        crate::controllers::category_controllers::add_category,
+        crate::controllers::category_controllers::update_category,
+        crate::controllers::category_controllers::delete_category,
+        crate::controllers::tag_controllers::get_tags,
+        crate::controllers::tag_controllers::add_tag,
+        crate::controllers::tag_controllers::rename_tag,
+        crate::controllers::tag_controllers::merge_tag,
+        crate::controllers::tag_controllers::delete_tag,
+        crate::controllers::goal_controllers::get_goals,
+        crate::controllers::goal_controllers::add_goal,
+        crate::controllers::goal_controllers::update_goal,
+        crate::controllers::goal_controllers::delete_goal,
+        crate::controllers::goal_controllers::get_goal_progress,
+        crate::controllers::timer_controllers::get_timer,
+        crate::controllers::timer_controllers::start_timer,
+        crate::controllers::timer_controllers::pause_timer,
+        crate::controllers::timer_controllers::resume_timer,
+        crate::controllers::timer_controllers::stop_timer,
+        crate::controllers::pomodoro_controllers::get_pomodoro_stats,
+        crate::controllers::event_controllers::connect_events,
+        crate::controllers::event_controllers::stream_events,
+        crate::controllers::heatmap_controllers::get_heatmap,
+        crate::controllers::heatmap_controllers::get_heatmap_svg,
+        crate::controllers::heatmap_controllers::get_public_heatmap,
+        crate::controllers::heatmap_controllers::get_public_heatmap_svg,
+        crate::controllers::streak_controllers::get_streaks,
+        crate::controllers::streak_controllers::get_public_streaks,
+        crate::controllers::identity_controllers::get_identities,
+        crate::controllers::identity_controllers::link_identity,
+        crate::controllers::identity_controllers::unlink_identity,
+        crate::controllers::session_controllers::get_sessions,
+        crate::controllers::session_controllers::revoke_session
     ),
     components(schemas(
+        User,
+        UserIdentity,
+        Effort,
+        Category,
+        Tag,
+        UserSession,
         LoginRequest,
         LoginResult,
         SignupRequest,
         SignupResult,
         LoginSituation,
-        SignupSituation
+        SignupSituation,
+        ProfileRequest,
+        ProfileResult,
+        ProfileSituation,
+        PublicCalendar,
+        LinkIdentityResult,
+        LinkIdentitySituation,
+        UnlinkIdentityResult,
+        UnlinkIdentitySituation,
+        EffortRequest,
+        EffortResult,
+        EffortSituation,
+        FilterMatch,
+        CategoryRequest,
+        CategoryResult,
+        CategorySituation,
+        TagRequest,
+        TagMergeRequest,
+        TagResult,
+        TagSituation,
+        Goal,
+        GoalMetric,
+        GoalPeriod,
+        GoalPeriodProgress,
+        GoalRequest,
+        GoalResult,
+        GoalSituation,
+        GoalProgress,
+        GoalProgressResult,
+        Timer,
+        TimerRequest,
+        TimerState,
+        TimerResult,
+        TimerSituation,
+        PomodoroSettings,
+        PomodoroPhase,
+        PomodoroPhaseKind,
+        PomodoroDay,
+        PomodoroStatsResult,
+        PomodoroStatsSituation,
+        EventKind,
+        UserEvent,
+        ImportPreset,
+        ColumnMapping,
+        ImportUpload,
+        ImportResult,
+        ImportSituation,
+        ImportReport,
+        ImportRow,
+        ImportRowStatus,
+        EffortExportFormat,
+        HeatmapBucket,
+        Heatmap,
+        HeatmapCell,
+        HeatmapResult,
+        HeatmapSituation,
+        Streak,
+        Streaks,
+        StreakResult,
+        StreakSituation,
+        ProblemDetails,
+        ErrorCode
     ))
 )]
 pub struct ApiDoc;
+
+#[cfg(test)]
+mod tests {
+    use super::ApiDoc;
+    use utoipa::OpenApi;
+
+    #[test]
+    fn 全てのパスがエラー時のスキーマを記述している() {
+        let document = serde_json::to_value(ApiDoc::openapi()).unwrap();
+
+        for (path, item) in document["paths"].as_object().unwrap() {
+            for (method, operation) in item.as_object().unwrap() {
+                let schema = &operation["responses"]["500"]["content"]["application/problem+json"]
+                    ["schema"]["$ref"];
+                assert_eq!(
+                    "#/components/schemas/ProblemDetails", schema,
+                    "{method} {path}"
+                );
+            }
+        }
+    }
+}
@@ -1,14 +1,16 @@
 use super::errors::ApiError;
+use super::event_controllers::log_push_failure;
+use super::extractors::{AuthenticatedUser, CURRENT_USER};
+use crate::domain::users::User;
 use crate::dto::{LoginRequest, LoginSituation, SignupRequest, SignupSituation};
-use crate::usecases::authentication_usecase::AuthenticationUsecase;
+use crate::usecases::{authentication_usecase::AuthenticationUsecase, event_usecase::EventUsecase};
 use actix_session::Session;
-use actix_web::error::ErrorInternalServerError;
 use actix_web::{
-    post,
+    get, post,
     web::{self, Data},
     HttpResponse,
 };
-use anyhow::Result;
+use anyhow::{anyhow, Result};
 use futures::TryFutureExt;
 
 #[utoipa::path(
@@ -17,8 +19,10 @@ use futures::TryFutureExt;
     responses(
         (status = 200, description = "Login user", body = LoginResult),
         (status = 202, description = "Not Registered", body = LoginResult),
+        (status = 400, description = "The request body is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
         (status = 401, description = "Login failed", body = LoginResult),
-        (status = 500, description = "Internal error")
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
     ),
 )]
 #[post("/login")]
@@ -28,14 +32,18 @@ pub async fn login(
     credential_info: web::Json<LoginRequest>,
 ) -> Result<HttpResponse, actix_web::Error> {
     let result = usecase
-        .login(&credential_info.credential)
+        .login(&credential_info)
         .map_err(ApiError::from)
         .await?;
     match result.situation {
         LoginSituation::Succeeded => {
             match &result.login_user {
-                Some(user) => session.insert("current_user", user)?,
-                None => return Err(ErrorInternalServerError("Missing user")),
+                Some(user) => {
+                    // A new session key keeps one planted before logging in from being used.
+                    session.renew();
+                    session.insert(CURRENT_USER, user)?
+                }
+                None => return Err(ApiError::Internal(anyhow!("Missing user")).into()),
             }
             Ok(HttpResponse::Ok().json(result))
         }
@@ -50,8 +58,10 @@ pub async fn login(
     responses(
         (status = 200, description = "Sign up is succeeded.", body = SignupResult),
         (status = 202, description = "The user is already registered.", body = SignupResult),
+        (status = 400, description = "The request body is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
         (status = 401, description = "Login failed.", body = SignupResult),
-        (status = 500, description = "Internal error.")
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
     ),
 )]
 #[post("/signup")]
@@ -64,8 +74,12 @@ pub async fn signup(
     match result.situation {
         SignupSituation::Succeeded => {
             match &result.login_user {
-                Some(user) => session.insert("current_user", user)?,
-                None => return Err(ErrorInternalServerError("Missing user")),
+                Some(user) => {
+                    // A new session key keeps one planted before logging in from being used.
+                    session.renew();
+                    session.insert(CURRENT_USER, user)?
+                }
+                None => return Err(ApiError::Internal(anyhow!("Missing user")).into()),
             }
             Ok(HttpResponse::Ok().json(result))
         }
@@ -74,6 +88,40 @@ pub async fn signup(
     }
 }
 
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "The user of the current session.", body = User),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/me")]
+pub async fn me(user: AuthenticatedUser) -> Result<HttpResponse, actix_web::Error> {
+    Ok(HttpResponse::Ok().json(user.0))
+}
+
+#[utoipa::path(
+    post,
+    responses(
+        (status = 200, description = "The session is cleared."),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/logout")]
+pub async fn logout(
+    session: Session,
+    events: Data<Box<dyn EventUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let user = session.get::<User>(CURRENT_USER).ok().flatten();
+    session.purge();
+    // Closes the event connections opened with the session.
+    if let Some(user) = user {
+        log_push_failure(events.session_ended(&user.email).await);
+    }
+    Ok(HttpResponse::Ok().finish())
+}
+
 #[cfg(test)]
 mod tests {
     mod login {
@@ -95,10 +143,12 @@ mod tests {
                     situation: LoginSituation::Succeeded,
                     login_user: Some(User {
                         email: "".to_owned(),
-                        external_id: "".to_owned(),
                         user_name: "".to_owned(),
-                        registered_date: std::time::SystemTime::now(),
-                        updated_date: std::time::SystemTime::now(),
+                        avatar_url: None,
+                        timezone: "UTC".to_owned(),
+                        locale: "en_US".to_owned(),
+                        registered_date: chrono::Utc::now(),
+                        updated_date: chrono::Utc::now(),
                     }),
                     description: None,
                 })
@@ -110,6 +160,7 @@ mod tests {
             let req = test::TestRequest::post()
                 .uri("/login")
                 .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
                     credential: "test".to_owned(),
                 })
                 .to_request();
@@ -125,10 +176,12 @@ mod tests {
                 situation: LoginSituation::Succeeded,
                 login_user: Some(User {
                     email: "".to_owned(),
-                    external_id: "".to_owned(),
                     user_name: "".to_owned(),
-                    registered_date: std::time::SystemTime::now(),
-                    updated_date: std::time::SystemTime::now(),
+                    avatar_url: None,
+                    timezone: "UTC".to_owned(),
+                    locale: "en_US".to_owned(),
+                    registered_date: chrono::Utc::now(),
+                    updated_date: chrono::Utc::now(),
                 }),
                 description: None,
             };
@@ -143,6 +196,7 @@ mod tests {
             let req = test::TestRequest::post()
                 .uri("/login")
                 .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
                     credential: "test".to_owned(),
                 })
                 .to_request();
@@ -172,6 +226,7 @@ mod tests {
             let req = test::TestRequest::post()
                 .uri("/login")
                 .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
                     credential: "test".to_owned(),
                 })
                 .to_request();
@@ -197,6 +252,7 @@ mod tests {
             let req = test::TestRequest::post()
                 .uri("/login")
                 .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
                     credential: "test".to_owned(),
                 })
                 .to_request();
@@ -224,6 +280,7 @@ mod tests {
             let req = test::TestRequest::post()
                 .uri("/login")
                 .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
                     credential: "test".to_owned(),
                 })
                 .to_request();
@@ -253,6 +310,7 @@ mod tests {
             let req = test::TestRequest::post()
                 .uri("/login")
                 .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
                     credential: "test".to_owned(),
                 })
                 .to_request();
@@ -280,6 +338,7 @@ mod tests {
             let req = test::TestRequest::post()
                 .uri("/login")
                 .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
                     credential: "test".to_owned(),
                 })
                 .to_request();
@@ -292,4 +351,144 @@ mod tests {
             assert_eq!(expected, login_result_from_response);
         }
     }
+    mod me {
+        use crate::domain::users::User;
+        use crate::dto::{LoginRequest, LoginResult, LoginSituation};
+        use crate::usecases::authentication_usecase::{
+            AuthenticationUsecase, MockAuthenticationUsecase,
+        };
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use crate::{login, logout, me};
+        use actix_session::{storage::CookieSessionStore, SessionMiddleware};
+        use actix_web::{body::MessageBody, cookie::Key, http, test, web, App};
+
+        fn login_user() -> User {
+            User {
+                email: "test@example.com".to_owned(),
+                user_name: "".to_owned(),
+                avatar_url: None,
+                timezone: "UTC".to_owned(),
+                locale: "en_US".to_owned(),
+                registered_date: chrono::Utc::now(),
+                updated_date: chrono::Utc::now(),
+            }
+        }
+
+        fn succeeded_usecase(user: User) -> web::Data<Box<dyn AuthenticationUsecase>> {
+            let mut mock_usecase = MockAuthenticationUsecase::new();
+            mock_usecase.expect_login().returning(move |_| {
+                Ok(LoginResult {
+                    situation: LoginSituation::Succeeded,
+                    login_user: Some(user.clone()),
+                    description: None,
+                })
+            });
+            web::Data::new(Box::new(mock_usecase) as Box<dyn AuthenticationUsecase>)
+        }
+
+        fn session_middleware() -> SessionMiddleware<CookieSessionStore> {
+            SessionMiddleware::new(CookieSessionStore::default(), Key::from(&[0; 64]))
+        }
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let app = test::init_service(App::new().wrap(session_middleware()).service(me)).await;
+
+            let req = test::TestRequest::get().uri("/me").to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn ログイン中のユーザを返す() {
+            let expected = login_user();
+            let usecase = succeeded_usecase(expected.clone());
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(login)
+                    .service(me),
+            )
+            .await;
+
+            let login_req = test::TestRequest::post()
+                .uri("/login")
+                .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
+                    credential: "test".to_owned(),
+                })
+                .to_request();
+            let login_resp = test::call_service(&app, login_req).await;
+            let cookie = login_resp.response().cookies().next().unwrap().into_owned();
+
+            let req = test::TestRequest::get()
+                .uri("/me")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let user_from_response: User =
+                serde_json::from_slice(resp.into_body().try_into_bytes().unwrap().as_ref())
+                    .unwrap();
+            assert_eq!(expected, user_from_response);
+        }
+
+        #[actix_web::test]
+        async fn ログアウト後はステータス401を返す() {
+            let usecase = succeeded_usecase(login_user());
+            let mut mock_events = MockEventUsecase::new();
+            mock_events
+                .expect_session_ended()
+                .withf(|owner| owner == "test@example.com")
+                .times(1)
+                .returning(|_| Ok(()));
+            let events = web::Data::new(Box::new(mock_events) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(login)
+                    .service(logout)
+                    .service(me),
+            )
+            .await;
+
+            let login_req = test::TestRequest::post()
+                .uri("/login")
+                .set_json(&LoginRequest {
+                    provider: "google".to_owned(),
+                    credential: "test".to_owned(),
+                })
+                .to_request();
+            let login_resp = test::call_service(&app, login_req).await;
+            let cookie = login_resp.response().cookies().next().unwrap().into_owned();
+
+            let logout_req = test::TestRequest::post()
+                .uri("/logout")
+                .cookie(cookie)
+                .to_request();
+            let logout_resp = test::call_service(&app, logout_req).await;
+            assert_eq!(http::StatusCode::OK, logout_resp.status());
+            let cookie = logout_resp
+                .response()
+                .cookies()
+                .next()
+                .unwrap()
+                .into_owned();
+
+            let req = test::TestRequest::get()
+                .uri("/me")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+    }
 }
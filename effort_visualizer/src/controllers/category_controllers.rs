@@ -0,0 +1,201 @@
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use crate::dto::{CategoryRequest, CategoryResult, CategorySituation};
+use crate::usecases::category_usecase::CategoryUsecase;
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+fn to_response(result: CategoryResult) -> HttpResponse {
+    match result.situation {
+        CategorySituation::Succeeded => HttpResponse::Ok().json(result),
+        CategorySituation::NotFound => HttpResponse::NotFound().json(result),
+        CategorySituation::NameAlreadyUsed => HttpResponse::Conflict().json(result),
+        CategorySituation::NameIsEmpty | CategorySituation::InvalidColour => {
+            HttpResponse::BadRequest().json(result)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "Categories of the current user.", body = [Category]),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/categories")]
+pub async fn get_categories(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn CategoryUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let categories = usecase
+        .get_categories(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(HttpResponse::Ok().json(categories))
+}
+
+#[utoipa::path(
+    post,
+    request_body = CategoryRequest,
+    responses(
+        (status = 201, description = "The category is created.", body = CategoryResult),
+        (status = 400, description = "The name is empty or the colour is invalid.", body = CategoryResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Another category has the name.", body = CategoryResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/categories")]
+pub async fn add_category(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn CategoryUsecase>>,
+    request: web::Json<CategoryRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .add_category(&user.email, &request)
+        .map_err(ApiError::from)
+        .await?;
+    match result.situation {
+        CategorySituation::Succeeded => Ok(HttpResponse::Created().json(result)),
+        _ => Ok(to_response(result)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    params(("id" = i64, Path, description = "Category id")),
+    request_body = CategoryRequest,
+    responses(
+        (status = 200, description = "The category is updated.", body = CategoryResult),
+        (status = 400, description = "The name is empty or the colour is invalid.", body = CategoryResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The category is not found.", body = CategoryResult),
+        (status = 409, description = "Another category has the name.", body = CategoryResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[put("/categories/{id}")]
+pub async fn update_category(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn CategoryUsecase>>,
+    id: web::Path<i64>,
+    request: web::Json<CategoryRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .update_category(&user.email, id.into_inner(), &request)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    delete,
+    params(("id" = i64, Path, description = "Category id")),
+    responses(
+        (status = 200, description = "The category and its goals are deleted. Its efforts are kept without a category.", body = CategoryResult),
+        (status = 400, description = "The id is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The category is not found.", body = CategoryResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/categories/{id}")]
+pub async fn delete_category(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn CategoryUsecase>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .delete_category(&user.email, id.into_inner())
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[cfg(test)]
+mod tests {
+    mod add_category {
+        use crate::add_category;
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::dto::{CategoryRequest, CategoryResult, CategorySituation};
+        use crate::usecases::category_usecase::{CategoryUsecase, MockCategoryUsecase};
+        use actix_web::{http, test, web, App};
+
+        fn category_request() -> CategoryRequest {
+            CategoryRequest {
+                name: "Study".to_owned(),
+                colour: "#40c463".to_owned(),
+                icon: None,
+            }
+        }
+
+        #[actix_web::test]
+        async fn 同じ名前のカテゴリがあるときステータス409を返す() {
+            let mut mock_usecase = MockCategoryUsecase::new();
+            mock_usecase
+                .expect_add_category()
+                .withf(|owner, request| owner == "test@example.com" && request.name == "Study")
+                .returning(|_, _| {
+                    Ok(CategoryResult {
+                        situation: CategorySituation::NameAlreadyUsed,
+                        category: None,
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn CategoryUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(add_category),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/categories")
+                .cookie(cookie)
+                .set_json(category_request())
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::CONFLICT, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockCategoryUsecase::new()) as Box<dyn CategoryUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(add_category),
+            )
+            .await;
+
+            let req = test::TestRequest::post()
+                .uri("/categories")
+                .set_json(category_request())
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+    }
+}
@@ -0,0 +1,818 @@
+use std::io;
+
+use super::errors::ApiError;
+use super::event_controllers::log_push_failure;
+use super::extractors::AuthenticatedUser;
+use crate::domain::efforts::EffortFilter;
+use crate::dto::{
+    ColumnMapping, EffortExportQuery, EffortFilterQuery, EffortRequest, EffortResult,
+    EffortSituation, ImportQuery, ImportRequest, ImportSituation,
+};
+use crate::helpers::{correlation_id, effort_export::EffortExportWriter};
+use crate::usecases::{effort_usecase::EffortUsecase, event_usecase::EventUsecase};
+use actix_multipart::form::{bytes::Bytes, json::Json as MultipartJson, MultipartForm};
+use actix_web::{
+    delete, get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    post, put,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::{StreamExt, TryFutureExt};
+use tracing::error;
+
+fn to_response(result: EffortResult) -> HttpResponse {
+    match result.situation {
+        EffortSituation::Succeeded => HttpResponse::Ok().json(result),
+        EffortSituation::NotFound => HttpResponse::NotFound().json(result),
+        _ => HttpResponse::BadRequest().json(result),
+    }
+}
+
+#[utoipa::path(
+    get,
+    params(EffortFilterQuery),
+    responses(
+        (status = 200, description = "Efforts of the current user.", body = [Effort]),
+        (status = 400, description = "The filter is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/efforts")]
+pub async fn get_efforts(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn EffortUsecase>>,
+    query: web::Query<EffortFilterQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let filter = EffortFilter::parse(
+        query.category.as_deref(),
+        query.tag.as_deref(),
+        query.match_mode,
+    )
+    .map_err(ApiError::InvalidRequest)?;
+    let efforts = usecase
+        .get_efforts(&user.email, &filter)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(HttpResponse::Ok().json(efforts))
+}
+
+#[utoipa::path(
+    get,
+    params(("id" = i64, Path, description = "Effort id")),
+    responses(
+        (status = 200, description = "The effort.", body = EffortResult),
+        (status = 400, description = "The id is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The effort is not found.", body = EffortResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/efforts/{id}")]
+pub async fn get_effort(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn EffortUsecase>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .get_effort(&user.email, id.into_inner())
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    post,
+    request_body = EffortRequest,
+    responses(
+        (status = 201, description = "The effort is recorded.", body = EffortResult),
+        (status = 400, description = "The request is invalid.", body = EffortResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/efforts")]
+pub async fn add_effort(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn EffortUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+    effort_info: web::Json<EffortRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .add_effort(&user.email, &effort_info)
+        .map_err(ApiError::from)
+        .await?;
+    if let Some(effort) = &result.effort {
+        log_push_failure(
+            events
+                .efforts_created(&user.email, &user.timezone, std::slice::from_ref(effort))
+                .await,
+        );
+    }
+    match result.situation {
+        EffortSituation::Succeeded => Ok(HttpResponse::Created().json(result)),
+        _ => Ok(to_response(result)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    params(("id" = i64, Path, description = "Effort id")),
+    request_body = EffortRequest,
+    responses(
+        (status = 200, description = "The effort is updated.", body = EffortResult),
+        (status = 400, description = "The request is invalid.", body = EffortResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The effort is not found.", body = EffortResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[put("/efforts/{id}")]
+pub async fn update_effort(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn EffortUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+    id: web::Path<i64>,
+    effort_info: web::Json<EffortRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .update_effort(&user.email, id.into_inner(), &effort_info)
+        .map_err(ApiError::from)
+        .await?;
+    if let Some(effort) = &result.effort {
+        log_push_failure(
+            events
+                .effort_updated(&user.email, &user.timezone, effort)
+                .await,
+        );
+    }
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    delete,
+    params(("id" = i64, Path, description = "Effort id")),
+    responses(
+        (status = 200, description = "The effort is deleted.", body = EffortResult),
+        (status = 400, description = "The id is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The effort is not found.", body = EffortResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/efforts/{id}")]
+pub async fn delete_effort(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn EffortUsecase>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .delete_effort(&user.email, id.into_inner())
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[derive(MultipartForm)]
+pub struct ImportForm {
+    file: Bytes,
+    mapping: Option<MultipartJson<ColumnMapping>>,
+}
+
+#[utoipa::path(
+    post,
+    params(ImportQuery),
+    request_body(content = ImportUpload, content_type = "multipart/form-data"),
+    responses(
+        (status = 200, description = "The import report, or the preview of a dry run.", body = ImportResult),
+        (status = 400, description = "The mapping is missing or the file can't be read.", body = ImportResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/efforts/import")]
+pub async fn import_efforts(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn EffortUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+    query: web::Query<ImportQuery>,
+    form: MultipartForm<ImportForm>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let form = form.into_inner();
+    let request = ImportRequest {
+        data: form.file.data.to_vec(),
+        preset: query.preset,
+        mapping: form.mapping.map(|mapping| mapping.into_inner()),
+        dry_run: query.dry_run.unwrap_or(false),
+    };
+    let result = usecase
+        .import_efforts(&user.email, &user.timezone, &request)
+        .map_err(ApiError::from)
+        .await?;
+    if !result.stored.is_empty() {
+        log_push_failure(
+            events
+                .efforts_created(&user.email, &user.timezone, &result.stored)
+                .await,
+        );
+    }
+    match result.situation {
+        ImportSituation::Succeeded => Ok(HttpResponse::Ok().json(result)),
+        ImportSituation::InvalidMapping | ImportSituation::InvalidFile => {
+            Ok(HttpResponse::BadRequest().json(result))
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    params(EffortExportQuery),
+    responses(
+        (status = 200, description = "The efforts of the current user as CSV, JSON Lines or iCalendar.", content_type = "text/csv", body = String),
+        (status = 400, description = "The format or the period is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/efforts/export")]
+pub async fn export_efforts(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn EffortUsecase>>,
+    query: web::Query<EffortExportQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if let (Some(from), Some(to)) = (query.from, query.to) {
+        if from > to {
+            return Err(ApiError::InvalidRequest("`from` is after `to`.".to_owned()).into());
+        }
+    }
+    let efforts = usecase
+        .export_efforts(&user.email, &user.timezone, &query)
+        .map_err(ApiError::from)
+        .await?;
+
+    // Rows are read while the response is sent, so a failure can only abort it.
+    let correlation_id = correlation_id::current();
+    let body = efforts.map(move |chunk| {
+        chunk.map(web::Bytes::from).map_err(|e| {
+            error!("[{}] The export failed: {:?}", correlation_id, e);
+            io::Error::other("The export failed.")
+        })
+    });
+    Ok(HttpResponse::Ok()
+        .content_type(EffortExportWriter::content_type(query.format))
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(
+                EffortExportWriter::file_name(query.format).to_owned(),
+            )],
+        })
+        .streaming(body))
+}
+
+#[cfg(test)]
+mod tests {
+    mod get_efforts {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::efforts::{Effort, EffortFilter, FilterMatch};
+        use crate::get_efforts;
+        use crate::usecases::effort_usecase::{EffortUsecase, MockEffortUsecase};
+        use actix_web::{body::MessageBody, http, test, web, App};
+        use chrono::Utc;
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let mock_usecase = MockEffortUsecase::new();
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(get_efforts),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/efforts").to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn ログイン中のユーザの努力記録一覧を返す() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            let efforts = vec![Effort {
+                id: 1,
+                owner: "test@example.com".to_owned(),
+                title: "Rust".to_owned(),
+                duration_seconds: 3600,
+                started_at: Utc::now(),
+                ended_at: Utc::now(),
+                notes: None,
+                category_id: Some(2),
+                tags: vec!["books".to_owned(), "rust".to_owned()],
+                pomodoro: false,
+            }];
+            let expected = efforts.clone();
+            mock_usecase
+                .expect_get_efforts()
+                .withf(|owner, filter| {
+                    owner == "test@example.com"
+                        && *filter
+                            == EffortFilter {
+                                category_ids: vec![2],
+                                tags: vec!["rust".to_owned()],
+                                match_mode: FilterMatch::Any,
+                            }
+                })
+                .returning(move |_, _| Ok(efforts.clone()));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_efforts),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/efforts?category=2&tag=rust&match=any")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let efforts_from_response: Vec<Effort> =
+                serde_json::from_slice(resp.into_body().try_into_bytes().unwrap().as_ref())
+                    .unwrap();
+            assert_eq!(expected, efforts_from_response);
+        }
+    }
+
+    mod add_effort {
+        use crate::add_effort;
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::efforts::Effort;
+        use crate::dto::{EffortRequest, EffortResult, EffortSituation};
+        use crate::usecases::effort_usecase::{EffortUsecase, MockEffortUsecase};
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use actix_web::{http, test, web, App};
+        use chrono::Utc;
+
+        fn effort_request() -> EffortRequest {
+            EffortRequest {
+                title: "".to_owned(),
+                duration_seconds: 0,
+                started_at: Utc::now(),
+                ended_at: Utc::now(),
+                notes: None,
+                category_id: None,
+                tags: vec![],
+            }
+        }
+
+        #[actix_web::test]
+        async fn 登録成功時ステータス201を返す() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            mock_usecase
+                .expect_add_effort()
+                .returning(|owner, request| {
+                    Ok(EffortResult {
+                        situation: EffortSituation::Succeeded,
+                        effort: Some(Effort {
+                            id: 1,
+                            owner: owner.to_owned(),
+                            title: "Rust".to_owned(),
+                            duration_seconds: request.duration_seconds,
+                            started_at: request.started_at,
+                            ended_at: request.ended_at,
+                            notes: None,
+                            category_id: None,
+                            tags: vec![],
+                            pomodoro: false,
+                        }),
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+            let mut mock_events = MockEventUsecase::new();
+            mock_events
+                .expect_efforts_created()
+                .withf(|owner, timezone, efforts| {
+                    owner == "test@example.com" && timezone == "UTC" && efforts[0].id == 1
+                })
+                .times(1)
+                .returning(|_, _, _| Ok(()));
+            let events = web::Data::new(Box::new(mock_events) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(add_effort),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/efforts")
+                .cookie(cookie)
+                .set_json(effort_request())
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::CREATED, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn タイトルが空のときステータス400を返す() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            mock_usecase.expect_add_effort().returning(|_, _| {
+                Ok(EffortResult {
+                    situation: EffortSituation::TitleIsEmpty,
+                    effort: None,
+                    description: None,
+                })
+            });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+            let events = web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(add_effort),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/efforts")
+                .cookie(cookie)
+                .set_json(effort_request())
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        }
+    }
+
+    mod delete_effort {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::delete_effort;
+        use crate::dto::{EffortResult, EffortSituation};
+        use crate::usecases::effort_usecase::{EffortUsecase, MockEffortUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 記録が存在しないときステータス404を返す() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            mock_usecase.expect_delete_effort().returning(|_, _| {
+                Ok(EffortResult {
+                    situation: EffortSituation::NotFound,
+                    effort: None,
+                    description: None,
+                })
+            });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(delete_effort),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::delete()
+                .uri("/efforts/1")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NOT_FOUND, resp.status());
+        }
+    }
+    mod import_efforts {
+        use crate::controllers::errors::configure_extractors;
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::efforts::Effort;
+        use crate::dto::{ImportPreset, ImportReport, ImportResult, ImportSituation};
+        use crate::import_efforts;
+        use crate::usecases::effort_usecase::{EffortUsecase, MockEffortUsecase};
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use actix_web::{http, test, web, App};
+        use chrono::{Duration, Utc};
+
+        const BOUNDARY: &str = "boundary";
+
+        fn multipart_body(file: &str) -> String {
+            format!(
+                "--{BOUNDARY}\r\n\
+                Content-Disposition: form-data; name=\"file\"; filename=\"toggl.csv\"\r\n\
+                Content-Type: text/csv\r\n\r\n\
+                {file}\r\n\
+                --{BOUNDARY}--\r\n"
+            )
+        }
+
+        #[actix_web::test]
+        async fn アップロードしたファイルをプリセットで取り込む() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            mock_usecase
+                .expect_import_efforts()
+                .withf(|owner, timezone, request| {
+                    owner == "test@example.com"
+                        && timezone == "UTC"
+                        && request.preset == Some(ImportPreset::Toggl)
+                        && request.dry_run
+                        && request.data == b"Description,Start date"
+                })
+                .returning(|_, _, request| {
+                    Ok(ImportResult {
+                        situation: ImportSituation::Succeeded,
+                        report: Some(ImportReport {
+                            dry_run: request.dry_run,
+                            imported: 0,
+                            duplicates: 0,
+                            invalid: 0,
+                            rows: vec![],
+                        }),
+                        description: None,
+                        stored: vec![],
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+            let events = web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .configure(configure_extractors)
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(import_efforts),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/efforts/import?preset=toggl&dry_run=true")
+                .cookie(cookie)
+                .insert_header((
+                    http::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={BOUNDARY}"),
+                ))
+                .set_payload(multipart_body("Description,Start date"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn 取り込んだ努力をクライアントに知らせる() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            mock_usecase
+                .expect_import_efforts()
+                .returning(|owner, _, request| {
+                    let started_at = Utc::now() - Duration::hours(1);
+                    Ok(ImportResult {
+                        situation: ImportSituation::Succeeded,
+                        report: Some(ImportReport {
+                            dry_run: request.dry_run,
+                            imported: 1,
+                            duplicates: 0,
+                            invalid: 0,
+                            rows: vec![],
+                        }),
+                        description: None,
+                        stored: vec![Effort {
+                            id: 7,
+                            owner: owner.to_owned(),
+                            title: "Rust".to_owned(),
+                            duration_seconds: 3600,
+                            started_at,
+                            ended_at: started_at + Duration::hours(1),
+                            notes: None,
+                            category_id: None,
+                            tags: vec![],
+                            pomodoro: false,
+                        }],
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+            let mut mock_events = MockEventUsecase::new();
+            mock_events
+                .expect_efforts_created()
+                .withf(|owner, timezone, efforts| {
+                    owner == "test@example.com"
+                        && timezone == "UTC"
+                        && efforts.len() == 1
+                        && efforts[0].id == 7
+                })
+                .times(1)
+                .returning(|_, _, _| Ok(()));
+            let events = web::Data::new(Box::new(mock_events) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .configure(configure_extractors)
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(import_efforts),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/efforts/import?preset=toggl")
+                .cookie(cookie)
+                .insert_header((
+                    http::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={BOUNDARY}"),
+                ))
+                .set_payload(multipart_body("Description,Start date"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn ファイルがないときステータス400を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockEffortUsecase::new()) as Box<dyn EffortUsecase>);
+            let events = web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .configure(configure_extractors)
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(import_efforts),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/efforts/import?preset=toggl")
+                .cookie(cookie)
+                .insert_header((
+                    http::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={BOUNDARY}"),
+                ))
+                .set_payload(format!("--{BOUNDARY}--\r\n"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        }
+    }
+    mod export_efforts {
+        use crate::controllers::errors::configure_extractors;
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::dto::EffortExportFormat;
+        use crate::export_efforts;
+        use crate::usecases::effort_usecase::{EffortUsecase, MockEffortUsecase};
+        use actix_web::{body, http, test, web, App};
+        use anyhow::anyhow;
+        use chrono::NaiveDate;
+        use futures::{stream, StreamExt};
+
+        #[actix_web::test]
+        async fn 指定した形式で努力を書き出す() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            mock_usecase
+                .expect_export_efforts()
+                .withf(|owner, timezone, query| {
+                    owner == "test@example.com"
+                        && timezone == "UTC"
+                        && query.format == EffortExportFormat::Ics
+                        && query.from == NaiveDate::from_ymd_opt(2023, 5, 1)
+                        && query.to.is_none()
+                })
+                .returning(|_, _, _| {
+                    Ok(stream::iter(vec![
+                        Ok(b"BEGIN:VCALENDAR\r\n".to_vec()),
+                        Ok(b"END:VCALENDAR\r\n".to_vec()),
+                    ])
+                    .boxed())
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .configure(configure_extractors)
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(export_efforts),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/efforts/export?format=ics&from=2023-05-01")
+                .cookie(cookie)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            assert_eq!(
+                "text/calendar; charset=utf-8",
+                resp.headers().get(http::header::CONTENT_TYPE).unwrap()
+            );
+            let body = body::to_bytes(resp.into_body()).await.unwrap();
+            assert_eq!(&b"BEGIN:VCALENDAR\r\nEND:VCALENDAR\r\n"[..], body);
+        }
+
+        #[actix_web::test]
+        async fn 読み出しに失敗したときレスポンスを中断する() {
+            let mut mock_usecase = MockEffortUsecase::new();
+            mock_usecase.expect_export_efforts().returning(|_, _, _| {
+                Ok(stream::iter(vec![
+                    Ok(b"id,title\n".to_vec()),
+                    Err(anyhow!("The database went away.")),
+                ])
+                .boxed())
+            });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EffortUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .configure(configure_extractors)
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(export_efforts),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/efforts/export?format=csv")
+                .cookie(cookie)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            assert!(body::to_bytes(resp.into_body()).await.is_err());
+        }
+
+        #[actix_web::test]
+        async fn 期間が逆転しているときステータス400を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockEffortUsecase::new()) as Box<dyn EffortUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .configure(configure_extractors)
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(export_efforts),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/efforts/export?format=jsonl&from=2023-05-02&to=2023-05-01")
+                .cookie(cookie)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        }
+    }
+}
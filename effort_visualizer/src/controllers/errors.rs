@@ -1,23 +1,204 @@
-use actix_web::{body::BoxBody, HttpResponse, ResponseError};
+use actix_multipart::form::{json::JsonConfig as MultipartJsonConfig, MultipartFormConfig};
+use actix_web::{
+    body::BoxBody,
+    http::{header::HeaderName, StatusCode},
+    web, HttpResponse, ResponseError,
+};
 
-use derive_more::{Display, From};
+use derive_more::Display;
 
 use serde::ser::StdError;
-use tracing::log::error;
+use tracing::log::{error, warn};
 
-#[derive(Debug, Display, From)]
-#[display(fmt = "{}", _0)]
-pub struct ApiError(anyhow::Error);
+use crate::dto::{ErrorCode, ProblemDetails};
+use crate::helpers::correlation_id::{self, CORRELATION_ID_HEADER};
+use crate::repositories::database::PoolExhausted;
+
+const PROBLEM_JSON: &str = "application/problem+json";
+/// The largest multipart form accepted, e.g. an uploaded CSV file.
+const MAX_UPLOAD_BYTES: usize = 10 * 1024 * 1024;
+
+/// Errors returned by handlers. Each one is rendered as an RFC 7807 problem.
+#[derive(Debug, Display)]
+pub enum ApiError {
+    #[display(fmt = "Not logged in.")]
+    Unauthorized,
+    #[display(fmt = "{}", _0)]
+    NotFound(String),
+    #[display(fmt = "{}", _0)]
+    InvalidRequest(String),
+    #[display(fmt = "{:#}", _0)]
+    DatabaseUnavailable(anyhow::Error),
+    #[display(fmt = "{:#}", _0)]
+    Internal(anyhow::Error),
+    /// The request is valid but the server is not configured to serve it.
+    #[display(fmt = "{}", _0)]
+    Unsupported(String),
+}
+
+impl ApiError {
+    pub fn code(&self) -> ErrorCode {
+        match self {
+            ApiError::Unauthorized => ErrorCode::Unauthorized,
+            ApiError::NotFound(_) => ErrorCode::NotFound,
+            ApiError::InvalidRequest(_) => ErrorCode::InvalidRequest,
+            ApiError::DatabaseUnavailable(_) => ErrorCode::DatabaseUnavailable,
+            ApiError::Internal(_) => ErrorCode::InternalError,
+            ApiError::Unsupported(_) => ErrorCode::Unsupported,
+        }
+    }
+
+    fn title(&self) -> &'static str {
+        match self {
+            ApiError::Unauthorized => "Not logged in.",
+            ApiError::NotFound(_) => "The resource is not found.",
+            ApiError::InvalidRequest(_) => "The request is invalid.",
+            ApiError::DatabaseUnavailable(_) => "The database is unavailable.",
+            ApiError::Internal(_) => "Internal error.",
+            ApiError::Unsupported(_) => "The server does not support the request.",
+        }
+    }
+
+    /// Details of internal failures stay in the log rather than in the response.
+    fn detail(&self) -> Option<String> {
+        match self {
+            ApiError::NotFound(detail)
+            | ApiError::InvalidRequest(detail)
+            | ApiError::Unsupported(detail) => Some(detail.clone()),
+            _ => None,
+        }
+    }
+}
+
+impl From<anyhow::Error> for ApiError {
+    fn from(e: anyhow::Error) -> Self {
+        if e.chain().any(|e| e.is::<PoolExhausted>()) {
+            ApiError::DatabaseUnavailable(e)
+        } else {
+            ApiError::Internal(e)
+        }
+    }
+}
 
 impl StdError for ApiError {
     fn source(&self) -> Option<&(dyn StdError + 'static)> {
-        Some(self.0.as_ref())
+        match self {
+            ApiError::DatabaseUnavailable(e) | ApiError::Internal(e) => Some(e.as_ref()),
+            _ => None,
+        }
     }
 }
 
 impl ResponseError for ApiError {
+    fn status_code(&self) -> StatusCode {
+        match self {
+            ApiError::Unauthorized => StatusCode::UNAUTHORIZED,
+            ApiError::NotFound(_) => StatusCode::NOT_FOUND,
+            ApiError::InvalidRequest(_) => StatusCode::BAD_REQUEST,
+            ApiError::DatabaseUnavailable(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ApiError::Internal(_) => StatusCode::INTERNAL_SERVER_ERROR,
+            ApiError::Unsupported(_) => StatusCode::NOT_IMPLEMENTED,
+        }
+    }
+
     fn error_response(&self) -> HttpResponse<BoxBody> {
-        error!("{}", &self);
-        HttpResponse::new(self.status_code())
+        let status = self.status_code();
+        let code = self.code();
+        let correlation_id = correlation_id::current();
+        // A server configured without a feature is not failing.
+        if status.is_server_error() && !matches!(self, ApiError::Unsupported(_)) {
+            error!("[{}] {:?}: {}", correlation_id, code, &self);
+        } else {
+            warn!("[{}] {:?}: {}", correlation_id, code, &self);
+        }
+        let problem = ProblemDetails {
+            problem_type: format!("/problems/{}", code.as_str()),
+            title: self.title().to_owned(),
+            status: status.as_u16(),
+            detail: self.detail(),
+            code,
+            correlation_id: correlation_id.clone(),
+        };
+        HttpResponse::build(status)
+            .content_type(PROBLEM_JSON)
+            .insert_header((
+                HeaderName::from_static(CORRELATION_ID_HEADER),
+                correlation_id,
+            ))
+            .json(problem)
+    }
+}
+
+/// Makes malformed paths, queries, JSON bodies and multipart forms fail with an `invalid_request` problem.
+pub fn configure_extractors(cfg: &mut web::ServiceConfig) {
+    cfg.app_data(
+        web::JsonConfig::default()
+            .error_handler(|e, _| ApiError::InvalidRequest(e.to_string()).into()),
+    )
+    .app_data(
+        web::QueryConfig::default()
+            .error_handler(|e, _| ApiError::InvalidRequest(e.to_string()).into()),
+    )
+    .app_data(
+        web::PathConfig::default()
+            .error_handler(|e, _| ApiError::InvalidRequest(e.to_string()).into()),
+    )
+    .app_data(
+        MultipartFormConfig::default()
+            .memory_limit(MAX_UPLOAD_BYTES)
+            .total_limit(MAX_UPLOAD_BYTES)
+            .error_handler(|e, _| ApiError::InvalidRequest(e.to_string()).into()),
+    )
+    // Browsers send JSON parts of a form without a content type.
+    .app_data(MultipartJsonConfig::default().validate_content_type(false));
+}
+
+/// Answers requests no route matches.
+pub async fn route_not_found() -> Result<HttpResponse, ApiError> {
+    Err(ApiError::NotFound(
+        "No route matches the request.".to_owned(),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::ApiError;
+    use crate::dto::{ErrorCode, ProblemDetails};
+    use crate::repositories::database::PoolExhausted;
+    use actix_web::{body::to_bytes, http::StatusCode, ResponseError};
+
+    #[test]
+    fn コネクションプール枯渇時ステータス503を返す() {
+        let error =
+            ApiError::from(anyhow::Error::new(PoolExhausted).context("Failed to find user."));
+
+        assert_eq!(StatusCode::SERVICE_UNAVAILABLE, error.status_code());
+    }
+
+    #[test]
+    fn その他のエラーはステータス500を返す() {
+        let error = ApiError::from(anyhow::anyhow!("Unexpected."));
+
+        assert_eq!(StatusCode::INTERNAL_SERVER_ERROR, error.status_code());
+    }
+
+    #[actix_web::test]
+    async fn problem_jsonを返し内部エラーの詳細は含めない() {
+        let error = ApiError::from(anyhow::anyhow!("password=secret"));
+
+        let resp = error.error_response();
+
+        assert_eq!(
+            "application/problem+json",
+            resp.headers().get("content-type").unwrap()
+        );
+        let correlation_id = resp.headers().get("x-correlation-id").unwrap().to_owned();
+        let body = to_bytes(resp.into_body()).await.unwrap();
+        let problem: ProblemDetails = serde_json::from_slice(&body).unwrap();
+        assert_eq!(ErrorCode::InternalError, problem.code);
+        assert_eq!("/problems/internal_error", problem.problem_type);
+        assert_eq!(500, problem.status);
+        assert_eq!(None, problem.detail);
+        assert_eq!(correlation_id, problem.correlation_id.as_str());
     }
 }
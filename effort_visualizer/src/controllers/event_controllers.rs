@@ -0,0 +1,779 @@
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use super::timer_controllers::push;
+use crate::domain::events::{EventKind, MissedEvents, UserEvent};
+use crate::domain::timers::Timer;
+use crate::domain::users::User;
+use crate::dto::TimerState;
+use crate::helpers::{correlation_id, event_hub::EventHub};
+use crate::usecases::{event_usecase::EventUsecase, timer_usecase::TimerUsecase};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective},
+    web::{self, Bytes, Data},
+    HttpRequest, HttpResponse,
+};
+use actix_ws::{CloseCode, CloseReason, Message, MessageStream, Session};
+use anyhow::Result;
+use chrono::Utc;
+use futures::{channel::mpsc, SinkExt, StreamExt, TryFutureExt};
+use tokio::sync::broadcast::{error::RecvError, Receiver};
+use tracing::error;
+
+const HEARTBEAT_INTERVAL: Duration = Duration::from_secs(5);
+/// A client that has not answered for this long is disconnected.
+const CLIENT_TIMEOUT: Duration = Duration::from_secs(10);
+const TICK_INTERVAL: Duration = Duration::from_secs(1);
+/// Comments sent this often keep proxies from closing an idle event stream.
+const KEEP_ALIVE_INTERVAL: Duration = Duration::from_secs(15);
+/// Milliseconds an event stream client waits before reconnecting.
+const RETRY_MILLISECONDS: u64 = 5000;
+const LAST_EVENT_ID: &str = "Last-Event-ID";
+
+/// Logs a failure to push events. The change they tell about is stored already, so the
+/// request still succeeds.
+pub(super) fn log_push_failure(result: Result<()>) {
+    if let Err(e) = result {
+        error!(
+            "[{}] Failed to push events: {:?}",
+            correlation_id::current(),
+            e
+        );
+    }
+}
+
+/// Follows the timer through the `timer.tick` events pushed to a client, to tick it every
+/// second while it is running.
+#[derive(Default)]
+struct TimerTicks {
+    timer: Option<Timer>,
+}
+
+impl TimerTicks {
+    fn observe(&mut self, event: &UserEvent) {
+        if event.kind == EventKind::TimerTick {
+            self.timer = serde_json::from_value::<Option<TimerState>>(event.data.clone())
+                .ok()
+                .flatten()
+                .map(|state| state.timer);
+        }
+    }
+
+    fn is_running(&self) -> bool {
+        self.timer.as_ref().is_some_and(Timer::is_running)
+    }
+
+    fn tick(&self, owner: &str) -> Option<UserEvent> {
+        let state = self
+            .timer
+            .clone()
+            .map(|timer| TimerState::new(timer, Utc::now()));
+        UserEvent::new(owner, EventKind::TimerTick, &state).ok()
+    }
+}
+
+/// The `timer.tick` a client gets first, so that it starts from the current state of the
+/// owner's timer. Pomodoros completed meanwhile are recorded and pushed as on `GET /timer`.
+async fn current_timer(
+    user: &User,
+    timers: &dyn TimerUsecase,
+    events: &dyn EventUsecase,
+) -> Result<UserEvent, ApiError> {
+    let result = timers
+        .get_timer(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    if !result.efforts.is_empty() {
+        push(user, events, &result).await;
+    }
+    UserEvent::new(&user.email, EventKind::TimerTick, &result.timer)
+        .map_err(|e| ApiError::from(anyhow::Error::from(e)))
+}
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 101, description = "Switched to a WebSocket, over which the events of the current user are pushed as JSON text messages. The state of the timer comes first, and a running timer is ticked every second. The socket is closed after a `session.ended` event, and with the code 1013 when the client fell behind the events and has to fetch its data again. Clients still logged in connect again.", body = UserEvent),
+        (status = 400, description = "The request is not a WebSocket handshake.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/events/ws")]
+pub async fn connect_events(
+    user: AuthenticatedUser,
+    hub: Data<EventHub>,
+    usecase: Data<Box<dyn EventUsecase>>,
+    timers: Data<Box<dyn TimerUsecase>>,
+    req: HttpRequest,
+    body: web::Payload,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (response, session, messages) = actix_ws::handle(&req, body)?;
+    // Subscribed before the timer is read, so that no change falls in between.
+    let events = hub.subscribe(&user.email);
+    let timer = current_timer(&user, timers.as_ref().as_ref(), usecase.as_ref().as_ref()).await?;
+    actix_web::rt::spawn(relay(
+        user.email.to_owned(),
+        session,
+        messages,
+        events,
+        timer,
+    ));
+    Ok(response)
+}
+
+/// Pushes the events of `owner` to a client until either side goes away, starting with the
+/// state of the `timer`.
+async fn relay(
+    owner: String,
+    mut session: Session,
+    mut messages: MessageStream,
+    mut events: Receiver<Arc<UserEvent>>,
+    timer: UserEvent,
+) {
+    let mut last_heard = Instant::now();
+    let mut heartbeat = tokio::time::interval(HEARTBEAT_INTERVAL);
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+    let mut ticks = TimerTicks::default();
+    ticks.observe(&timer);
+    if send(&mut session, &timer).await.is_err() {
+        return;
+    }
+    let reason = loop {
+        let sent = tokio::select! {
+            message = messages.recv() => match message {
+                Some(Ok(Message::Ping(bytes))) => {
+                    last_heard = Instant::now();
+                    session.pong(&bytes).await
+                }
+                Some(Ok(Message::Close(reason))) => break reason,
+                // Clients only listen, so anything else just shows they are there.
+                Some(Ok(_)) => {
+                    last_heard = Instant::now();
+                    Ok(())
+                }
+                Some(Err(_)) | None => break None,
+            },
+            event = events.recv() => match event {
+                Ok(event) if event.kind == EventKind::SessionEnded => {
+                    if send(&mut session, &event).await.is_err() {
+                        return;
+                    }
+                    break Some(CloseReason {
+                        code: CloseCode::Normal,
+                        description: Some("A session ended.".to_owned()),
+                    });
+                }
+                Ok(event) => {
+                    ticks.observe(&event);
+                    send(&mut session, &event).await
+                }
+                // Events are not logged for WebSockets, so the client catches up by fetching.
+                Err(RecvError::Lagged(_)) => {
+                    break Some(CloseReason {
+                        code: CloseCode::Again,
+                        description: Some("Fell behind the events.".to_owned()),
+                    });
+                }
+                Err(RecvError::Closed) => break None,
+            },
+            _ = heartbeat.tick() => {
+                if last_heard.elapsed() > CLIENT_TIMEOUT {
+                    break None;
+                }
+                session.ping(b"").await
+            }
+            _ = tick.tick(), if ticks.is_running() => match ticks.tick(&owner) {
+                Some(event) => send(&mut session, &event).await,
+                None => Ok(()),
+            },
+        };
+        if sent.is_err() {
+            return;
+        }
+    };
+    let _ = session.close(reason).await;
+}
+
+async fn send(session: &mut Session, event: &UserEvent) -> Result<(), actix_ws::Closed> {
+    match serde_json::to_string(event) {
+        Ok(message) => session.text(message).await,
+        Err(_) => Ok(()),
+    }
+}
+
+#[utoipa::path(
+    get,
+    params(
+        ("Last-Event-ID" = Option<i64>, Header, description = "Id of the last event received. The events logged after it are sent first. When some of them are no longer logged, a `reset` event is sent instead, after which the client fetches its data again.")
+    ),
+    responses(
+        (status = 200, description = "A stream of Server-Sent Events carrying the events of the current user, named by their type and with JSON data. Logged events carry their id. The state of the timer follows the missed events, and a running timer is ticked every second. When the client fell behind events that are not logged, a `reset` event is sent. The stream ends after a `session.ended` event, or when missed events cannot be read, and clients still logged in connect again.", body = UserEvent, content_type = "text/event-stream"),
+        (status = 400, description = "`Last-Event-ID` is not an event id.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/events/stream")]
+pub async fn stream_events(
+    user: AuthenticatedUser,
+    hub: Data<EventHub>,
+    usecase: Data<Box<dyn EventUsecase>>,
+    timers: Data<Box<dyn TimerUsecase>>,
+    req: HttpRequest,
+) -> Result<HttpResponse, actix_web::Error> {
+    let last_event_id = req
+        .headers()
+        .get(LAST_EVENT_ID)
+        .map(|value| {
+            value
+                .to_str()
+                .ok()
+                .and_then(|value| value.trim().parse::<i64>().ok())
+                .ok_or_else(|| {
+                    ApiError::InvalidRequest(format!("`{LAST_EVENT_ID}` must be an event id."))
+                })
+        })
+        .transpose()?;
+    // Subscribed before the log is read, so that no event falls in between.
+    let events = hub.subscribe(&user.email);
+    let missed = match last_event_id {
+        Some(after) => {
+            usecase
+                .get_events_after(&user.email, after)
+                .map_err(ApiError::from)
+                .await?
+        }
+        None => MissedEvents::Logged(Vec::new()),
+    };
+    let timer = current_timer(&user, timers.as_ref().as_ref(), usecase.as_ref().as_ref()).await?;
+    let (sender, receiver) = mpsc::channel(16);
+    actix_web::rt::spawn(stream(
+        user.email.to_owned(),
+        usecase,
+        events,
+        missed,
+        timer,
+        last_event_id,
+        sender,
+    ));
+    Ok(HttpResponse::Ok()
+        .content_type("text/event-stream")
+        .insert_header(CacheControl(vec![CacheDirective::NoCache]))
+        .streaming(receiver.map(Ok::<_, actix_web::Error>)))
+}
+
+/// Writes the events of `owner` to an event stream until the client goes away, starting with
+/// the `missed` ones after `last_event_id` and the state of the `timer`.
+async fn stream(
+    owner: String,
+    usecase: Data<Box<dyn EventUsecase>>,
+    mut events: Receiver<Arc<UserEvent>>,
+    missed: MissedEvents,
+    timer: UserEvent,
+    mut last_event_id: Option<i64>,
+    mut sender: mpsc::Sender<Bytes>,
+) {
+    let mut keep_alive = tokio::time::interval(KEEP_ALIVE_INTERVAL);
+    let mut tick = tokio::time::interval(TICK_INTERVAL);
+    let mut ticks = TimerTicks::default();
+    let retry = Bytes::from(format!("retry: {RETRY_MILLISECONDS}\n\n"));
+    if sender.send(retry).await.is_err() {
+        return;
+    }
+    let mut pending = Vec::new();
+    let mut missed = Some(missed);
+    let mut timer = Some(timer);
+    loop {
+        match missed.take() {
+            Some(MissedEvents::Logged(logged)) => pending = logged,
+            Some(MissedEvents::Dropped { last_id }) => {
+                last_event_id = Some(last_id);
+                if sender.send(reset_message(Some(last_id))).await.is_err() {
+                    return;
+                }
+            }
+            None => {}
+        }
+        // Sent after the missed events, which may carry older states of the timer.
+        pending.extend(timer.take());
+        for event in pending.drain(..) {
+            // Events read from the log may come again from the hub.
+            if let (Some(id), Some(last)) = (event.id, last_event_id) {
+                if id <= last {
+                    continue;
+                }
+            }
+            last_event_id = event.id.or(last_event_id);
+            ticks.observe(&event);
+            if sender.send(to_message(&event)).await.is_err() {
+                return;
+            }
+        }
+        let sent = tokio::select! {
+            event = events.recv() => match event {
+                Ok(event) if event.kind == EventKind::SessionEnded => {
+                    let _ = sender.send(to_message(&event)).await;
+                    return;
+                }
+                Ok(event) => {
+                    pending.push(event.as_ref().clone());
+                    Ok(())
+                }
+                // Catch up from the log when this stream fell behind the hub.
+                Err(RecvError::Lagged(_)) => match last_event_id {
+                    Some(after) => match usecase.get_events_after(&owner, after).await {
+                        Ok(logged) => {
+                            missed = Some(logged);
+                            Ok(())
+                        }
+                        // Ending the stream makes the client resume from `after` instead.
+                        Err(e) => {
+                            error!("Failed to read missed events: {:?}", e);
+                            return;
+                        }
+                    },
+                    // Only events that are not logged were received, so there is nothing to
+                    // resume from.
+                    None => sender.send(reset_message(None)).await,
+                },
+                Err(RecvError::Closed) => return,
+            },
+            _ = keep_alive.tick() => sender.send(Bytes::from_static(b": keep-alive\n\n")).await,
+            _ = tick.tick(), if ticks.is_running() => match ticks.tick(&owner) {
+                Some(event) => sender.send(to_message(&event)).await,
+                None => Ok(()),
+            },
+        };
+        if sent.is_err() {
+            return;
+        }
+    }
+}
+
+/// Tells the client to fetch its data again, as it missed events that are no longer logged,
+/// and moves its resume position to `last_id`.
+fn reset_message(last_id: Option<i64>) -> Bytes {
+    let id = last_id.map(|id| format!("id: {id}\n")).unwrap_or_default();
+    Bytes::from(format!("{id}event: reset\ndata: {{}}\n\n"))
+}
+
+/// Formats `event` as a Server-Sent Event. The JSON data holds no line breaks.
+fn to_message(event: &UserEvent) -> Bytes {
+    let data = serde_json::to_string(event).unwrap_or_else(|_| "null".to_owned());
+    let id = event.id.map(|id| format!("id: {id}\n")).unwrap_or_default();
+    Bytes::from(format!(
+        "{id}event: {}\ndata: {data}\n\n",
+        event.kind.as_str()
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::domain::timers::Timer;
+    use crate::dto::{TimerResult, TimerSituation, TimerState};
+    use crate::usecases::timer_usecase::{MockTimerUsecase, TimerUsecase};
+    use actix_web::web;
+    use chrono::Utc;
+
+    /// A timer usecase that finds `timer`, or no timer when it is `None`.
+    fn timer_usecase(timer: Option<Timer>) -> web::Data<Box<dyn TimerUsecase>> {
+        let mut mock_usecase = MockTimerUsecase::new();
+        mock_usecase.expect_get_timer().returning(move |_| {
+            Ok(TimerResult {
+                situation: match timer {
+                    Some(_) => TimerSituation::Succeeded,
+                    None => TimerSituation::NotFound,
+                },
+                timer: timer
+                    .clone()
+                    .map(|timer| TimerState::new(timer, Utc::now())),
+                efforts: vec![],
+                description: None,
+            })
+        });
+        web::Data::new(Box::new(mock_usecase) as Box<dyn TimerUsecase>)
+    }
+
+    mod connect_events {
+        use super::timer_usecase;
+        use crate::connect_events;
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::helpers::event_hub::EventHub;
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn ログインしていればウェブソケットに切り替える() {
+            let usecase =
+                web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(None))
+                    .app_data(web::Data::new(EventHub::default()))
+                    .service(test_login)
+                    .service(connect_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/ws")
+                .cookie(cookie)
+                .insert_header((http::header::CONNECTION, "upgrade"))
+                .insert_header((http::header::UPGRADE, "websocket"))
+                .insert_header((http::header::SEC_WEBSOCKET_VERSION, "13"))
+                .insert_header((http::header::SEC_WEBSOCKET_KEY, "dGhlIHNhbXBsZSBub25jZQ=="))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::SWITCHING_PROTOCOLS, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(web::Data::new(EventHub::default()))
+                    .service(connect_events),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/events/ws").to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+    }
+
+    mod stream_events {
+        use std::future::poll_fn;
+        use std::pin::Pin;
+
+        use super::timer_usecase;
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::events::{EventKind, MissedEvents, UserEvent};
+        use crate::domain::timers::Timer;
+        use crate::helpers::event_hub::EventHub;
+        use crate::stream_events;
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use actix_web::body::MessageBody;
+        use actix_web::{http, test, web, App};
+        use chrono::{Duration, Utc};
+
+        /// Reads the never-ending event stream until `marker` and the end of the event with it.
+        async fn read_until<B>(body: &mut B, marker: &str) -> String
+        where
+            B: MessageBody + Unpin,
+            B::Error: std::fmt::Debug,
+        {
+            let mut received = String::new();
+            while !received.contains(marker) || !received.ends_with("\n\n") {
+                let chunk = poll_fn(|cx| Pin::new(&mut *body).poll_next(cx))
+                    .await
+                    .unwrap()
+                    .unwrap();
+                received.push_str(std::str::from_utf8(&chunk).unwrap());
+            }
+            received
+        }
+
+        #[actix_web::test]
+        async fn 最後に受け取ったイベントより後のイベントから送る() {
+            let mut mock_usecase = MockEventUsecase::new();
+            mock_usecase
+                .expect_get_events_after()
+                .withf(|owner, after| owner == "test@example.com" && *after == 41)
+                .returning(|owner, _| {
+                    Ok(MissedEvents::Logged(vec![UserEvent {
+                        id: Some(42),
+                        owner: owner.to_owned(),
+                        kind: EventKind::EffortCreated,
+                        data: serde_json::json!({ "title": "Rust" }),
+                    }]))
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(None))
+                    .app_data(web::Data::new(EventHub::default()))
+                    .service(test_login)
+                    .service(stream_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/stream")
+                .cookie(cookie)
+                .insert_header(("Last-Event-ID", "41"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            // The stream never ends, so only the retry interval and the missed event are read.
+            let mut body = resp.into_body();
+            let received = read_until(&mut body, "id: 42\n").await;
+            assert!(received.starts_with("retry: 5000\n\n"));
+            assert!(received.contains("id: 42\nevent: effort.created\ndata: {"));
+        }
+
+        #[actix_web::test]
+        async fn 見逃したイベントの後にタイマーの状態を送る() {
+            let mut mock_usecase = MockEventUsecase::new();
+            mock_usecase
+                .expect_get_events_after()
+                .returning(|owner, _| {
+                    Ok(MissedEvents::Logged(vec![UserEvent {
+                        id: Some(42),
+                        owner: owner.to_owned(),
+                        kind: EventKind::TimerTick,
+                        data: serde_json::Value::Null,
+                    }]))
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EventUsecase>);
+            let started_at = Utc::now() - Duration::minutes(5);
+            let timer = Timer {
+                owner: "test@example.com".to_owned(),
+                title: "Rust".to_owned(),
+                notes: None,
+                category_id: None,
+                tags: vec![],
+                started_at,
+                running_since: Some(started_at),
+                accumulated_seconds: 0,
+                pomodoro: None,
+                settled_seconds: 0,
+                work_started_at: None,
+                revision: 0,
+            };
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(Some(timer)))
+                    .app_data(web::Data::new(EventHub::default()))
+                    .service(test_login)
+                    .service(stream_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/stream")
+                .cookie(cookie)
+                .insert_header(("Last-Event-ID", "41"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let mut body = resp.into_body();
+            let received = read_until(&mut body, "\"running\":true").await;
+            let stopped = received.find("id: 42\nevent: timer.tick").unwrap();
+            let running = received.find("\"running\":true").unwrap();
+            assert!(stopped < running);
+        }
+
+        #[actix_web::test]
+        async fn 遅れて届いたイベントで再開位置を戻さない() {
+            let mut mock_usecase = MockEventUsecase::new();
+            mock_usecase
+                .expect_get_events_after()
+                .returning(|_, _| Ok(MissedEvents::Logged(vec![])));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EventUsecase>);
+            let hub = web::Data::new(EventHub::default());
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(None))
+                    .app_data(hub.clone())
+                    .service(test_login)
+                    .service(stream_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/stream")
+                .cookie(cookie)
+                .insert_header(("Last-Event-ID", "41"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let event = |id| UserEvent {
+                id: Some(id),
+                owner: "test@example.com".to_owned(),
+                kind: EventKind::EffortCreated,
+                data: serde_json::json!({ "title": "Rust" }),
+            };
+            hub.dispatch(event(43));
+            hub.dispatch(event(42));
+            hub.dispatch(event(44));
+            let mut body = resp.into_body();
+            let received = read_until(&mut body, "id: 44\n").await;
+            assert!(received.contains("id: 43\n"));
+            assert!(!received.contains("id: 42\n"));
+        }
+
+        #[actix_web::test]
+        async fn 記録から消えたイベントがあればリセットを送る() {
+            let mut mock_usecase = MockEventUsecase::new();
+            mock_usecase
+                .expect_get_events_after()
+                .returning(|_, _| Ok(MissedEvents::Dropped { last_id: 900 }));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(None))
+                    .app_data(web::Data::new(EventHub::default()))
+                    .service(test_login)
+                    .service(stream_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/stream")
+                .cookie(cookie)
+                .insert_header(("Last-Event-ID", "41"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let mut body = resp.into_body();
+            let received = read_until(&mut body, "event: reset").await;
+            assert!(received.ends_with("id: 900\nevent: reset\ndata: {}\n\n"));
+        }
+
+        #[actix_web::test]
+        async fn セッションが終わればストリームを閉じる() {
+            let usecase =
+                web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+            let hub = web::Data::new(EventHub::default());
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(None))
+                    .app_data(hub.clone())
+                    .service(test_login)
+                    .service(stream_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/stream")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            hub.dispatch(UserEvent {
+                id: None,
+                owner: "test@example.com".to_owned(),
+                kind: EventKind::SessionEnded,
+                data: serde_json::Value::Null,
+            });
+            let mut body = resp.into_body();
+            read_until(&mut body, "event: session.ended").await;
+            assert!(poll_fn(|cx| Pin::new(&mut body).poll_next(cx))
+                .await
+                .is_none());
+        }
+
+        #[actix_web::test]
+        async fn 再開位置がないまま遅れたクライアントにはリセットを送る() {
+            let usecase =
+                web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+            let hub = web::Data::new(EventHub::default());
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(None))
+                    .app_data(hub.clone())
+                    .service(test_login)
+                    .service(stream_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/stream")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            // Far more ticks than the hub keeps for a client that does not read.
+            for _ in 0..200 {
+                hub.dispatch(UserEvent {
+                    id: None,
+                    owner: "test@example.com".to_owned(),
+                    kind: EventKind::TimerTick,
+                    data: serde_json::Value::Null,
+                });
+            }
+            let mut body = resp.into_body();
+            let received = read_until(&mut body, "event: reset").await;
+            assert!(received.ends_with("\n\nevent: reset\ndata: {}\n\n"));
+        }
+
+        #[actix_web::test]
+        async fn 不正な再開位置にはステータス400を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(timer_usecase(None))
+                    .app_data(web::Data::new(EventHub::default()))
+                    .service(test_login)
+                    .service(stream_events),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/events/stream")
+                .cookie(cookie)
+                .insert_header(("Last-Event-ID", "latest"))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::ops::Deref;
+
+use super::errors::ApiError;
+use crate::domain::users::User;
+use actix_session::SessionExt;
+use actix_web::{dev::Payload, FromRequest, HttpRequest};
+use futures::future::{ready, Ready};
+
+/// The session key under which the logged in `User` is stored.
+pub const CURRENT_USER: &str = "current_user";
+
+/// The user of the current session. Extracting it fails with 401 when nobody is logged in.
+pub struct AuthenticatedUser(pub User);
+
+impl Deref for AuthenticatedUser {
+    type Target = User;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl FromRequest for AuthenticatedUser {
+    type Error = actix_web::Error;
+    type Future = Ready<Result<Self, Self::Error>>;
+
+    fn from_request(req: &HttpRequest, _: &mut Payload) -> Self::Future {
+        ready(match req.get_session().get::<User>(CURRENT_USER) {
+            Ok(Some(user)) => Ok(AuthenticatedUser(user)),
+            Ok(None) => Err(ApiError::Unauthorized.into()),
+            Err(e) => Err(ApiError::Internal(e.into()).into()),
+        })
+    }
+}
@@ -0,0 +1,226 @@
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use crate::dto::{GoalProgressQuery, GoalRequest, GoalResult, GoalSituation};
+use crate::usecases::goal_usecase::GoalUsecase;
+use actix_web::{
+    delete, get,
+    http::StatusCode,
+    post, put,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+fn status_code(situation: &GoalSituation) -> StatusCode {
+    match situation {
+        GoalSituation::Succeeded => StatusCode::OK,
+        GoalSituation::NotFound => StatusCode::NOT_FOUND,
+        GoalSituation::TitleIsEmpty
+        | GoalSituation::InvalidTarget
+        | GoalSituation::InvalidPeriod
+        | GoalSituation::CategoryNotFound
+        | GoalSituation::InvalidRange => StatusCode::BAD_REQUEST,
+    }
+}
+
+fn to_response(result: GoalResult) -> HttpResponse {
+    HttpResponse::build(status_code(&result.situation)).json(result)
+}
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "Goals of the current user.", body = [Goal]),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/goals")]
+pub async fn get_goals(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn GoalUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let goals = usecase
+        .get_goals(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(HttpResponse::Ok().json(goals))
+}
+
+#[utoipa::path(
+    post,
+    request_body = GoalRequest,
+    responses(
+        (status = 201, description = "The goal is created.", body = GoalResult),
+        (status = 400, description = "The goal is invalid or its category is not found.", body = GoalResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/goals")]
+pub async fn add_goal(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn GoalUsecase>>,
+    request: web::Json<GoalRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .add_goal(&user.email, &request)
+        .map_err(ApiError::from)
+        .await?;
+    match result.situation {
+        GoalSituation::Succeeded => Ok(HttpResponse::Created().json(result)),
+        _ => Ok(to_response(result)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    params(("id" = i64, Path, description = "Goal id")),
+    request_body = GoalRequest,
+    responses(
+        (status = 200, description = "The goal is updated.", body = GoalResult),
+        (status = 400, description = "The goal is invalid or its category is not found.", body = GoalResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The goal is not found.", body = GoalResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[put("/goals/{id}")]
+pub async fn update_goal(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn GoalUsecase>>,
+    id: web::Path<i64>,
+    request: web::Json<GoalRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .update_goal(&user.email, id.into_inner(), &request)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    delete,
+    params(("id" = i64, Path, description = "Goal id")),
+    responses(
+        (status = 200, description = "The goal is deleted.", body = GoalResult),
+        (status = 400, description = "The id is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The goal is not found.", body = GoalResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/goals/{id}")]
+pub async fn delete_goal(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn GoalUsecase>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .delete_goal(&user.email, id.into_inner())
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    get,
+    params(("id" = i64, Path, description = "Goal id"), GoalProgressQuery),
+    responses(
+        (status = 200, description = "Progress of the current period and the ones before it, in the user's time zone.", body = GoalProgressResult),
+        (status = 400, description = "The number of periods is out of range.", body = GoalProgressResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The goal is not found.", body = GoalProgressResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/goals/{id}/progress")]
+pub async fn get_goal_progress(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn GoalUsecase>>,
+    id: web::Path<i64>,
+    query: web::Query<GoalProgressQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .get_progress(&user.email, &user.timezone, id.into_inner(), &query)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(HttpResponse::build(status_code(&result.situation)).json(result))
+}
+
+#[cfg(test)]
+mod tests {
+    mod get_goal_progress {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::dto::{GoalProgressResult, GoalSituation};
+        use crate::get_goal_progress;
+        use crate::usecases::goal_usecase::{GoalUsecase, MockGoalUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn ユーザのタイムゾーンで進捗を求める() {
+            let mut mock_usecase = MockGoalUsecase::new();
+            mock_usecase
+                .expect_get_progress()
+                .withf(|owner, timezone, id, query| {
+                    owner == "test@example.com"
+                        && timezone == "UTC"
+                        && *id == 1
+                        && query.periods == Some(4)
+                })
+                .returning(|_, _, _, _| {
+                    Ok(GoalProgressResult {
+                        situation: GoalSituation::NotFound,
+                        progress: None,
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn GoalUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_goal_progress),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/goals/1/progress?periods=4")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NOT_FOUND, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let usecase = web::Data::new(Box::new(MockGoalUsecase::new()) as Box<dyn GoalUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(get_goal_progress),
+            )
+            .await;
+
+            let req = test::TestRequest::get()
+                .uri("/goals/1/progress")
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+    }
+}
@@ -0,0 +1,511 @@
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use crate::domain::heatmap::HeatmapBucket;
+use crate::dto::{HeatmapQuery, HeatmapResult, HeatmapSituation, HeatmapSvgQuery};
+use crate::helpers::heatmap_svg::{self, SvgOptions};
+use crate::usecases::{heatmap_usecase::HeatmapUsecase, user_usecase::UserUsecase};
+use actix_web::{
+    get,
+    http::header::{CacheControl, CacheDirective, ETag, EntityTag, IfNoneMatch},
+    web::{self, Data},
+    HttpMessage, HttpRequest, HttpResponse,
+};
+use anyhow::{anyhow, Result};
+use futures::TryFutureExt;
+use sha2::{Digest, Sha256};
+
+/// How long the rendered calendar may be served from a cache without asking again.
+const SVG_MAX_AGE_SECONDS: u32 = 60 * 60;
+
+fn to_response(result: HeatmapResult) -> HttpResponse {
+    match result.situation {
+        HeatmapSituation::Succeeded => HttpResponse::Ok().json(result),
+        HeatmapSituation::UserNotFound => HttpResponse::NotFound().json(result),
+        HeatmapSituation::InvalidRange | HeatmapSituation::InvalidFilter => {
+            HttpResponse::BadRequest().json(result)
+        }
+    }
+}
+
+/// Finds the owner of the calendar that is public under `slug`. Unknown slugs and calendars
+/// made private again are not found alike.
+pub(super) async fn public_calendar_owner(
+    users: &dyn UserUsecase,
+    slug: &str,
+) -> Result<String, ApiError> {
+    users
+        .find_public_calendar_owner(slug)
+        .map_err(ApiError::from)
+        .await?
+        .ok_or_else(|| ApiError::NotFound("No calendar is public under the slug.".to_owned()))
+}
+
+#[utoipa::path(
+    get,
+    params(HeatmapQuery),
+    responses(
+        (status = 200, description = "Effort totals per bucket.", body = HeatmapResult),
+        (status = 400, description = "The range or the filter is invalid.", body = HeatmapResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The user is not registered anymore.", body = HeatmapResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/me/heatmap")]
+pub async fn get_heatmap(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn HeatmapUsecase>>,
+    query: web::Query<HeatmapQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .get_heatmap(&user.email, &query)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    get,
+    params(("slug" = String, Path, description = "Slug the owner made the calendar public under"), HeatmapQuery),
+    responses(
+        (status = 200, description = "Effort totals per bucket.", body = HeatmapResult),
+        (status = 400, description = "The range or the filter is invalid.", body = HeatmapResult),
+        (status = 404, description = "No calendar is public under the slug.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/public/{slug}/heatmap")]
+pub async fn get_public_heatmap(
+    usecase: Data<Box<dyn HeatmapUsecase>>,
+    users: Data<Box<dyn UserUsecase>>,
+    slug: web::Path<String>,
+    query: web::Query<HeatmapQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let owner = public_calendar_owner(users.as_ref().as_ref(), &slug).await?;
+    let result = usecase
+        .get_heatmap(&owner, &query)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    get,
+    params(HeatmapSvgQuery),
+    responses(
+        (status = 200, description = "The effort calendar.", content_type = "image/svg+xml", body = String),
+        (status = 304, description = "The calendar is not modified."),
+        (status = 400, description = "The query is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The user is not registered anymore.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/me/heatmap.svg")]
+pub async fn get_heatmap_svg(
+    user: AuthenticatedUser,
+    request: HttpRequest,
+    usecase: Data<Box<dyn HeatmapUsecase>>,
+    query: web::Query<HeatmapSvgQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let svg = render_svg(usecase.as_ref().as_ref(), &user.email, &query).await?;
+    Ok(svg_response(&request, svg, CacheDirective::Private))
+}
+
+#[utoipa::path(
+    get,
+    params(("slug" = String, Path, description = "Slug the owner made the calendar public under"), HeatmapSvgQuery),
+    responses(
+        (status = 200, description = "The effort calendar, which image proxies such as the ones of READMEs may cache.", content_type = "image/svg+xml", body = String),
+        (status = 304, description = "The calendar is not modified."),
+        (status = 400, description = "The query is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No calendar is public under the slug.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/public/{slug}/heatmap.svg")]
+pub async fn get_public_heatmap_svg(
+    request: HttpRequest,
+    usecase: Data<Box<dyn HeatmapUsecase>>,
+    users: Data<Box<dyn UserUsecase>>,
+    slug: web::Path<String>,
+    query: web::Query<HeatmapSvgQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let owner = public_calendar_owner(users.as_ref().as_ref(), &slug).await?;
+    let svg = render_svg(usecase.as_ref().as_ref(), &owner, &query).await?;
+    Ok(svg_response(&request, svg, CacheDirective::Public))
+}
+
+/// Renders the calendar of `owner` as an SVG document.
+async fn render_svg(
+    usecase: &dyn HeatmapUsecase,
+    owner: &str,
+    query: &HeatmapSvgQuery,
+) -> Result<String, ApiError> {
+    let options = match SvgOptions::from_query(query) {
+        Ok(options) => options,
+        Err(e) => return Err(ApiError::InvalidRequest(e.to_string())),
+    };
+    let heatmap_query = HeatmapQuery {
+        from: query.from,
+        to: query.to,
+        bucket: Some(HeatmapBucket::Day),
+        category: query.category.clone(),
+        tag: query.tag.clone(),
+        match_mode: query.match_mode,
+    };
+    let result = usecase
+        .get_heatmap(owner, &heatmap_query)
+        .map_err(ApiError::from)
+        .await?;
+    let heatmap = match (result.situation, result.heatmap) {
+        (HeatmapSituation::Succeeded, Some(heatmap)) => heatmap,
+        (HeatmapSituation::UserNotFound, _) => {
+            return Err(ApiError::NotFound("The user is not found.".to_owned()))
+        }
+        (HeatmapSituation::InvalidRange | HeatmapSituation::InvalidFilter, _) => {
+            return Err(ApiError::InvalidRequest(
+                result.description.unwrap_or_default(),
+            ))
+        }
+        (HeatmapSituation::Succeeded, None) => {
+            return Err(ApiError::Internal(anyhow!("Missing heatmap")))
+        }
+    };
+    Ok(heatmap_svg::render(&heatmap, &options))
+}
+
+/// Responds with `svg`, or with 304 when the client has it already. `visibility` tells whether
+/// shared caches may keep it too.
+fn svg_response(request: &HttpRequest, svg: String, visibility: CacheDirective) -> HttpResponse {
+    // A digest that stays the same across builds and instances, which proxies may ask alike.
+    let digest = Sha256::digest(svg.as_bytes());
+    let etag = EntityTag::new_strong(
+        digest
+            .iter()
+            .map(|byte| format!("{byte:02x}"))
+            .collect::<String>(),
+    );
+    let cache_control = CacheControl(vec![
+        visibility,
+        CacheDirective::MaxAge(SVG_MAX_AGE_SECONDS),
+    ]);
+
+    let not_modified = match request.get_header::<IfNoneMatch>() {
+        Some(IfNoneMatch::Any) => true,
+        Some(IfNoneMatch::Items(tags)) => tags.iter().any(|tag| tag.weak_eq(&etag)),
+        None => false,
+    };
+    if not_modified {
+        return HttpResponse::NotModified()
+            .insert_header(ETag(etag))
+            .insert_header(cache_control)
+            .finish();
+    }
+    HttpResponse::Ok()
+        .content_type("image/svg+xml")
+        .insert_header(ETag(etag))
+        .insert_header(cache_control)
+        .body(svg)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::usecases::user_usecase::{MockUserUsecase, UserUsecase};
+    use actix_web::web;
+
+    /// A user usecase under which only the calendar of test@example.com is public, as `public`.
+    fn public_calendars() -> web::Data<Box<dyn UserUsecase>> {
+        let mut mock_usecase = MockUserUsecase::new();
+        mock_usecase
+            .expect_find_public_calendar_owner()
+            .returning(|slug| Ok(Some("test@example.com".to_owned()).filter(|_| slug == "public")));
+        web::Data::new(Box::new(mock_usecase) as Box<dyn UserUsecase>)
+    }
+
+    mod get_heatmap {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::heatmap::HeatmapBucket;
+        use crate::dto::{Heatmap, HeatmapCell, HeatmapResult, HeatmapSituation};
+        use crate::get_heatmap;
+        use crate::usecases::heatmap_usecase::{HeatmapUsecase, MockHeatmapUsecase};
+        use actix_web::{body::MessageBody, http, test, web, App};
+        use chrono::NaiveDate;
+
+        #[actix_web::test]
+        async fn 集計成功時ヒートマップを返す() {
+            let mut mock_usecase = MockHeatmapUsecase::new();
+            let date = NaiveDate::from_ymd_opt(2023, 4, 1).unwrap();
+            let heatmap_result = HeatmapResult {
+                situation: HeatmapSituation::Succeeded,
+                heatmap: Some(Heatmap {
+                    from: date,
+                    to: date,
+                    bucket: HeatmapBucket::Week,
+                    timezone: "UTC".to_owned(),
+                    locale: "en_US".to_owned(),
+                    cells: vec![HeatmapCell {
+                        date,
+                        total_seconds: 3600,
+                        effort_count: 1,
+                        level: 4,
+                    }],
+                }),
+                description: None,
+            };
+            let expected = heatmap_result.clone();
+            mock_usecase
+                .expect_get_heatmap()
+                .withf(|user_id, query| {
+                    user_id == "test@example.com" && query.bucket == Some(HeatmapBucket::Week)
+                })
+                .returning(move |_, _| Ok(heatmap_result.clone()));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_heatmap),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/me/heatmap?from=2023-04-01&to=2023-04-01&bucket=week")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let result_from_response: HeatmapResult =
+                serde_json::from_slice(resp.into_body().try_into_bytes().unwrap().as_ref())
+                    .unwrap();
+            assert_eq!(expected, result_from_response);
+        }
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockHeatmapUsecase::new()) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(get_heatmap),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/me/heatmap").to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn 期間が不正なときステータス400を返す() {
+            let mut mock_usecase = MockHeatmapUsecase::new();
+            mock_usecase.expect_get_heatmap().returning(|_, _| {
+                Ok(HeatmapResult {
+                    situation: HeatmapSituation::InvalidRange,
+                    heatmap: None,
+                    description: None,
+                })
+            });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_heatmap),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/me/heatmap?from=2023-04-02&to=2023-04-01")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        }
+    }
+    mod get_public_heatmap {
+        use super::public_calendars;
+        use crate::get_public_heatmap;
+        use crate::usecases::heatmap_usecase::{HeatmapUsecase, MockHeatmapUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 公開されていないスラッグにはステータス404を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockHeatmapUsecase::new()) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(usecase.clone())
+                    .app_data(public_calendars())
+                    .service(get_public_heatmap),
+            )
+            .await;
+
+            let req = test::TestRequest::get()
+                .uri("/public/test@example.com/heatmap")
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NOT_FOUND, resp.status());
+        }
+    }
+    mod get_heatmap_svg {
+        use super::public_calendars;
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::heatmap::HeatmapBucket;
+        use crate::dto::{Heatmap, HeatmapResult, HeatmapSituation};
+        use crate::usecases::heatmap_usecase::{HeatmapUsecase, MockHeatmapUsecase};
+        use crate::{get_heatmap_svg, get_public_heatmap_svg};
+        use actix_web::{http, test, web, App};
+        use chrono::NaiveDate;
+
+        fn succeeded_usecase() -> MockHeatmapUsecase {
+            let mut mock_usecase = MockHeatmapUsecase::new();
+            mock_usecase
+                .expect_get_heatmap()
+                .withf(|user_id, query| {
+                    user_id == "test@example.com" && query.bucket == Some(HeatmapBucket::Day)
+                })
+                .returning(|_, _| {
+                    let date = NaiveDate::from_ymd_opt(2023, 4, 1).unwrap();
+                    Ok(HeatmapResult {
+                        situation: HeatmapSituation::Succeeded,
+                        heatmap: Some(Heatmap {
+                            from: date,
+                            to: date,
+                            bucket: HeatmapBucket::Day,
+                            timezone: "UTC".to_owned(),
+                            locale: "en_US".to_owned(),
+                            cells: vec![],
+                        }),
+                        description: None,
+                    })
+                });
+            mock_usecase
+        }
+
+        #[actix_web::test]
+        async fn 公開されたカレンダーはキャッシュ可能なsvgを返す() {
+            let usecase = web::Data::new(Box::new(succeeded_usecase()) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(usecase.clone())
+                    .app_data(public_calendars())
+                    .service(get_public_heatmap_svg),
+            )
+            .await;
+
+            let req = test::TestRequest::get()
+                .uri("/public/public/heatmap.svg")
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            assert_eq!(
+                "image/svg+xml",
+                resp.headers().get(http::header::CONTENT_TYPE).unwrap()
+            );
+            assert_eq!(
+                "public, max-age=3600",
+                resp.headers().get(http::header::CACHE_CONTROL).unwrap()
+            );
+            assert!(resp.headers().contains_key(http::header::ETAG));
+        }
+
+        #[actix_web::test]
+        async fn 自分のカレンダーは共有キャッシュに残させない() {
+            let usecase = web::Data::new(Box::new(succeeded_usecase()) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_heatmap_svg),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/me/heatmap.svg")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            assert_eq!(
+                "private, max-age=3600",
+                resp.headers().get(http::header::CACHE_CONTROL).unwrap()
+            );
+        }
+
+        #[actix_web::test]
+        async fn etagが一致するときステータス304を返す() {
+            let usecase = web::Data::new(Box::new(succeeded_usecase()) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(usecase.clone())
+                    .app_data(public_calendars())
+                    .service(get_public_heatmap_svg),
+            )
+            .await;
+
+            let req = test::TestRequest::get()
+                .uri("/public/public/heatmap.svg")
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            let etag = resp.headers().get(http::header::ETAG).unwrap().clone();
+
+            let req = test::TestRequest::get()
+                .uri("/public/public/heatmap.svg")
+                .insert_header((http::header::IF_NONE_MATCH, etag))
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NOT_MODIFIED, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn パレットが不正なときステータス400を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockHeatmapUsecase::new()) as Box<dyn HeatmapUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(usecase.clone())
+                    .app_data(public_calendars())
+                    .service(get_public_heatmap_svg),
+            )
+            .await;
+
+            let req = test::TestRequest::get()
+                .uri("/public/public/heatmap.svg?palette=red")
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        }
+    }
+}
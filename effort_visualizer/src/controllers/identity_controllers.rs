@@ -0,0 +1,195 @@
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use crate::dto::{LinkIdentitySituation, LoginRequest, UnlinkIdentitySituation};
+use crate::usecases::authentication_usecase::AuthenticationUsecase;
+use actix_web::{
+    delete, get, post,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "Identities the current user can sign in with.", body = [UserIdentity]),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/me/identities")]
+pub async fn get_identities(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn AuthenticationUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let identities = usecase
+        .get_identities(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(HttpResponse::Ok().json(identities))
+}
+
+#[utoipa::path(
+    post,
+    request_body = LoginRequest,
+    responses(
+        (status = 200, description = "The identity is linked.", body = LinkIdentityResult),
+        (status = 400, description = "The provider is unknown.", body = LinkIdentityResult),
+        (status = 401, description = "Not logged in, or the credential is invalid.", body = LinkIdentityResult),
+        (status = 409, description = "The account belongs to another user, or another account of the provider is linked.", body = LinkIdentityResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/me/identities")]
+pub async fn link_identity(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn AuthenticationUsecase>>,
+    credential_info: web::Json<LoginRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .link_identity(&user.email, &credential_info)
+        .map_err(ApiError::from)
+        .await?;
+    match result.situation {
+        LinkIdentitySituation::Succeeded | LinkIdentitySituation::AlreadyLinked => {
+            Ok(HttpResponse::Ok().json(result))
+        }
+        LinkIdentitySituation::BelongsToAnotherUser
+        | LinkIdentitySituation::ProviderAlreadyLinked => Ok(HttpResponse::Conflict().json(result)),
+        LinkIdentitySituation::VerificationFailed => Ok(HttpResponse::Unauthorized().json(result)),
+        LinkIdentitySituation::UnknownProvider => Ok(HttpResponse::BadRequest().json(result)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    params(("provider" = String, Path, description = "Identity provider id")),
+    responses(
+        (status = 200, description = "The identity is unlinked.", body = UnlinkIdentityResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No identity of the provider is linked.", body = UnlinkIdentityResult),
+        (status = 409, description = "The last identity can't be unlinked.", body = UnlinkIdentityResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/me/identities/{provider}")]
+pub async fn unlink_identity(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn AuthenticationUsecase>>,
+    provider: web::Path<String>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .unlink_identity(&user.email, &provider)
+        .map_err(ApiError::from)
+        .await?;
+    match result.situation {
+        UnlinkIdentitySituation::Succeeded => Ok(HttpResponse::Ok().json(result)),
+        UnlinkIdentitySituation::NotLinked => Ok(HttpResponse::NotFound().json(result)),
+        UnlinkIdentitySituation::LastIdentity => Ok(HttpResponse::Conflict().json(result)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod link_identity {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::dto::{LinkIdentityResult, LinkIdentitySituation, LoginRequest};
+        use crate::link_identity;
+        use crate::usecases::authentication_usecase::{
+            AuthenticationUsecase, MockAuthenticationUsecase,
+        };
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 他のユーザのアカウントのときステータス409を返す() {
+            let mut mock_usecase = MockAuthenticationUsecase::new();
+            mock_usecase
+                .expect_link_identity()
+                .withf(|user_email, request| {
+                    user_email == "test@example.com" && request.provider == "github"
+                })
+                .returning(|_, _| {
+                    Ok(LinkIdentityResult {
+                        situation: LinkIdentitySituation::BelongsToAnotherUser,
+                        identities: None,
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn AuthenticationUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(link_identity),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/me/identities")
+                .cookie(cookie)
+                .set_json(&LoginRequest {
+                    provider: "github".to_owned(),
+                    credential: "test".to_owned(),
+                })
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::CONFLICT, resp.status());
+        }
+    }
+
+    mod unlink_identity {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::dto::{UnlinkIdentityResult, UnlinkIdentitySituation};
+        use crate::unlink_identity;
+        use crate::usecases::authentication_usecase::{
+            AuthenticationUsecase, MockAuthenticationUsecase,
+        };
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 最後のidentityのときステータス409を返す() {
+            let mut mock_usecase = MockAuthenticationUsecase::new();
+            mock_usecase
+                .expect_unlink_identity()
+                .withf(|user_email, provider| {
+                    user_email == "test@example.com" && provider == "google"
+                })
+                .returning(|_, _| {
+                    Ok(UnlinkIdentityResult {
+                        situation: UnlinkIdentitySituation::LastIdentity,
+                        identities: Some(vec![]),
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn AuthenticationUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(unlink_identity),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::delete()
+                .uri("/me/identities/google")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::CONFLICT, resp.status());
+        }
+    }
+}
@@ -1,3 +1,18 @@
 pub mod api_doc;
 pub mod authentication_controllers;
-mod errors;
+pub mod category_controllers;
+pub mod effort_controllers;
+pub mod errors;
+pub mod event_controllers;
+pub mod extractors;
+pub mod goal_controllers;
+pub mod heatmap_controllers;
+pub mod identity_controllers;
+pub mod pomodoro_controllers;
+pub mod session_controllers;
+pub mod streak_controllers;
+pub mod tag_controllers;
+#[cfg(test)]
+pub mod test_helpers;
+pub mod timer_controllers;
+pub mod user_controllers;
@@ -0,0 +1,97 @@
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use crate::dto::{PomodoroStatsQuery, PomodoroStatsSituation};
+use crate::usecases::pomodoro_usecase::PomodoroUsecase;
+use actix_web::{
+    get,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+#[utoipa::path(
+    get,
+    params(PomodoroStatsQuery),
+    responses(
+        (status = 200, description = "Completed and abandoned pomodoros per day of the current user's time zone.", body = PomodoroStatsResult),
+        (status = 400, description = "The range is invalid or too long.", body = PomodoroStatsResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/pomodoros/stats")]
+pub async fn get_pomodoro_stats(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn PomodoroUsecase>>,
+    query: web::Query<PomodoroStatsQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .get_stats(&user.email, &user.timezone, &query)
+        .map_err(ApiError::from)
+        .await?;
+    match result.situation {
+        PomodoroStatsSituation::Succeeded => Ok(HttpResponse::Ok().json(result)),
+        PomodoroStatsSituation::InvalidRange => Ok(HttpResponse::BadRequest().json(result)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod get_pomodoro_stats {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::pomodoros::PomodoroDay;
+        use crate::dto::{PomodoroStatsResult, PomodoroStatsSituation};
+        use crate::get_pomodoro_stats;
+        use crate::usecases::pomodoro_usecase::{MockPomodoroUsecase, PomodoroUsecase};
+        use actix_web::{http, test, web, App};
+        use chrono::NaiveDate;
+
+        #[actix_web::test]
+        async fn ユーザのタイムゾーンで日ごとのポモドーロ数を返す() {
+            let mut mock_usecase = MockPomodoroUsecase::new();
+            mock_usecase
+                .expect_get_stats()
+                .withf(|owner, timezone, query| {
+                    owner == "test@example.com"
+                        && timezone == "UTC"
+                        && query.from == NaiveDate::from_ymd_opt(2023, 5, 1)
+                        && query.to.is_none()
+                })
+                .returning(|_, _, query| {
+                    Ok(PomodoroStatsResult {
+                        situation: PomodoroStatsSituation::Succeeded,
+                        days: Some(vec![PomodoroDay {
+                            date: query.from.unwrap(),
+                            completed: 6,
+                            abandoned: 2,
+                        }]),
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn PomodoroUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_pomodoro_stats),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/pomodoros/stats?from=2023-05-01")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let result: PomodoroStatsResult = test::read_body_json(resp).await;
+            assert_eq!(2, result.days.unwrap()[0].abandoned);
+        }
+    }
+}
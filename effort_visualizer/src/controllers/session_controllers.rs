@@ -0,0 +1,196 @@
+use super::errors::ApiError;
+use super::event_controllers::log_push_failure;
+use super::extractors::AuthenticatedUser;
+use crate::usecases::{event_usecase::EventUsecase, session_usecase::SessionUsecase};
+use actix_web::{
+    delete, get,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+fn cookie_sessions() -> ApiError {
+    ApiError::Unsupported(
+        "Sessions are kept in cookies, so the server can neither list nor revoke them.".to_owned(),
+    )
+}
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "Active sessions of the current user.", body = [UserSession]),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 501, description = "Sessions are only kept in cookies (`SESSION_STORE=cookie`).", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/me/sessions")]
+pub async fn get_sessions(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn SessionUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let sessions = usecase
+        .get_sessions(&user.email)
+        .map_err(ApiError::from)
+        .await?
+        .ok_or_else(cookie_sessions)?;
+    Ok(HttpResponse::Ok().json(sessions))
+}
+
+#[utoipa::path(
+    delete,
+    params(("id" = i64, Path, description = "Session id")),
+    responses(
+        (status = 204, description = "The session is revoked."),
+        (status = 400, description = "The id is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The session is not found.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 501, description = "Sessions are only kept in cookies (`SESSION_STORE=cookie`).", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/me/sessions/{id}")]
+pub async fn revoke_session(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn SessionUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let revoked = usecase
+        .revoke_session(&user.email, id.into_inner())
+        .map_err(ApiError::from)
+        .await?
+        .ok_or_else(cookie_sessions)?;
+    if revoked {
+        // Closes the event connections opened with the revoked session.
+        log_push_failure(events.session_ended(&user.email).await);
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(ApiError::NotFound("The session is not found.".to_owned()).into())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod get_sessions {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::dto::{ErrorCode, ProblemDetails};
+        use crate::get_sessions;
+        use crate::usecases::session_usecase::{MockSessionUsecase, SessionUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn セッションをクッキーに保存するときステータス501を返す() {
+            let mut mock_usecase = MockSessionUsecase::new();
+            mock_usecase
+                .expect_get_sessions()
+                .withf(|user_email| user_email == "test@example.com")
+                .returning(|_| Ok(None));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn SessionUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_sessions),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/me/sessions")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NOT_IMPLEMENTED, resp.status());
+            assert_eq!(
+                "application/problem+json",
+                resp.headers().get("content-type").unwrap()
+            );
+            let problem: ProblemDetails = test::read_body_json(resp).await;
+            assert_eq!(ErrorCode::Unsupported, problem.code);
+        }
+    }
+
+    mod revoke_session {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::revoke_session;
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use crate::usecases::session_usecase::{MockSessionUsecase, SessionUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 失効成功時ステータス204を返す() {
+            let mut mock_usecase = MockSessionUsecase::new();
+            mock_usecase
+                .expect_revoke_session()
+                .withf(|user_email, id| user_email == "test@example.com" && *id == 3)
+                .returning(|_, _| Ok(Some(true)));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn SessionUsecase>);
+            let mut mock_events = MockEventUsecase::new();
+            mock_events
+                .expect_session_ended()
+                .withf(|owner| owner == "test@example.com")
+                .times(1)
+                .returning(|_| Ok(()));
+            let events = web::Data::new(Box::new(mock_events) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(revoke_session),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::delete()
+                .uri("/me/sessions/3")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NO_CONTENT, resp.status());
+        }
+
+        #[actix_web::test]
+        async fn 他人のセッションは失効できずステータス404を返す() {
+            let mut mock_usecase = MockSessionUsecase::new();
+            mock_usecase
+                .expect_revoke_session()
+                .returning(|_, _| Ok(Some(false)));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn SessionUsecase>);
+            // Nothing was revoked, so no connection is closed.
+            let events = web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(revoke_session),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::delete()
+                .uri("/me/sessions/4")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NOT_FOUND, resp.status());
+        }
+    }
+}
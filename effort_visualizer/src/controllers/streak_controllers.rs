@@ -0,0 +1,183 @@
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use super::heatmap_controllers::public_calendar_owner;
+use crate::dto::{StreakQuery, StreakResult, StreakSituation};
+use crate::usecases::{streak_usecase::StreakUsecase, user_usecase::UserUsecase};
+use actix_web::{
+    get,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+fn to_response(result: StreakResult) -> HttpResponse {
+    match result.situation {
+        StreakSituation::Succeeded => HttpResponse::Ok().json(result),
+        StreakSituation::UserNotFound => HttpResponse::NotFound().json(result),
+        StreakSituation::InvalidRestDays => HttpResponse::BadRequest().json(result),
+    }
+}
+
+#[utoipa::path(
+    get,
+    params(StreakQuery),
+    responses(
+        (status = 200, description = "Current, longest and past streaks over the days of the user's time zone.", body = StreakResult),
+        (status = 400, description = "The rest days are invalid.", body = StreakResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The user is not registered anymore.", body = StreakResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/me/streaks")]
+pub async fn get_streaks(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn StreakUsecase>>,
+    query: web::Query<StreakQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .get_streaks(&user.email, &query)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    get,
+    params(("slug" = String, Path, description = "Slug the owner made the calendar public under"), StreakQuery),
+    responses(
+        (status = 200, description = "Current, longest and past streaks over the days of the owner's time zone.", body = StreakResult),
+        (status = 400, description = "The rest days are invalid.", body = StreakResult),
+        (status = 404, description = "No calendar is public under the slug.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/public/{slug}/streaks")]
+pub async fn get_public_streaks(
+    usecase: Data<Box<dyn StreakUsecase>>,
+    users: Data<Box<dyn UserUsecase>>,
+    slug: web::Path<String>,
+    query: web::Query<StreakQuery>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let owner = public_calendar_owner(users.as_ref().as_ref(), &slug).await?;
+    let result = usecase
+        .get_streaks(&owner, &query)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[cfg(test)]
+mod tests {
+    mod get_streaks {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::streaks::Streak;
+        use crate::dto::{StreakResult, StreakSituation, Streaks};
+        use crate::get_streaks;
+        use crate::usecases::streak_usecase::{MockStreakUsecase, StreakUsecase};
+        use actix_web::{http, test, web, App};
+        use chrono::NaiveDate;
+
+        #[actix_web::test]
+        async fn 休息日を指定して連続記録を返す() {
+            let streak = Streak {
+                first_day: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+                last_day: NaiveDate::from_ymd_opt(2023, 5, 8).unwrap(),
+                active_days: 6,
+            };
+            let mut mock_usecase = MockStreakUsecase::new();
+            mock_usecase
+                .expect_get_streaks()
+                .withf(|user_id, query| {
+                    user_id == "test@example.com" && query.rest_days.as_deref() == Some("sat,sun")
+                })
+                .returning(move |_, _| {
+                    Ok(StreakResult {
+                        situation: StreakSituation::Succeeded,
+                        streaks: Some(Streaks {
+                            current: Some(streak.clone()),
+                            longest: Some(streak.clone()),
+                            history: vec![streak.clone()],
+                        }),
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn StreakUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(get_streaks),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/me/streaks?rest_days=sat,sun")
+                .cookie(cookie)
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let result: StreakResult = test::read_body_json(resp).await;
+            assert_eq!(6, result.streaks.unwrap().current.unwrap().active_days);
+        }
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockStreakUsecase::new()) as Box<dyn StreakUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(get_streaks),
+            )
+            .await;
+
+            let req = test::TestRequest::get().uri("/me/streaks").to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+    }
+    mod get_public_streaks {
+        use crate::get_public_streaks;
+        use crate::usecases::streak_usecase::{MockStreakUsecase, StreakUsecase};
+        use crate::usecases::user_usecase::{MockUserUsecase, UserUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 公開されていないスラッグにはステータス404を返す() {
+            let mut mock_users = MockUserUsecase::new();
+            mock_users
+                .expect_find_public_calendar_owner()
+                .returning(|_| Ok(None));
+            let users = web::Data::new(Box::new(mock_users) as Box<dyn UserUsecase>);
+            let usecase =
+                web::Data::new(Box::new(MockStreakUsecase::new()) as Box<dyn StreakUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .app_data(usecase.clone())
+                    .app_data(users.clone())
+                    .service(get_public_streaks),
+            )
+            .await;
+
+            let req = test::TestRequest::get()
+                .uri("/public/test@example.com/streaks")
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NOT_FOUND, resp.status());
+        }
+    }
+}
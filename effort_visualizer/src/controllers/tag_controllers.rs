@@ -0,0 +1,207 @@
+use super::errors::ApiError;
+use super::extractors::AuthenticatedUser;
+use crate::dto::{TagMergeRequest, TagRequest, TagResult, TagSituation};
+use crate::usecases::tag_usecase::TagUsecase;
+use actix_web::{
+    delete, get, post, put,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+fn to_response(result: TagResult) -> HttpResponse {
+    match result.situation {
+        TagSituation::Succeeded => HttpResponse::Ok().json(result),
+        TagSituation::NotFound => HttpResponse::NotFound().json(result),
+        TagSituation::NameAlreadyUsed => HttpResponse::Conflict().json(result),
+        TagSituation::NameIsEmpty | TagSituation::SameTag => {
+            HttpResponse::BadRequest().json(result)
+        }
+    }
+}
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "Tags of the current user.", body = [Tag]),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/tags")]
+pub async fn get_tags(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TagUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let tags = usecase
+        .get_tags(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(HttpResponse::Ok().json(tags))
+}
+
+#[utoipa::path(
+    post,
+    request_body = TagRequest,
+    responses(
+        (status = 201, description = "The tag is created.", body = TagResult),
+        (status = 400, description = "The name is empty.", body = TagResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "Another tag has the name.", body = TagResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/tags")]
+pub async fn add_tag(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TagUsecase>>,
+    request: web::Json<TagRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .add_tag(&user.email, &request)
+        .map_err(ApiError::from)
+        .await?;
+    match result.situation {
+        TagSituation::Succeeded => Ok(HttpResponse::Created().json(result)),
+        _ => Ok(to_response(result)),
+    }
+}
+
+#[utoipa::path(
+    put,
+    params(("id" = i64, Path, description = "Tag id")),
+    request_body = TagRequest,
+    responses(
+        (status = 200, description = "The tag is renamed on every effort.", body = TagResult),
+        (status = 400, description = "The name is empty.", body = TagResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The tag is not found.", body = TagResult),
+        (status = 409, description = "Another tag has the name. Merge into it instead.", body = TagResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[put("/tags/{id}")]
+pub async fn rename_tag(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TagUsecase>>,
+    id: web::Path<i64>,
+    request: web::Json<TagRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .rename_tag(&user.email, id.into_inner(), &request)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    post,
+    params(("id" = i64, Path, description = "Id of the tag to merge and delete")),
+    request_body = TagMergeRequest,
+    responses(
+        (status = 200, description = "The efforts of the tag carry the other tag, and the tag is deleted.", body = TagResult),
+        (status = 400, description = "The tag would be merged into itself.", body = TagResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "Either tag is not found.", body = TagResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/tags/{id}/merge")]
+pub async fn merge_tag(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TagUsecase>>,
+    id: web::Path<i64>,
+    request: web::Json<TagMergeRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .merge_tag(&user.email, id.into_inner(), &request)
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    delete,
+    params(("id" = i64, Path, description = "Tag id")),
+    responses(
+        (status = 200, description = "The tag is deleted and removed from its efforts.", body = TagResult),
+        (status = 400, description = "The id is invalid.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The tag is not found.", body = TagResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/tags/{id}")]
+pub async fn delete_tag(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TagUsecase>>,
+    id: web::Path<i64>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .delete_tag(&user.email, id.into_inner())
+        .map_err(ApiError::from)
+        .await?;
+    Ok(to_response(result))
+}
+
+#[cfg(test)]
+mod tests {
+    mod merge_tag {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::tags::Tag;
+        use crate::dto::{TagMergeRequest, TagResult, TagSituation};
+        use crate::merge_tag;
+        use crate::usecases::tag_usecase::{MockTagUsecase, TagUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 統合先のタグを返す() {
+            let mut mock_usecase = MockTagUsecase::new();
+            mock_usecase
+                .expect_merge_tag()
+                .withf(|owner, id, request| {
+                    owner == "test@example.com" && *id == 1 && request.into == 2
+                })
+                .returning(|owner, _, request| {
+                    Ok(TagResult {
+                        situation: TagSituation::Succeeded,
+                        tag: Some(Tag {
+                            id: request.into,
+                            owner: owner.to_owned(),
+                            name: "rust".to_owned(),
+                        }),
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn TagUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(merge_tag),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/tags/1/merge")
+                .cookie(cookie)
+                .set_json(TagMergeRequest { into: 2 })
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let result: TagResult = test::read_body_json(resp).await;
+            assert_eq!(2, result.tag.unwrap().id);
+        }
+    }
+}
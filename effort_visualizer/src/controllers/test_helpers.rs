@@ -0,0 +1,40 @@
+use crate::controllers::extractors::CURRENT_USER;
+use crate::domain::users::User;
+use actix_http::Request;
+use actix_session::{storage::CookieSessionStore, Session, SessionMiddleware};
+use actix_web::{
+    cookie::{Cookie, Key},
+    dev::{Service, ServiceResponse},
+    post, test, HttpResponse,
+};
+
+pub fn session_middleware() -> SessionMiddleware<CookieSessionStore> {
+    SessionMiddleware::new(CookieSessionStore::default(), Key::from(&[0; 64]))
+}
+
+#[post("/test-login")]
+pub async fn test_login(session: Session) -> Result<HttpResponse, actix_web::Error> {
+    session.insert(
+        CURRENT_USER,
+        User {
+            email: "test@example.com".to_owned(),
+            user_name: "".to_owned(),
+            avatar_url: None,
+            timezone: "UTC".to_owned(),
+            locale: "en_US".to_owned(),
+            registered_date: chrono::Utc::now(),
+            updated_date: chrono::Utc::now(),
+        },
+    )?;
+    Ok(HttpResponse::Ok().finish())
+}
+
+/// Logs in through `test_login` and returns the session cookie to send with later requests.
+pub async fn login_cookie<S, B>(app: &S) -> Cookie<'static>
+where
+    S: Service<Request, Response = ServiceResponse<B>, Error = actix_web::Error>,
+{
+    let req = test::TestRequest::post().uri("/test-login").to_request();
+    let resp = test::call_service(app, req).await;
+    resp.response().cookies().next().unwrap().into_owned()
+}
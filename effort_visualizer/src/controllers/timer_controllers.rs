@@ -0,0 +1,264 @@
+use super::errors::ApiError;
+use super::event_controllers::log_push_failure;
+use super::extractors::AuthenticatedUser;
+use crate::domain::users::User;
+use crate::dto::{TimerRequest, TimerResult, TimerSituation};
+use crate::usecases::{event_usecase::EventUsecase, timer_usecase::TimerUsecase};
+use actix_web::{
+    get,
+    http::StatusCode,
+    post,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::Result;
+use futures::TryFutureExt;
+
+fn status_code(situation: &TimerSituation) -> StatusCode {
+    match situation {
+        TimerSituation::Succeeded => StatusCode::OK,
+        TimerSituation::NotFound => StatusCode::NOT_FOUND,
+        TimerSituation::AlreadyStarted
+        | TimerSituation::AlreadyPaused
+        | TimerSituation::AlreadyRunning
+        | TimerSituation::Conflict => StatusCode::CONFLICT,
+        TimerSituation::TitleIsEmpty
+        | TimerSituation::CategoryNotFound
+        | TimerSituation::TagIsEmpty
+        | TimerSituation::InvalidPomodoro => StatusCode::BAD_REQUEST,
+    }
+}
+
+/// Pushes the efforts a succeeded change recorded and the state it left the timer in.
+pub(super) async fn push(user: &User, events: &dyn EventUsecase, result: &TimerResult) {
+    if result.situation != TimerSituation::Succeeded {
+        return;
+    }
+    log_push_failure(
+        events
+            .efforts_created(&user.email, &user.timezone, &result.efforts)
+            .await,
+    );
+    log_push_failure(
+        events
+            .timer_changed(&user.email, result.timer.clone())
+            .await,
+    );
+}
+
+fn to_response(result: TimerResult) -> HttpResponse {
+    HttpResponse::build(status_code(&result.situation)).json(result)
+}
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "The timer of the current user and the time it has counted. Pomodoros completed since the last request are recorded and returned.", body = TimerResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No timer is started.", body = TimerResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/timer")]
+pub async fn get_timer(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TimerUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .get_timer(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    // Only pomodoros completed since the last request change anything.
+    if !result.efforts.is_empty() {
+        push(&user, events.as_ref().as_ref(), &result).await;
+    }
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    post,
+    request_body = TimerRequest,
+    responses(
+        (status = 201, description = "The timer is started.", body = TimerResult),
+        (status = 400, description = "The title or a tag is empty, the category is not found or the pomodoro settings are out of range.", body = TimerResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 409, description = "A timer is started already, e.g. in another tab. The response holds it.", body = TimerResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/timer/start")]
+pub async fn start_timer(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TimerUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+    request: web::Json<TimerRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .start_timer(&user.email, &request)
+        .map_err(ApiError::from)
+        .await?;
+    push(&user, events.as_ref().as_ref(), &result).await;
+    match result.situation {
+        TimerSituation::Succeeded => Ok(HttpResponse::Created().json(result)),
+        _ => Ok(to_response(result)),
+    }
+}
+
+#[utoipa::path(
+    post,
+    responses(
+        (status = 200, description = "The timer is paused.", body = TimerResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No timer is started.", body = TimerResult),
+        (status = 409, description = "The timer is paused already or was changed at the same time.", body = TimerResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/timer/pause")]
+pub async fn pause_timer(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TimerUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .pause_timer(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    push(&user, events.as_ref().as_ref(), &result).await;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    post,
+    responses(
+        (status = 200, description = "The timer is running again.", body = TimerResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No timer is started.", body = TimerResult),
+        (status = 409, description = "The timer is running already or was changed at the same time.", body = TimerResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/timer/resume")]
+pub async fn resume_timer(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TimerUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .resume_timer(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    push(&user, events.as_ref().as_ref(), &result).await;
+    Ok(to_response(result))
+}
+
+#[utoipa::path(
+    post,
+    responses(
+        (status = 200, description = "The timer is removed and the efforts it recorded are returned.", body = TimerResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "No timer is started.", body = TimerResult),
+        (status = 409, description = "The timer was changed or stopped at the same time.", body = TimerResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[post("/timer/stop")]
+pub async fn stop_timer(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn TimerUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .stop_timer(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    push(&user, events.as_ref().as_ref(), &result).await;
+    Ok(to_response(result))
+}
+
+#[cfg(test)]
+mod tests {
+    mod start_timer {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::dto::{TimerRequest, TimerResult, TimerSituation};
+        use crate::start_timer;
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use crate::usecases::timer_usecase::{MockTimerUsecase, TimerUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 既にタイマーがあるときステータス409を返す() {
+            let mut mock_usecase = MockTimerUsecase::new();
+            mock_usecase
+                .expect_start_timer()
+                .withf(|owner, request| owner == "test@example.com" && request.title == "Rust")
+                .returning(|_, _| {
+                    Ok(TimerResult {
+                        situation: TimerSituation::AlreadyStarted,
+                        timer: None,
+                        efforts: vec![],
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn TimerUsecase>);
+            // A timer that was not started has nothing to push.
+            let events = web::Data::new(Box::new(MockEventUsecase::new()) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(start_timer),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::post()
+                .uri("/timer/start")
+                .cookie(cookie)
+                .set_json(&TimerRequest {
+                    title: "Rust".to_owned(),
+                    ..Default::default()
+                })
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::CONFLICT, resp.status());
+        }
+    }
+
+    mod stop_timer {
+        use crate::controllers::test_helpers::session_middleware;
+        use crate::stop_timer;
+        use crate::usecases::timer_usecase::{MockTimerUsecase, TimerUsecase};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 未ログイン時ステータス401を返す() {
+            let usecase =
+                web::Data::new(Box::new(MockTimerUsecase::new()) as Box<dyn TimerUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(stop_timer),
+            )
+            .await;
+
+            let req = test::TestRequest::post().uri("/timer/stop").to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+    }
+}
@@ -0,0 +1,390 @@
+use std::io;
+
+use super::errors::ApiError;
+use super::event_controllers::log_push_failure;
+use super::extractors::{AuthenticatedUser, CURRENT_USER};
+use crate::dto::{ProfileRequest, ProfileSituation};
+use crate::helpers::correlation_id;
+use crate::usecases::event_usecase::EventUsecase;
+use crate::usecases::export_usecase::ExportUsecase;
+use crate::usecases::user_usecase::UserUsecase;
+use actix_session::Session;
+use actix_web::{
+    delete, get,
+    http::header::{ContentDisposition, DispositionParam, DispositionType},
+    patch, put,
+    web::{self, Data},
+    HttpResponse,
+};
+use anyhow::{anyhow, Result};
+use futures::{future, stream, StreamExt, TryFutureExt};
+use tokio_util::io::ReaderStream;
+use tracing::error;
+
+/// Bytes of the archive buffered between the task writing it and the response.
+const EXPORT_BUFFER_SIZE: usize = 64 * 1024;
+
+#[utoipa::path(
+    patch,
+    request_body = ProfileRequest,
+    responses(
+        (status = 200, description = "The profile is updated.", body = ProfileResult),
+        (status = 400, description = "The user name is empty, or the avatar URL, time zone or locale is invalid.", body = ProfileResult),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The user is not registered anymore.", body = ProfileResult),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[patch("/me")]
+pub async fn update_profile(
+    session: Session,
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn UserUsecase>>,
+    profile: web::Json<ProfileRequest>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let result = usecase
+        .update_profile(&user.email, &profile)
+        .map_err(ApiError::from)
+        .await?;
+    match result.situation {
+        ProfileSituation::Succeeded => {
+            // The session keeps a copy of the user, which `/me` returns.
+            match &result.user {
+                Some(user) => session.insert(CURRENT_USER, user)?,
+                None => return Err(ApiError::Internal(anyhow!("Missing user")).into()),
+            }
+            Ok(HttpResponse::Ok().json(result))
+        }
+        ProfileSituation::UserNameIsEmpty
+        | ProfileSituation::InvalidAvatarUrl
+        | ProfileSituation::InvalidTimezone
+        | ProfileSituation::InvalidLocale => Ok(HttpResponse::BadRequest().json(result)),
+        ProfileSituation::NotFound => Ok(HttpResponse::NotFound().json(result)),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    responses(
+        (status = 204, description = "The user and everything they own are deleted, and the session is cleared."),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The user is not registered anymore.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/me")]
+pub async fn delete_account(
+    session: Session,
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn UserUsecase>>,
+    events: Data<Box<dyn EventUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let deleted = usecase
+        .delete_account(&user.email)
+        .map_err(ApiError::from)
+        .await?;
+    session.purge();
+    // Closes the event connections of every session, which were deleted with the user.
+    log_push_failure(events.session_ended(&user.email).await);
+    if !deleted {
+        return Err(ApiError::NotFound("The user is not registered.".to_owned()).into());
+    }
+    Ok(HttpResponse::NoContent().finish())
+}
+
+#[utoipa::path(
+    put,
+    responses(
+        (status = 200, description = "The heatmap, calendar and streaks of the current user are public under a new slug. The previous slug stops working.", body = PublicCalendar),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The user is not registered anymore.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[put("/me/public-calendar")]
+pub async fn publish_calendar(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn UserUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    match usecase
+        .publish_calendar(&user.email)
+        .map_err(ApiError::from)
+        .await?
+    {
+        Some(calendar) => Ok(HttpResponse::Ok().json(calendar)),
+        None => Err(ApiError::NotFound("The user is not registered.".to_owned()).into()),
+    }
+}
+
+#[utoipa::path(
+    delete,
+    responses(
+        (status = 204, description = "The heatmap, calendar and streaks of the current user are private."),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 404, description = "The user is not registered anymore.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 503, description = "The database is unavailable.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[delete("/me/public-calendar")]
+pub async fn unpublish_calendar(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn UserUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    if usecase
+        .unpublish_calendar(&user.email)
+        .map_err(ApiError::from)
+        .await?
+    {
+        Ok(HttpResponse::NoContent().finish())
+    } else {
+        Err(ApiError::NotFound("The user is not registered.".to_owned()).into())
+    }
+}
+
+#[utoipa::path(
+    get,
+    responses(
+        (status = 200, description = "A ZIP archive of everything stored about the current user.", content_type = "application/zip", body = String),
+        (status = 401, description = "Not logged in.", body = ProblemDetails, content_type = "application/problem+json"),
+        (status = 500, description = "Internal error.", body = ProblemDetails, content_type = "application/problem+json")
+    ),
+)]
+#[get("/me/export")]
+pub async fn export_data(
+    user: AuthenticatedUser,
+    usecase: Data<Box<dyn ExportUsecase>>,
+) -> Result<HttpResponse, actix_web::Error> {
+    let (writer, reader) = tokio::io::duplex(EXPORT_BUFFER_SIZE);
+    let user_email = user.email.to_owned();
+    let export =
+        actix_web::rt::spawn(async move { usecase.export(&user_email, Box::new(writer)).await });
+
+    // The status is sent before the archive is complete, so a failure can only abort the
+    // response. An error at the end of the body keeps the client from taking a truncated
+    // archive for a finished one.
+    let correlation_id = correlation_id::current();
+    let outcome = stream::once(async move {
+        let e = match export.await {
+            Ok(Ok(())) => return None,
+            Ok(Err(e)) => e,
+            Err(e) => anyhow!(e),
+        };
+        error!("[{}] The export failed: {:?}", correlation_id, e);
+        Some(Err(io::Error::other("The export failed.")))
+    })
+    .filter_map(future::ready);
+
+    Ok(HttpResponse::Ok()
+        .content_type("application/zip")
+        .insert_header(ContentDisposition {
+            disposition: DispositionType::Attachment,
+            parameters: vec![DispositionParam::Filename(
+                "effort_visualizer-export.zip".to_owned(),
+            )],
+        })
+        .streaming(ReaderStream::new(reader).chain(outcome)))
+}
+
+#[cfg(test)]
+mod tests {
+    mod update_profile {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::domain::users::User;
+        use crate::dto::{ProfileRequest, ProfileResult, ProfileSituation};
+        use crate::usecases::user_usecase::{MockUserUsecase, UserUsecase};
+        use crate::{me, update_profile};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 更新後のユーザをセッションに保存する() {
+            let mut mock_usecase = MockUserUsecase::new();
+            mock_usecase
+                .expect_update_profile()
+                .withf(|user_email, request| {
+                    user_email == "test@example.com"
+                        && request.timezone.as_deref() == Some("Asia/Tokyo")
+                })
+                .returning(|_, _| {
+                    Ok(ProfileResult {
+                        situation: ProfileSituation::Succeeded,
+                        user: Some(User {
+                            email: "test@example.com".to_owned(),
+                            user_name: "test".to_owned(),
+                            avatar_url: None,
+                            timezone: "Asia/Tokyo".to_owned(),
+                            locale: "en_US".to_owned(),
+                            registered_date: chrono::Utc::now(),
+                            updated_date: chrono::Utc::now(),
+                        }),
+                        description: None,
+                    })
+                });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn UserUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(me)
+                    .service(update_profile),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::patch()
+                .uri("/me")
+                .cookie(cookie)
+                .set_json(&ProfileRequest {
+                    timezone: Some("Asia/Tokyo".to_owned()),
+                    ..Default::default()
+                })
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            let cookie = resp.response().cookies().next().unwrap().into_owned();
+
+            let req = test::TestRequest::get()
+                .uri("/me")
+                .cookie(cookie)
+                .to_request();
+            let user: User = test::call_and_read_body_json(&app, req).await;
+            assert_eq!("Asia/Tokyo", user.timezone);
+        }
+
+        #[actix_web::test]
+        async fn ユーザ名が空のときステータス400を返す() {
+            let mut mock_usecase = MockUserUsecase::new();
+            mock_usecase.expect_update_profile().returning(|_, _| {
+                Ok(ProfileResult {
+                    situation: ProfileSituation::UserNameIsEmpty,
+                    user: None,
+                    description: None,
+                })
+            });
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn UserUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(update_profile),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::patch()
+                .uri("/me")
+                .cookie(cookie)
+                .set_json(&ProfileRequest {
+                    user_name: Some("".to_owned()),
+                    ..Default::default()
+                })
+                .to_request();
+
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::BAD_REQUEST, resp.status());
+        }
+    }
+
+    mod delete_account {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::usecases::event_usecase::{EventUsecase, MockEventUsecase};
+        use crate::usecases::user_usecase::{MockUserUsecase, UserUsecase};
+        use crate::{delete_account, me};
+        use actix_web::{http, test, web, App};
+
+        #[actix_web::test]
+        async fn 削除後はセッションが破棄される() {
+            let mut mock_usecase = MockUserUsecase::new();
+            mock_usecase
+                .expect_delete_account()
+                .withf(|user_email| user_email == "test@example.com")
+                .times(1)
+                .returning(|_| Ok(true));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn UserUsecase>);
+            let mut mock_events = MockEventUsecase::new();
+            mock_events
+                .expect_session_ended()
+                .withf(|owner| owner == "test@example.com")
+                .times(1)
+                .returning(|_| Ok(()));
+            let events = web::Data::new(Box::new(mock_events) as Box<dyn EventUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .app_data(events.clone())
+                    .service(test_login)
+                    .service(me)
+                    .service(delete_account),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::delete()
+                .uri("/me")
+                .cookie(cookie)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::NO_CONTENT, resp.status());
+            let cookie = resp.response().cookies().next().unwrap().into_owned();
+
+            let req = test::TestRequest::get()
+                .uri("/me")
+                .cookie(cookie)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::UNAUTHORIZED, resp.status());
+        }
+    }
+    mod export_data {
+        use crate::controllers::test_helpers::{login_cookie, session_middleware, test_login};
+        use crate::export_data;
+        use crate::usecases::export_usecase::{ExportUsecase, MockExportUsecase};
+        use actix_web::{body, http, test, web, App};
+        use anyhow::anyhow;
+
+        #[actix_web::test]
+        async fn 書き出しに失敗したときレスポンスを中断する() {
+            let mut mock_usecase = MockExportUsecase::new();
+            mock_usecase
+                .expect_export()
+                .withf(|user_email, _| user_email == "test@example.com")
+                .returning(|_, _| Err(anyhow!("The database went away.")));
+            let usecase = web::Data::new(Box::new(mock_usecase) as Box<dyn ExportUsecase>);
+
+            let app = test::init_service(
+                App::new()
+                    .wrap(session_middleware())
+                    .app_data(usecase.clone())
+                    .service(test_login)
+                    .service(export_data),
+            )
+            .await;
+
+            let cookie = login_cookie(&app).await;
+
+            let req = test::TestRequest::get()
+                .uri("/me/export")
+                .cookie(cookie)
+                .to_request();
+            let resp = test::call_service(&app, req).await;
+            assert_eq!(http::StatusCode::OK, resp.status());
+            assert_eq!(
+                "application/zip",
+                resp.headers().get(http::header::CONTENT_TYPE).unwrap()
+            );
+            assert!(body::to_bytes(resp.into_body()).await.is_err());
+        }
+    }
+}
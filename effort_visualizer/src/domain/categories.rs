@@ -0,0 +1,22 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A topic the user files efforts under. Each effort has at most one category.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct Category {
+    pub id: i64,
+    pub owner: String,
+    /// Unique among the owner's categories.
+    pub name: String,
+    /// Hex colour such as `#40c463`.
+    pub colour: String,
+    /// Name of the icon, or an emoji.
+    pub icon: Option<String>,
+}
+
+/// Whether `colour` is a hex colour of the form `#rrggbb`.
+pub fn is_valid_colour(colour: &str) -> bool {
+    colour.len() == 7
+        && colour.starts_with('#')
+        && colour[1..].chars().all(|c| c.is_ascii_hexdigit())
+}
@@ -0,0 +1,101 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct Effort {
+    pub id: i64,
+    pub owner: String,
+    pub title: String,
+    pub duration_seconds: i64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub notes: Option<String>,
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    /// Names of the tags, in alphabetical order.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Whether the effort is a work interval a pomodoro timer completed.
+    #[serde(default)]
+    pub pomodoro: bool,
+}
+
+/// An effort read from an uploaded file, identified by a hash of the row it was read from.
+#[derive(Clone, Debug, PartialEq)]
+pub struct ImportedEffort {
+    pub effort: Effort,
+    pub import_hash: String,
+}
+
+/// How the conditions of an `EffortFilter` combine.
+#[derive(Clone, Copy, Debug, Default, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum FilterMatch {
+    /// Efforts meeting every condition.
+    #[default]
+    All,
+    /// Efforts meeting at least one condition.
+    Any,
+}
+
+/// Narrows efforts down to categories and tags. Every category and every tag is a condition
+/// of its own, and an empty filter keeps every effort.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct EffortFilter {
+    pub category_ids: Vec<i64>,
+    pub tags: Vec<String>,
+    pub match_mode: FilterMatch,
+}
+
+impl EffortFilter {
+    /// Reads the comma separated category ids and tag names of a query.
+    pub fn parse(
+        categories: Option<&str>,
+        tags: Option<&str>,
+        match_mode: Option<FilterMatch>,
+    ) -> Result<Self, String> {
+        let category_ids = split_list(categories)
+            .map(|id| {
+                id.parse()
+                    .map_err(|_| format!("`{id}` is not a category id."))
+            })
+            .collect::<Result<_, _>>()?;
+        Ok(Self {
+            category_ids,
+            tags: split_list(tags).map(str::to_owned).collect(),
+            match_mode: match_mode.unwrap_or_default(),
+        })
+    }
+}
+
+fn split_list(list: Option<&str>) -> impl Iterator<Item = &str> {
+    list.unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{EffortFilter, FilterMatch};
+
+    #[test]
+    fn カンマ区切りのカテゴリとタグを読む() {
+        let filter = EffortFilter::parse(Some("1, 2"), Some("rust,,books "), None).unwrap();
+
+        assert_eq!(
+            EffortFilter {
+                category_ids: vec![1, 2],
+                tags: vec!["rust".to_owned(), "books".to_owned()],
+                match_mode: FilterMatch::All,
+            },
+            filter
+        );
+    }
+
+    #[test]
+    fn 数字でないカテゴリは読まない() {
+        assert!(EffortFilter::parse(Some("study"), None, Some(FilterMatch::Any)).is_err());
+    }
+}
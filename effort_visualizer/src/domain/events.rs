@@ -0,0 +1,83 @@
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum EventKind {
+    /// An effort was recorded. The data is the effort.
+    #[serde(rename = "effort.created")]
+    EffortCreated,
+    /// The timer changed, or is still running. The data is the state of the timer, or null
+    /// once it is stopped.
+    #[serde(rename = "timer.tick")]
+    TimerTick,
+    /// A goal was reached within a period. The data is the goal and the period.
+    #[serde(rename = "goal.completed")]
+    GoalCompleted,
+    /// A session of the owner ended by logging out, being revoked or the account being
+    /// deleted. Connections are closed after it, and clients still logged in connect again.
+    /// The data is null. It is never logged.
+    #[serde(rename = "session.ended")]
+    SessionEnded,
+}
+
+/// A change pushed to every client `owner` has connected.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct UserEvent {
+    /// Position in the owner's event log, by which a client resumes. Events that are not
+    /// logged, such as the ticks of a running timer, have none.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub id: Option<i64>,
+    pub owner: String,
+    #[serde(rename = "type")]
+    pub kind: EventKind,
+    /// Null when the data was too large to be pushed. Clients fetch it again then.
+    #[schema(value_type = Object)]
+    pub data: serde_json::Value,
+}
+
+/// What a client resuming after an event has missed.
+#[derive(Clone, Debug, PartialEq)]
+pub enum MissedEvents {
+    /// The events logged since, the oldest first.
+    Logged(Vec<UserEvent>),
+    /// Some of them were dropped from the log already, so the client has to fetch its data
+    /// again and resume from `last_id`, the newest event logged.
+    Dropped { last_id: i64 },
+}
+
+impl EventKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            EventKind::EffortCreated => "effort.created",
+            EventKind::TimerTick => "timer.tick",
+            EventKind::GoalCompleted => "goal.completed",
+            EventKind::SessionEnded => "session.ended",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [
+            EventKind::EffortCreated,
+            EventKind::TimerTick,
+            EventKind::GoalCompleted,
+            EventKind::SessionEnded,
+        ]
+        .into_iter()
+        .find(|kind| kind.as_str() == name)
+    }
+}
+
+impl UserEvent {
+    pub fn new(
+        owner: &str,
+        kind: EventKind,
+        data: &impl Serialize,
+    ) -> Result<Self, serde_json::Error> {
+        Ok(Self {
+            id: None,
+            owner: owner.to_owned(),
+            kind,
+            data: serde_json::to_value(data)?,
+        })
+    }
+}
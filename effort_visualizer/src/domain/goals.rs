@@ -0,0 +1,266 @@
+use chrono::{DateTime, Datelike, Duration, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// How often a goal starts over. Periods are calendar days, weeks from Monday and calendar
+/// months of the user's time zone, or runs of `period_days` days from the day the goal starts.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum GoalPeriod {
+    Daily,
+    Weekly,
+    Monthly,
+    Custom,
+}
+
+impl GoalPeriod {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalPeriod::Daily => "daily",
+            GoalPeriod::Weekly => "weekly",
+            GoalPeriod::Monthly => "monthly",
+            GoalPeriod::Custom => "custom",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [
+            GoalPeriod::Daily,
+            GoalPeriod::Weekly,
+            GoalPeriod::Monthly,
+            GoalPeriod::Custom,
+        ]
+        .into_iter()
+        .find(|period| period.as_str() == name)
+    }
+}
+
+/// What a goal counts within each period.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum GoalMetric {
+    /// Seconds spent.
+    Duration,
+    /// Number of efforts.
+    Count,
+    /// Number of days with at least one effort.
+    DistinctDays,
+}
+
+impl GoalMetric {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            GoalMetric::Duration => "duration",
+            GoalMetric::Count => "count",
+            GoalMetric::DistinctDays => "distinct_days",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [
+            GoalMetric::Duration,
+            GoalMetric::Count,
+            GoalMetric::DistinctDays,
+        ]
+        .into_iter()
+        .find(|metric| metric.as_str() == name)
+    }
+}
+
+/// A target such as "10 hours of Rust per week" or "study 5 days a month".
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct Goal {
+    pub id: i64,
+    pub owner: String,
+    pub title: String,
+    pub metric: GoalMetric,
+    /// Amount to reach in every period, in the unit of `metric`.
+    pub target: i64,
+    pub period: GoalPeriod,
+    /// Length of a `custom` period in days.
+    pub period_days: Option<i32>,
+    /// Efforts before this day don't count.
+    pub starts_on: NaiveDate,
+    /// Only efforts of this category count when it is set.
+    pub category_id: Option<i64>,
+}
+
+/// A period in which a goal was reached.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct GoalCompletion {
+    pub goal_id: i64,
+    pub first_day: NaiveDate,
+    pub completed_at: DateTime<Utc>,
+}
+
+/// The efforts started within a period, summed up.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct PeriodTotals {
+    pub total_seconds: i64,
+    pub effort_count: i64,
+    pub distinct_days: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct GoalPeriodProgress {
+    pub first_day: NaiveDate,
+    pub last_day: NaiveDate,
+    /// Amount reached, in the unit of the goal's metric.
+    pub value: i64,
+    /// `value` divided by the target. Above 1 when the target is exceeded.
+    pub completion: f64,
+    pub completed: bool,
+}
+
+impl Goal {
+    /// The first and the last day of the period `date` falls in.
+    pub fn period_of(&self, date: NaiveDate) -> (NaiveDate, NaiveDate) {
+        match self.period {
+            GoalPeriod::Daily => (date, date),
+            GoalPeriod::Weekly => {
+                let first_day = date - Duration::days(date.weekday().num_days_from_monday().into());
+                (first_day, first_day + Duration::days(6))
+            }
+            GoalPeriod::Monthly => {
+                let first_day = date - Duration::days((date.day() - 1).into());
+                let next_month = first_day + Duration::days(31);
+                let next_first_day = next_month - Duration::days((next_month.day() - 1).into());
+                (first_day, next_first_day - Duration::days(1))
+            }
+            GoalPeriod::Custom => {
+                let days = self.period_days.unwrap_or(1).max(1) as i64;
+                let index = (date - self.starts_on).num_days().div_euclid(days);
+                let first_day = self.starts_on + Duration::days(index * days);
+                (first_day, first_day + Duration::days(days - 1))
+            }
+        }
+    }
+
+    /// The period `today` falls in followed by up to `count - 1` periods before it. Periods
+    /// ending before the goal starts are left out, and the first one is cut to `starts_on`.
+    pub fn periods(&self, today: NaiveDate, count: usize) -> Vec<(NaiveDate, NaiveDate)> {
+        let mut periods = Vec::new();
+        let mut date = today;
+        while periods.len() < count {
+            let (first_day, last_day) = self.period_of(date);
+            if last_day < self.starts_on {
+                break;
+            }
+            periods.push((first_day.max(self.starts_on), last_day));
+            date = first_day - Duration::days(1);
+        }
+        periods
+    }
+
+    pub fn progress(
+        &self,
+        first_day: NaiveDate,
+        last_day: NaiveDate,
+        totals: &PeriodTotals,
+    ) -> GoalPeriodProgress {
+        let value = match self.metric {
+            GoalMetric::Duration => totals.total_seconds,
+            GoalMetric::Count => totals.effort_count,
+            GoalMetric::DistinctDays => totals.distinct_days,
+        };
+        GoalPeriodProgress {
+            first_day,
+            last_day,
+            value,
+            completion: value as f64 / self.target as f64,
+            completed: value >= self.target,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Goal, GoalMetric, GoalPeriod, PeriodTotals};
+    use chrono::NaiveDate;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    fn goal(period: GoalPeriod, period_days: Option<i32>) -> Goal {
+        Goal {
+            id: 1,
+            owner: "test@example.com".to_owned(),
+            title: "Rust".to_owned(),
+            metric: GoalMetric::DistinctDays,
+            target: 5,
+            period,
+            period_days,
+            starts_on: date(2023, 5, 3),
+            category_id: None,
+        }
+    }
+
+    #[test]
+    fn 週は月曜日から始まる() {
+        assert_eq!(
+            (date(2023, 5, 29), date(2023, 6, 4)),
+            goal(GoalPeriod::Weekly, None).period_of(date(2023, 6, 1))
+        );
+    }
+
+    #[test]
+    fn 月の最終日はうるう年を考慮する() {
+        let goal = goal(GoalPeriod::Monthly, None);
+
+        assert_eq!(
+            (date(2024, 2, 1), date(2024, 2, 29)),
+            goal.period_of(date(2024, 2, 15))
+        );
+        assert_eq!(
+            (date(2023, 12, 1), date(2023, 12, 31)),
+            goal.period_of(date(2023, 12, 31))
+        );
+    }
+
+    #[test]
+    fn 任意の期間は開始日から数える() {
+        let goal = goal(GoalPeriod::Custom, Some(10));
+
+        assert_eq!(
+            (date(2023, 5, 13), date(2023, 5, 22)),
+            goal.period_of(date(2023, 5, 20))
+        );
+        assert_eq!(
+            (date(2023, 4, 23), date(2023, 5, 2)),
+            goal.period_of(date(2023, 5, 1))
+        );
+    }
+
+    #[test]
+    fn 開始日より前の期間は含めない() {
+        let goal = goal(GoalPeriod::Weekly, None);
+
+        assert_eq!(
+            vec![
+                (date(2023, 5, 15), date(2023, 5, 21)),
+                (date(2023, 5, 8), date(2023, 5, 14)),
+                (date(2023, 5, 3), date(2023, 5, 7)),
+            ],
+            goal.periods(date(2023, 5, 17), 8)
+        );
+        assert_eq!(1, goal.periods(date(2023, 5, 17), 1).len());
+    }
+
+    #[test]
+    fn 指標の値を目標と比べる() {
+        let progress = goal(GoalPeriod::Monthly, None).progress(
+            date(2023, 5, 3),
+            date(2023, 5, 31),
+            &PeriodTotals {
+                total_seconds: 36000,
+                effort_count: 8,
+                distinct_days: 6,
+            },
+        );
+
+        assert_eq!(6, progress.value);
+        assert_eq!(1.2, progress.completion);
+        assert!(progress.completed);
+    }
+}
@@ -0,0 +1,39 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum HeatmapBucket {
+    Day,
+    Week,
+}
+
+impl HeatmapBucket {
+    /// The field name understood by Postgres `date_trunc`.
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HeatmapBucket::Day => "day",
+            HeatmapBucket::Week => "week",
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct EffortAggregate {
+    pub bucket_start: NaiveDate,
+    pub total_seconds: i64,
+    pub effort_count: i64,
+}
+
+/// Maps a bucket total to a level from 0 to 4 using the quartiles of the
+/// user's own history, so that every user gets a fully used colour scale.
+pub fn intensity_level(total_seconds: i64, quartiles: &[f64]) -> u8 {
+    if total_seconds <= 0 {
+        return 0;
+    }
+    1 + quartiles
+        .iter()
+        .filter(|quartile| total_seconds as f64 > **quartile)
+        .count() as u8
+}
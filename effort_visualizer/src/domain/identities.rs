@@ -0,0 +1,13 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// An account at an identity provider that can be used to sign in as `user_email`.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct UserIdentity {
+    pub provider: String,
+    /// The subject the provider identifies the account with.
+    pub external_id: String,
+    pub user_email: String,
+    pub linked_at: DateTime<Utc>,
+}
@@ -1 +1,12 @@
+pub mod categories;
+pub mod efforts;
+pub mod events;
+pub mod goals;
+pub mod heatmap;
+pub mod identities;
+pub mod pomodoros;
+pub mod sessions;
+pub mod streaks;
+pub mod tags;
+pub mod timers;
 pub mod users;
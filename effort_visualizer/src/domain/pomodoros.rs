@@ -0,0 +1,150 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Lengths of the intervals of a pomodoro timer. A long break follows every `cycles` work
+/// intervals, and a short break every other one. Missing lengths default to 25 minutes of
+/// work, 5 and 15 minute breaks and 4 cycles.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(default)]
+pub struct PomodoroSettings {
+    pub work_seconds: i32,
+    pub short_break_seconds: i32,
+    pub long_break_seconds: i32,
+    /// Work intervals up to a long break.
+    pub cycles: i32,
+}
+
+impl Default for PomodoroSettings {
+    fn default() -> Self {
+        Self {
+            work_seconds: 25 * 60,
+            short_break_seconds: 5 * 60,
+            long_break_seconds: 15 * 60,
+            cycles: 4,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum PomodoroPhaseKind {
+    Work,
+    ShortBreak,
+    LongBreak,
+}
+
+/// An interval of a pomodoro timer, in seconds counted by the timer.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct PomodoroPhase {
+    pub kind: PomodoroPhaseKind,
+    /// Number of the work interval this phase is or follows, from 1.
+    pub pomodoro: i64,
+    pub start_seconds: i64,
+    pub end_seconds: i64,
+}
+
+impl PomodoroSettings {
+    /// Seconds from the start of a work interval following a long break to the end of the
+    /// next long break.
+    pub fn round_seconds(&self) -> i64 {
+        let cycles = i64::from(self.cycles);
+        cycles * i64::from(self.work_seconds)
+            + (cycles - 1) * i64::from(self.short_break_seconds)
+            + i64::from(self.long_break_seconds)
+    }
+
+    /// The phase the timer is in once it has counted `elapsed_seconds`.
+    pub fn phase_at(&self, elapsed_seconds: i64) -> PomodoroPhase {
+        let work = i64::from(self.work_seconds);
+        let short_break = i64::from(self.short_break_seconds);
+        let long_break = i64::from(self.long_break_seconds);
+        let cycles = i64::from(self.cycles);
+        let round = self.round_seconds();
+        let rounds = elapsed_seconds.div_euclid(round);
+        let mut start = rounds * round;
+        for cycle in 0..cycles {
+            let pomodoro = rounds * cycles + cycle + 1;
+            let (kind, length) = if elapsed_seconds < start + work {
+                (PomodoroPhaseKind::Work, work)
+            } else if cycle + 1 < cycles {
+                start += work;
+                if elapsed_seconds >= start + short_break {
+                    start += short_break;
+                    continue;
+                }
+                (PomodoroPhaseKind::ShortBreak, short_break)
+            } else {
+                start += work;
+                (PomodoroPhaseKind::LongBreak, long_break)
+            };
+            return PomodoroPhase {
+                kind,
+                pomodoro,
+                start_seconds: start,
+                end_seconds: start + length,
+            };
+        }
+        unreachable!("A round ends with a long break.")
+    }
+}
+
+/// A work interval stopped before it was completed.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct PomodoroInterruption {
+    pub owner: String,
+    pub title: String,
+    pub started_at: DateTime<Utc>,
+    pub interrupted_at: DateTime<Utc>,
+    /// Seconds of work counted before the interruption.
+    pub worked_seconds: i64,
+}
+
+/// Pomodoros started on a day of the user's time zone.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct PomodoroDay {
+    pub date: NaiveDate,
+    pub completed: i64,
+    /// Work intervals stopped before they were completed.
+    pub abandoned: i64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{PomodoroPhaseKind, PomodoroSettings};
+
+    #[test]
+    fn 規定回数の作業の後に長い休憩を挟む() {
+        let settings = PomodoroSettings {
+            work_seconds: 10,
+            short_break_seconds: 2,
+            long_break_seconds: 5,
+            cycles: 2,
+        };
+        let phases: Vec<_> = [0, 9, 10, 12, 22, 26, 27]
+            .into_iter()
+            .map(|elapsed| {
+                let phase = settings.phase_at(elapsed);
+                (
+                    phase.kind,
+                    phase.pomodoro,
+                    phase.start_seconds,
+                    phase.end_seconds,
+                )
+            })
+            .collect();
+
+        assert_eq!(
+            vec![
+                (PomodoroPhaseKind::Work, 1, 0, 10),
+                (PomodoroPhaseKind::Work, 1, 0, 10),
+                (PomodoroPhaseKind::ShortBreak, 1, 10, 12),
+                (PomodoroPhaseKind::Work, 2, 12, 22),
+                (PomodoroPhaseKind::LongBreak, 2, 22, 27),
+                (PomodoroPhaseKind::LongBreak, 2, 22, 27),
+                (PomodoroPhaseKind::Work, 3, 27, 37),
+            ],
+            phases
+        );
+    }
+}
@@ -0,0 +1,54 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A server-side session as shown to its owner. The session key itself is never exposed.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct UserSession {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub updated_at: DateTime<Utc>,
+    pub expires_at: DateTime<Utc>,
+}
+
+/// Why a session ended.
+#[derive(Clone, Copy, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SessionEndReason {
+    /// The user logged out, or logged in again and got a new session.
+    LoggedOut,
+    /// The user revoked it from another session.
+    Revoked,
+    Expired,
+}
+
+impl SessionEndReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SessionEndReason::LoggedOut => "logged_out",
+            SessionEndReason::Revoked => "revoked",
+            SessionEndReason::Expired => "expired",
+        }
+    }
+
+    pub fn parse(name: &str) -> Option<Self> {
+        [
+            SessionEndReason::LoggedOut,
+            SessionEndReason::Revoked,
+            SessionEndReason::Expired,
+        ]
+        .into_iter()
+        .find(|reason| reason.as_str() == name)
+    }
+}
+
+/// A server-side session of the user, whether it is still active or has ended.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub struct SessionRecord {
+    pub id: i64,
+    pub created_at: DateTime<Utc>,
+    pub last_seen_at: DateTime<Utc>,
+    /// None while the session is active.
+    pub ended_at: Option<DateTime<Utc>>,
+    pub end_reason: Option<SessionEndReason>,
+}
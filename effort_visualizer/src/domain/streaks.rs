@@ -0,0 +1,242 @@
+use chrono::{Datelike, Duration, NaiveDate, Weekday};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// Days with efforts in a row. Rest days in between don't break it.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct Streak {
+    pub first_day: NaiveDate,
+    pub last_day: NaiveDate,
+    /// Number of days with at least one effort.
+    pub active_days: i64,
+}
+
+/// Reads comma separated weekdays such as `sat,sun` or `Saturday, Sunday`.
+pub fn parse_rest_days(rest_days: Option<&str>) -> Result<Vec<Weekday>, String> {
+    rest_days
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|day| !day.is_empty())
+        .map(|day| {
+            day.parse()
+                .map_err(|_| format!("`{day}` is not a weekday."))
+        })
+        .collect()
+}
+
+/// Splits the ordered effort days into streaks, the oldest first.
+pub fn streaks(days: &[NaiveDate], rest_days: &[Weekday]) -> Vec<Streak> {
+    let mut streaks: Vec<Streak> = Vec::new();
+    for day in days {
+        match streaks.last_mut() {
+            Some(streak) if only_rest_days_between(streak.last_day, *day, rest_days) => {
+                streak.last_day = *day;
+                streak.active_days += 1;
+            }
+            _ => streaks.push(Streak {
+                first_day: *day,
+                last_day: *day,
+                active_days: 1,
+            }),
+        }
+    }
+    streaks
+}
+
+/// The latest streak, unless a day other than a rest day has passed without an effort since.
+/// Today doesn't break a streak, as there is still time for an effort.
+pub fn current_streak(
+    streaks: &[Streak],
+    today: NaiveDate,
+    rest_days: &[Weekday],
+) -> Option<Streak> {
+    streaks
+        .last()
+        .filter(|streak| {
+            streak.last_day >= today || only_rest_days_between(streak.last_day, today, rest_days)
+        })
+        .cloned()
+}
+
+/// The streak with the most active days. Of equally long ones, the latest.
+pub fn longest_streak(streaks: &[Streak]) -> Option<Streak> {
+    // `max_by_key` returns the last of equal elements.
+    streaks
+        .iter()
+        .max_by_key(|streak| streak.active_days)
+        .cloned()
+}
+
+/// Whether every day after `from` and before `to` is a rest day.
+fn only_rest_days_between(from: NaiveDate, to: NaiveDate, rest_days: &[Weekday]) -> bool {
+    let mut day = from + Duration::days(1);
+    while day < to {
+        if !rest_days.contains(&day.weekday()) {
+            return false;
+        }
+        day += Duration::days(1);
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::BTreeSet;
+
+    use super::{current_streak, longest_streak, streaks, Streak};
+    use chrono::{DateTime, Datelike, Duration, NaiveDate, TimeZone, Utc, Weekday};
+    use chrono_tz::Tz;
+    use proptest::prelude::*;
+
+    fn date(year: i32, month: u32, day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(year, month, day).unwrap()
+    }
+
+    /// The days of `timezone` the efforts started on, as `find_effort_days` reads them.
+    fn effort_days(started_at: &[DateTime<Utc>], timezone: Tz) -> Vec<NaiveDate> {
+        started_at
+            .iter()
+            .map(|instant| instant.with_timezone(&timezone).date_naive())
+            .collect::<BTreeSet<_>>()
+            .into_iter()
+            .collect()
+    }
+
+    #[test]
+    fn 休息日は連続記録を途切れさせない() {
+        // 2023-05-05 is a Friday and 2023-05-08 a Monday.
+        let days = [date(2023, 5, 4), date(2023, 5, 5), date(2023, 5, 8)];
+
+        assert_eq!(1, streaks(&days, &[Weekday::Sat, Weekday::Sun]).len());
+        assert_eq!(2, streaks(&days, &[]).len());
+    }
+
+    #[test]
+    fn 今日の努力がなくても現在の連続記録は続く() {
+        let history = streaks(&[date(2023, 5, 3), date(2023, 5, 4)], &[]);
+
+        assert_eq!(
+            Some(Streak {
+                first_day: date(2023, 5, 3),
+                last_day: date(2023, 5, 4),
+                active_days: 2,
+            }),
+            current_streak(&history, date(2023, 5, 5), &[])
+        );
+        assert_eq!(None, current_streak(&history, date(2023, 5, 6), &[]));
+    }
+
+    #[test]
+    fn 同じ長さの連続記録は新しい方を最長とする() {
+        let history = streaks(&[date(2023, 5, 1), date(2023, 5, 3), date(2023, 5, 5)], &[]);
+
+        assert_eq!(
+            date(2023, 5, 5),
+            longest_streak(&history).unwrap().first_day
+        );
+    }
+
+    fn time_zones() -> impl Strategy<Value = chrono_tz::Tz> {
+        prop::sample::select(vec![
+            chrono_tz::UTC,
+            chrono_tz::America::New_York,
+            chrono_tz::America::Santiago,
+            chrono_tz::Europe::London,
+            chrono_tz::Australia::Lord_Howe,
+            chrono_tz::Asia::Tokyo,
+            chrono_tz::Pacific::Apia,
+        ])
+    }
+
+    fn rest_days() -> impl Strategy<Value = Vec<Weekday>> {
+        prop::collection::vec(
+            prop::sample::select(vec![
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+                Weekday::Sat,
+                Weekday::Sun,
+            ]),
+            0..3,
+        )
+    }
+
+    proptest! {
+        #[test]
+        fn 毎日同じ時刻の努力は夏時間やうるう日をまたいでも一つの連続記録になる(
+            timezone in time_zones(),
+            // Spans the DST transitions of March, April, September and October, and 2024-02-29.
+            first_day in (0i64..400).prop_map(|offset| date(2023, 9, 1) + Duration::days(offset)),
+            length in 1i64..200,
+            minutes in 0u32..(24 * 60),
+        ) {
+            let started_at: Vec<_> = (0..length)
+                .map(|offset| {
+                    let local = (first_day + Duration::days(offset))
+                        .and_hms_opt(minutes / 60, minutes % 60, 0)
+                        .unwrap();
+                    // Times the clocks skip are taken an hour later, as a wall clock shows them.
+                    timezone
+                        .from_local_datetime(&local)
+                        .earliest()
+                        .or_else(|| timezone.from_local_datetime(&(local + Duration::hours(1))).earliest())
+                        .unwrap()
+                        .with_timezone(&Utc)
+                })
+                .collect();
+
+            let days = effort_days(&started_at, timezone);
+            let history = streaks(&days, &[]);
+
+            prop_assert_eq!(
+                vec![Streak {
+                    first_day,
+                    last_day: first_day + Duration::days(length - 1),
+                    active_days: length,
+                }],
+                history
+            );
+        }
+
+        #[test]
+        fn 連続記録は全ての努力日を重ならずに覆う(
+            timezone in time_zones(),
+            rest_days in rest_days(),
+            started_at in prop::collection::vec(
+                // 2024-01-01 to 2024-12-31 in UTC, including the leap day.
+                (1_704_067_200i64..1_735_689_600).prop_map(|seconds| Utc.timestamp_opt(seconds, 0).unwrap()),
+                0..60,
+            ),
+        ) {
+            let days = effort_days(&started_at, timezone);
+            let history = streaks(&days, &rest_days);
+
+            prop_assert_eq!(
+                days.len() as i64,
+                history.iter().map(|streak| streak.active_days).sum::<i64>()
+            );
+            for pair in history.windows(2) {
+                prop_assert!(pair[0].last_day < pair[1].first_day);
+                // Something other than a rest day without efforts parts two streaks.
+                let mut day = pair[0].last_day + Duration::days(1);
+                let mut broken = false;
+                while day < pair[1].first_day {
+                    broken |= !rest_days.contains(&day.weekday());
+                    day += Duration::days(1);
+                }
+                prop_assert!(broken);
+            }
+            for day in &days {
+                prop_assert!(history
+                    .iter()
+                    .any(|streak| streak.first_day <= *day && *day <= streak.last_day));
+            }
+            if let Some(longest) = longest_streak(&history) {
+                prop_assert!(history.iter().all(|streak| streak.active_days <= longest.active_days));
+            }
+        }
+    }
+}
@@ -0,0 +1,22 @@
+use std::collections::HashSet;
+
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+/// A free-form label. An effort can carry any number of tags.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct Tag {
+    pub id: i64,
+    pub owner: String,
+    /// Unique among the owner's tags.
+    pub name: String,
+}
+
+/// Trims the tag names and drops the repeated ones.
+pub fn normalize_tags(tags: &[String]) -> Vec<String> {
+    let mut seen = HashSet::new();
+    tags.iter()
+        .map(|tag| tag.trim().to_owned())
+        .filter(|tag| seen.insert(tag.clone()))
+        .collect()
+}
@@ -0,0 +1,268 @@
+use chrono::{DateTime, Duration, Utc};
+use serde::{Deserialize, Serialize};
+use utoipa::ToSchema;
+
+use super::efforts::Effort;
+use super::pomodoros::{PomodoroInterruption, PomodoroPhase, PomodoroPhaseKind, PomodoroSettings};
+
+/// Rounds of a pomodoro timer settled at once. A timer left running for longer is paused at
+/// the end of the last of them, so that a forgotten timer doesn't record pomodoros for days.
+const MAX_SETTLED_ROUNDS: i64 = 2;
+
+/// An effort being timed. A user has at most one timer, which becomes an effort when stopped.
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
+pub struct Timer {
+    pub owner: String,
+    pub title: String,
+    pub notes: Option<String>,
+    pub category_id: Option<i64>,
+    /// Names of the tags the effort gets.
+    pub tags: Vec<String>,
+    pub started_at: DateTime<Utc>,
+    /// When the timer last started or resumed. `None` while it is paused.
+    pub running_since: Option<DateTime<Utc>>,
+    /// Seconds counted before `running_since`.
+    pub accumulated_seconds: i64,
+    /// Set for a pomodoro timer, whose completed work intervals become efforts one by one.
+    pub pomodoro: Option<PomodoroSettings>,
+    /// Counted seconds up to which the intervals of a pomodoro timer have been recorded.
+    #[serde(skip)]
+    pub settled_seconds: i64,
+    /// When the current work interval of a pomodoro timer started.
+    #[serde(skip)]
+    pub work_started_at: Option<DateTime<Utc>>,
+    /// Bumped on every change, so that only one of two concurrent changes applies.
+    #[serde(skip)]
+    pub revision: i64,
+}
+
+impl Timer {
+    pub fn is_running(&self) -> bool {
+        self.running_since.is_some()
+    }
+
+    /// Seconds counted up to `now`, leaving out the time spent paused.
+    pub fn elapsed_seconds(&self, now: DateTime<Utc>) -> i64 {
+        let running = self
+            .running_since
+            .map(|since| (now - since).num_seconds().max(0))
+            .unwrap_or(0);
+        self.accumulated_seconds + running
+    }
+
+    pub fn pause(&mut self, now: DateTime<Utc>) {
+        self.accumulated_seconds = self.elapsed_seconds(now);
+        self.running_since = None;
+    }
+
+    pub fn resume(&mut self, now: DateTime<Utc>) {
+        if self.running_since.is_none() {
+            self.running_since = Some(now);
+        }
+    }
+
+    /// The effort the timer records when it is stopped at `now`.
+    pub fn to_effort(&self, now: DateTime<Utc>) -> Effort {
+        let ended_at = now.max(self.started_at);
+        self.effort(
+            self.started_at,
+            ended_at,
+            self.elapsed_seconds(now)
+                .min((ended_at - self.started_at).num_seconds()),
+        )
+    }
+
+    /// The pomodoro phase the timer is in at `now`.
+    pub fn phase(&self, now: DateTime<Utc>) -> Option<PomodoroPhase> {
+        self.pomodoro
+            .map(|settings| settings.phase_at(self.elapsed_seconds(now)))
+    }
+
+    /// Records the work intervals of a pomodoro timer completed up to `now` and returns them
+    /// as efforts. Call it before every pause, so that the intervals not recorded yet all fall
+    /// in the time the timer has been running since `running_since`. Settles at most
+    /// `MAX_SETTLED_ROUNDS` rounds, pausing the timer at the end of the last one.
+    pub fn settle(&mut self, now: DateTime<Utc>) -> Vec<Effort> {
+        let (Some(settings), Some(running_since)) = (self.pomodoro, self.running_since) else {
+            return Vec::new();
+        };
+        let round = settings.round_seconds();
+        let limit = (self.settled_seconds.div_euclid(round) + MAX_SETTLED_ROUNDS) * round;
+        let forgotten = self.elapsed_seconds(now) > limit;
+        let elapsed = self.elapsed_seconds(now).min(limit);
+        let accumulated = self.accumulated_seconds;
+        let wall_clock =
+            |seconds: i64| running_since + Duration::seconds((seconds - accumulated).max(0));
+        let mut efforts = Vec::new();
+        while self.settled_seconds < elapsed {
+            let phase = settings.phase_at(self.settled_seconds);
+            if phase.kind == PomodoroPhaseKind::Work && self.work_started_at.is_none() {
+                self.work_started_at = Some(wall_clock(phase.start_seconds));
+            }
+            if phase.end_seconds > elapsed {
+                break;
+            }
+            if phase.kind == PomodoroPhaseKind::Work {
+                let ended_at = wall_clock(phase.end_seconds);
+                let started_at = self
+                    .work_started_at
+                    .take()
+                    .unwrap_or(ended_at - Duration::seconds(settings.work_seconds.into()));
+                let mut effort = self.effort(started_at, ended_at, settings.work_seconds.into());
+                effort.pomodoro = true;
+                efforts.push(effort);
+            }
+            self.settled_seconds = phase.end_seconds;
+        }
+        if forgotten {
+            self.accumulated_seconds = limit;
+            self.running_since = None;
+            // The next interval starts once the timer is resumed.
+            self.work_started_at = None;
+        }
+        efforts
+    }
+
+    /// The work interval a pomodoro timer stopped at `now` leaves incomplete, if any. Expects
+    /// the timer to be settled.
+    pub fn interruption(&self, now: DateTime<Utc>) -> Option<PomodoroInterruption> {
+        let phase = self.phase(now)?;
+        let worked_seconds = self.elapsed_seconds(now) - phase.start_seconds;
+        if phase.kind != PomodoroPhaseKind::Work || worked_seconds <= 0 {
+            return None;
+        }
+        Some(PomodoroInterruption {
+            owner: self.owner.to_owned(),
+            title: self.title.to_owned(),
+            started_at: self.work_started_at.unwrap_or(self.started_at),
+            interrupted_at: now,
+            worked_seconds,
+        })
+    }
+
+    fn effort(
+        &self,
+        started_at: DateTime<Utc>,
+        ended_at: DateTime<Utc>,
+        duration_seconds: i64,
+    ) -> Effort {
+        Effort {
+            id: 0,
+            owner: self.owner.to_owned(),
+            title: self.title.to_owned(),
+            duration_seconds,
+            started_at,
+            ended_at,
+            notes: self.notes.to_owned(),
+            category_id: self.category_id,
+            tags: self.tags.to_owned(),
+            pomodoro: false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Timer;
+    use crate::domain::pomodoros::PomodoroSettings;
+    use chrono::{DateTime, Duration, TimeZone, Utc};
+
+    fn at(minutes: i64) -> DateTime<Utc> {
+        Utc.with_ymd_and_hms(2023, 5, 1, 9, 0, 0).unwrap() + Duration::minutes(minutes)
+    }
+
+    fn timer() -> Timer {
+        Timer {
+            owner: "test@example.com".to_owned(),
+            title: "Rust".to_owned(),
+            notes: None,
+            category_id: None,
+            tags: vec![],
+            started_at: at(0),
+            running_since: Some(at(0)),
+            accumulated_seconds: 0,
+            pomodoro: None,
+            settled_seconds: 0,
+            work_started_at: None,
+            revision: 0,
+        }
+    }
+
+    #[test]
+    fn 一時停止中の時間は経過時間に含めない() {
+        let mut timer = timer();
+        timer.pause(at(10));
+        assert_eq!(600, timer.elapsed_seconds(at(30)));
+
+        timer.resume(at(30));
+        assert_eq!(900, timer.elapsed_seconds(at(35)));
+    }
+
+    #[test]
+    fn 停止すると開始から停止までの努力になる() {
+        let mut timer = timer();
+        timer.pause(at(20));
+        timer.resume(at(50));
+
+        let effort = timer.to_effort(at(60));
+
+        assert_eq!(at(0), effort.started_at);
+        assert_eq!(at(60), effort.ended_at);
+        assert_eq!(1800, effort.duration_seconds);
+    }
+
+    #[test]
+    fn 完了した作業時間だけをポモドーロとして記録する() {
+        let mut timer = Timer {
+            pomodoro: Some(PomodoroSettings {
+                work_seconds: 25 * 60,
+                short_break_seconds: 5 * 60,
+                long_break_seconds: 15 * 60,
+                cycles: 4,
+            }),
+            work_started_at: Some(at(0)),
+            ..timer()
+        };
+
+        // Paused for 10 minutes in the middle of the first interval.
+        assert!(timer.settle(at(20)).is_empty());
+        timer.pause(at(20));
+        timer.resume(at(30));
+        let efforts = timer.settle(at(60));
+
+        assert_eq!(1, efforts.len());
+        assert!(efforts[0].pomodoro);
+        assert_eq!(1500, efforts[0].duration_seconds);
+        assert_eq!(
+            (at(0), at(35)),
+            (efforts[0].started_at, efforts[0].ended_at)
+        );
+        // The second interval started after a 5 minute break and has run for 20 minutes.
+        let interruption = timer.interruption(at(60)).unwrap();
+        assert_eq!(at(40), interruption.started_at);
+        assert_eq!(1200, interruption.worked_seconds);
+    }
+
+    #[test]
+    fn 放置されたタイマーは上限の回数で一時停止する() {
+        let mut timer = Timer {
+            pomodoro: Some(PomodoroSettings::default()),
+            work_started_at: Some(at(0)),
+            ..timer()
+        };
+
+        // A round of 4 pomodoros and their breaks takes 130 minutes.
+        let efforts = timer.settle(at(3 * 24 * 60));
+
+        assert_eq!(8, efforts.len());
+        assert!(!timer.is_running());
+        assert_eq!(260 * 60, timer.elapsed_seconds(at(4 * 24 * 60)));
+        assert_eq!(at(260 - 15), efforts[7].ended_at);
+        assert!(timer.interruption(at(3 * 24 * 60)).is_none());
+
+        timer.resume(at(3 * 24 * 60));
+        let efforts = timer.settle(at(3 * 24 * 60 + 30));
+        assert_eq!(1, efforts.len());
+        assert_eq!(at(3 * 24 * 60), efforts[0].started_at);
+    }
+}
@@ -1,10 +1,71 @@
-use serde::{Deserialize, Serialize};
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Deserializer, Serialize};
+use utoipa::ToSchema;
 
-#[derive(Clone, Debug, Deserialize, Serialize, PartialEq)]
+pub const DEFAULT_TIMEZONE: &str = "UTC";
+pub const DEFAULT_LOCALE: &str = "en_US";
+
+#[derive(Clone, Debug, Deserialize, Serialize, PartialEq, ToSchema)]
 pub struct User {
     pub email: String,
-    pub external_id: String,
     pub user_name: String,
-    pub registered_date: std::time::SystemTime,
-    pub updated_date: std::time::SystemTime,
+    #[serde(default)]
+    pub avatar_url: Option<String>,
+    /// IANA name of the time zone the user lives in, such as `Asia/Tokyo`. Days are counted
+    /// in it wherever efforts are summed up.
+    #[serde(default = "default_timezone")]
+    pub timezone: String,
+    /// Locale of dates and labels shown to the user, such as `ja_JP`.
+    #[serde(default = "default_locale")]
+    pub locale: String,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub registered_date: DateTime<Utc>,
+    #[serde(deserialize_with = "deserialize_timestamp")]
+    pub updated_date: DateTime<Utc>,
+}
+
+fn default_timezone() -> String {
+    DEFAULT_TIMEZONE.to_owned()
+}
+
+fn default_locale() -> String {
+    DEFAULT_LOCALE.to_owned()
+}
+
+/// Reads RFC 3339 timestamps, as well as the `SystemTime` objects of sessions stored before
+/// timestamps had a time zone.
+fn deserialize_timestamp<'de, D: Deserializer<'de>>(
+    deserializer: D,
+) -> Result<DateTime<Utc>, D::Error> {
+    #[derive(Deserialize)]
+    #[serde(untagged)]
+    enum Timestamp {
+        Rfc3339(DateTime<Utc>),
+        SystemTime(std::time::SystemTime),
+    }
+    Ok(match Timestamp::deserialize(deserializer)? {
+        Timestamp::Rfc3339(timestamp) => timestamp,
+        Timestamp::SystemTime(timestamp) => timestamp.into(),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::User;
+    use chrono::{TimeZone, Utc};
+
+    #[test]
+    fn 以前のセッションに保存された形式の日時を読み込む() {
+        let user: User = serde_json::from_str(
+            r#"{"email":"test@example.com","user_name":"","registered_date":{"secs_since_epoch":1682899200,"nanos_since_epoch":0},"updated_date":"2023-05-01T00:00:00Z"}"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 5, 1, 0, 0, 0).unwrap(),
+            user.registered_date
+        );
+        assert_eq!(user.registered_date, user.updated_date);
+        assert_eq!("en_US", user.locale);
+    }
 }
@@ -1,13 +1,32 @@
+use chrono::{DateTime, NaiveDate, Utc, Weekday};
 use serde::{Deserialize, Serialize};
-use utoipa::ToSchema;
+use utoipa::{IntoParams, ToSchema};
 
-use crate::domain::users::User;
+use crate::domain::{
+    categories::Category,
+    efforts::{Effort, FilterMatch},
+    goals::{Goal, GoalMetric, GoalPeriod, GoalPeriodProgress},
+    heatmap::HeatmapBucket,
+    identities::UserIdentity,
+    pomodoros::{PomodoroDay, PomodoroPhase, PomodoroSettings},
+    streaks::Streak,
+    tags::Tag,
+    timers::Timer,
+    users::User,
+};
 
 #[derive(Deserialize, Serialize, ToSchema)]
 pub struct LoginRequest {
+    /// Id of the identity provider that issued the credential. Defaults to `google`.
+    #[serde(default = "default_provider")]
+    pub provider: String,
     pub credential: String,
 }
 
+fn default_provider() -> String {
+    "google".to_owned()
+}
+
 #[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
 pub struct LoginResult {
     pub situation: LoginSituation,
@@ -15,12 +34,13 @@ pub struct LoginResult {
     pub description: Option<String>,
 }
 
-#[derive(Clone,Debug,  Deserialize, PartialEq, Serialize, ToSchema)]
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
 pub enum LoginSituation {
     Succeeded,
     NotRegistered,
     VerificationFailed,
     EmailIsEmpty,
+    UnknownProvider,
 }
 
 #[derive(Deserialize, ToSchema)]
@@ -43,4 +63,591 @@ pub enum SignupSituation {
     VerificationFailed,
     EmailIsEmpty,
     UserNameIsEmpty,
+    UnknownProvider,
+}
+
+/// Fields that are left out keep their current value.
+#[derive(Clone, Debug, Default, Deserialize, Serialize, ToSchema)]
+pub struct ProfileRequest {
+    pub user_name: Option<String>,
+    /// An empty string removes the avatar.
+    pub avatar_url: Option<String>,
+    /// IANA time zone name such as `Asia/Tokyo`.
+    pub timezone: Option<String>,
+    /// Locale such as `ja_JP`. Used for month and weekday names.
+    pub locale: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ProfileResult {
+    pub situation: ProfileSituation,
+    pub user: Option<User>,
+    pub description: Option<String>,
+}
+
+/// Where the calendar of a user who opted in is public.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct PublicCalendar {
+    /// Random and unrelated to the user, so that it reveals nothing about them. The calendar
+    /// is served under `/public/{slug}/`.
+    pub slug: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum ProfileSituation {
+    Succeeded,
+    UserNameIsEmpty,
+    InvalidAvatarUrl,
+    InvalidTimezone,
+    InvalidLocale,
+    NotFound,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct LinkIdentityResult {
+    pub situation: LinkIdentitySituation,
+    /// The identities of the user after linking.
+    pub identities: Option<Vec<UserIdentity>>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum LinkIdentitySituation {
+    Succeeded,
+    AlreadyLinked,
+    /// The credential signs in as a different user.
+    BelongsToAnotherUser,
+    /// The user already has another account of the provider linked.
+    ProviderAlreadyLinked,
+    VerificationFailed,
+    UnknownProvider,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct UnlinkIdentityResult {
+    pub situation: UnlinkIdentitySituation,
+    /// The identities of the user after unlinking.
+    pub identities: Option<Vec<UserIdentity>>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum UnlinkIdentitySituation {
+    Succeeded,
+    NotLinked,
+    LastIdentity,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct EffortRequest {
+    pub title: String,
+    pub duration_seconds: i64,
+    pub started_at: DateTime<Utc>,
+    pub ended_at: DateTime<Utc>,
+    pub notes: Option<String>,
+    /// One of the user's categories.
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    /// Names of the tags. Tags the user doesn't have yet are created.
+    #[serde(default)]
+    pub tags: Vec<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EffortFilterQuery {
+    /// Comma separated ids of categories.
+    pub category: Option<String>,
+    /// Comma separated names of tags.
+    pub tag: Option<String>,
+    /// Whether an effort must match every category and tag given or just one of them.
+    /// Defaults to `all`.
+    #[serde(rename = "match")]
+    #[param(inline)]
+    pub match_mode: Option<FilterMatch>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct EffortResult {
+    pub situation: EffortSituation,
+    pub effort: Option<Effort>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum EffortSituation {
+    Succeeded,
+    NotFound,
+    TitleIsEmpty,
+    InvalidPeriod,
+    InvalidDuration,
+    CategoryNotFound,
+    TagIsEmpty,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct CategoryRequest {
+    pub name: String,
+    /// Hex colour such as `#40c463`.
+    pub colour: String,
+    pub icon: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct CategoryResult {
+    pub situation: CategorySituation,
+    pub category: Option<Category>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum CategorySituation {
+    Succeeded,
+    NotFound,
+    NameIsEmpty,
+    InvalidColour,
+    NameAlreadyUsed,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct TagRequest {
+    pub name: String,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct TagMergeRequest {
+    /// Id of the tag that takes over the efforts.
+    pub into: i64,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct TagResult {
+    pub situation: TagSituation,
+    pub tag: Option<Tag>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum TagSituation {
+    Succeeded,
+    NotFound,
+    NameIsEmpty,
+    NameAlreadyUsed,
+    /// A tag can't be merged into itself.
+    SameTag,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct GoalRequest {
+    pub title: String,
+    pub metric: GoalMetric,
+    /// Amount to reach in every period: seconds for `duration`, efforts for `count` and days
+    /// for `distinct_days`.
+    pub target: i64,
+    pub period: GoalPeriod,
+    /// Length of a `custom` period in days. Only given for `custom` periods.
+    pub period_days: Option<i32>,
+    /// Efforts before this day don't count. `custom` periods are counted from this day.
+    pub starts_on: NaiveDate,
+    /// One of the user's categories. Only efforts of it count when given.
+    pub category_id: Option<i64>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct GoalResult {
+    pub situation: GoalSituation,
+    pub goal: Option<Goal>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum GoalSituation {
+    Succeeded,
+    NotFound,
+    TitleIsEmpty,
+    InvalidTarget,
+    InvalidPeriod,
+    CategoryNotFound,
+    InvalidRange,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct GoalProgressQuery {
+    /// Number of periods to report, the current one included. Defaults to 12.
+    pub periods: Option<usize>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct GoalProgress {
+    pub goal: Goal,
+    /// The current period first, then the ones before it.
+    pub periods: Vec<GoalPeriodProgress>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct GoalProgressResult {
+    pub situation: GoalSituation,
+    pub progress: Option<GoalProgress>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct TimerRequest {
+    pub title: String,
+    pub notes: Option<String>,
+    /// One of the user's categories.
+    #[serde(default)]
+    pub category_id: Option<i64>,
+    /// Names of the tags the effort gets. Tags the user doesn't have yet are created on stop.
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Makes a pomodoro timer, which records every completed work interval as an effort.
+    pub pomodoro: Option<PomodoroSettings>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct TimerState {
+    pub timer: Timer,
+    pub running: bool,
+    /// Seconds counted so far, leaving out the time spent paused.
+    pub elapsed_seconds: i64,
+    /// The interval a pomodoro timer is in.
+    pub phase: Option<PomodoroPhase>,
+}
+
+impl TimerState {
+    /// The state of `timer` at `now`.
+    pub fn new(timer: Timer, now: DateTime<Utc>) -> Self {
+        Self {
+            running: timer.is_running(),
+            elapsed_seconds: timer.elapsed_seconds(now),
+            phase: timer.phase(now),
+            timer,
+        }
+    }
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct TimerResult {
+    pub situation: TimerSituation,
+    pub timer: Option<TimerState>,
+    /// Efforts the request recorded: the one of a stopped timer, or the work intervals a
+    /// pomodoro timer completed since the last request.
+    pub efforts: Vec<Effort>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum TimerSituation {
+    Succeeded,
+    /// The user has no timer.
+    NotFound,
+    AlreadyStarted,
+    AlreadyPaused,
+    AlreadyRunning,
+    /// The timer was changed by another request at the same time.
+    Conflict,
+    TitleIsEmpty,
+    CategoryNotFound,
+    TagIsEmpty,
+    InvalidPomodoro,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct PomodoroStatsQuery {
+    /// First day to report. Defaults to 29 days before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last day to report. Defaults to today in the user's time zone.
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct PomodoroStatsResult {
+    pub situation: PomodoroStatsSituation,
+    /// Every day from `from` to `to`, the earliest first.
+    pub days: Option<Vec<PomodoroDay>>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum PomodoroStatsSituation {
+    Succeeded,
+    InvalidRange,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ImportPreset {
+    /// The detailed report Toggl Track exports.
+    Toggl,
+    /// The detailed report Clockify exports.
+    Clockify,
+}
+
+/// Names the CSV columns the fields of an effort are read from. Names are matched
+/// case-insensitively.
+#[derive(Clone, Debug, Default, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ColumnMapping {
+    pub title: String,
+    /// Column used for the title when the title column is blank.
+    pub title_fallback: Option<String>,
+    /// The start date, or the start date and time when `start_time` is not given.
+    pub start_date: String,
+    pub start_time: Option<String>,
+    /// Defaults to the start date when only `end_time` is given.
+    pub end_date: Option<String>,
+    pub end_time: Option<String>,
+    /// `HH:MM:SS` or decimal hours. Required unless the end is mapped.
+    pub duration: Option<String>,
+    pub notes: Option<String>,
+    /// Names of the tags, separated by commas.
+    pub tags: Option<String>,
+    /// chrono format of the dates, e.g. `%d/%m/%Y`. Defaults to ISO dates and `%m/%d/%Y`.
+    pub date_format: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct ImportQuery {
+    /// Reads the CSV with the mapping of a time tracker's export instead of `mapping`.
+    #[param(inline)]
+    pub preset: Option<ImportPreset>,
+    /// Only validates the file and reports what would be imported. Defaults to `false`.
+    pub dry_run: Option<bool>,
+}
+
+/// The multipart form `POST /efforts/import` accepts. It only documents the form, which is
+/// read by `ImportForm`.
+#[allow(dead_code)]
+#[derive(ToSchema)]
+pub struct ImportUpload {
+    #[schema(value_type = String, format = Binary)]
+    pub file: Vec<u8>,
+    /// JSON of the mapping. Required unless a preset is given.
+    pub mapping: Option<ColumnMapping>,
+}
+
+#[derive(Clone, Debug)]
+pub struct ImportRequest {
+    pub data: Vec<u8>,
+    pub preset: Option<ImportPreset>,
+    pub mapping: Option<ColumnMapping>,
+    pub dry_run: bool,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ImportResult {
+    pub situation: ImportSituation,
+    pub report: Option<ImportReport>,
+    pub description: Option<String>,
+    /// The efforts stored by the import, to push to the owner's clients.
+    #[serde(skip)]
+    pub stored: Vec<Effort>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum ImportSituation {
+    Succeeded,
+    InvalidMapping,
+    InvalidFile,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    /// Efforts that were stored, or would be stored on a dry run.
+    pub imported: usize,
+    /// Rows that were imported before, by an earlier upload or earlier in the same file.
+    pub duplicates: usize,
+    pub invalid: usize,
+    pub rows: Vec<ImportRow>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ImportRow {
+    /// Line of the row in the file, counting the header as line 1.
+    pub line: u64,
+    pub status: ImportRowStatus,
+    pub effort: Option<EffortRequest>,
+    pub errors: Vec<String>,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum ImportRowStatus {
+    New,
+    Duplicate,
+    Invalid,
+}
+
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "lowercase")]
+pub enum EffortExportFormat {
+    Csv,
+    /// One JSON object per line.
+    Jsonl,
+    /// An iCalendar file with one event per effort.
+    Ics,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct EffortExportQuery {
+    #[param(inline)]
+    pub format: EffortExportFormat,
+    /// Exports the efforts started on or after this day, in the user's time zone.
+    pub from: Option<NaiveDate>,
+    /// Exports the efforts started on or before this day, in the user's time zone.
+    pub to: Option<NaiveDate>,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HeatmapQuery {
+    /// First day of the heatmap. Defaults to 52 weeks before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last day of the heatmap. Defaults to today in the user's time zone.
+    pub to: Option<NaiveDate>,
+    #[param(inline)]
+    pub bucket: Option<HeatmapBucket>,
+    /// Comma separated ids of categories to count the efforts of.
+    pub category: Option<String>,
+    /// Comma separated names of tags to count the efforts of.
+    pub tag: Option<String>,
+    /// Whether an effort must match every category and tag given or just one of them.
+    /// Defaults to `all`.
+    #[serde(rename = "match")]
+    #[param(inline)]
+    pub match_mode: Option<FilterMatch>,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct HeatmapSvgQuery {
+    /// First day of the calendar. Defaults to 52 weeks before `to`.
+    pub from: Option<NaiveDate>,
+    /// Last day of the calendar. Defaults to today in the user's time zone.
+    pub to: Option<NaiveDate>,
+    /// Five comma separated hex colours from level 0 to level 4, e.g. `ebedf0,9be9a8,40c463,30a14e,216e39`.
+    pub palette: Option<String>,
+    /// The day each calendar column starts with, e.g. `sun` or `monday`. Defaults to Sunday.
+    #[param(value_type = Option<String>)]
+    pub week_start: Option<Weekday>,
+    /// Locale of the month labels, e.g. `en_US` or `ja-JP`. Defaults to the user's locale.
+    pub locale: Option<String>,
+    /// Comma separated ids of categories to count the efforts of.
+    pub category: Option<String>,
+    /// Comma separated names of tags to count the efforts of.
+    pub tag: Option<String>,
+    /// Whether an effort must match every category and tag given or just one of them.
+    /// Defaults to `all`.
+    #[serde(rename = "match")]
+    #[param(inline)]
+    pub match_mode: Option<FilterMatch>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct HeatmapResult {
+    pub situation: HeatmapSituation,
+    pub heatmap: Option<Heatmap>,
+    pub description: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct Heatmap {
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+    pub bucket: HeatmapBucket,
+    /// The user's time zone, whose days the buckets are made of.
+    pub timezone: String,
+    /// The user's locale.
+    pub locale: String,
+    pub cells: Vec<HeatmapCell>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct HeatmapCell {
+    pub date: NaiveDate,
+    pub total_seconds: i64,
+    pub effort_count: i64,
+    /// Intensity from 0 (no effort) to 4.
+    pub level: u8,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum HeatmapSituation {
+    Succeeded,
+    UserNotFound,
+    InvalidRange,
+    InvalidFilter,
+}
+
+#[derive(Clone, Debug, Deserialize, IntoParams)]
+#[into_params(parameter_in = Query)]
+pub struct StreakQuery {
+    /// Comma separated weekdays that don't break a streak, e.g. `sat,sun`. Defaults to none.
+    pub rest_days: Option<String>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct StreakResult {
+    pub situation: StreakSituation,
+    pub streaks: Option<Streaks>,
+    pub description: Option<String>,
+}
+
+/// Streaks over the days of the user's time zone.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct Streaks {
+    /// The latest streak while it is still going on.
+    pub current: Option<Streak>,
+    pub longest: Option<Streak>,
+    /// Every streak, the latest first.
+    pub history: Vec<Streak>,
+}
+
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub enum StreakSituation {
+    Succeeded,
+    UserNotFound,
+    InvalidRestDays,
+}
+
+/// Machine-readable error codes. They are part of the API and never change meaning.
+#[derive(Clone, Copy, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCode {
+    Unauthorized,
+    NotFound,
+    InvalidRequest,
+    DatabaseUnavailable,
+    InternalError,
+    Unsupported,
+}
+
+impl ErrorCode {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ErrorCode::Unauthorized => "unauthorized",
+            ErrorCode::NotFound => "not_found",
+            ErrorCode::InvalidRequest => "invalid_request",
+            ErrorCode::DatabaseUnavailable => "database_unavailable",
+            ErrorCode::InternalError => "internal_error",
+            ErrorCode::Unsupported => "unsupported",
+        }
+    }
+}
+
+/// An RFC 7807 `application/problem+json` error body.
+#[derive(Clone, Debug, Deserialize, PartialEq, Serialize, ToSchema)]
+pub struct ProblemDetails {
+    #[serde(rename = "type")]
+    pub problem_type: String,
+    pub title: String,
+    pub status: u16,
+    pub detail: Option<String>,
+    pub code: ErrorCode,
+    /// Also sent as the `X-Correlation-Id` header and written to the server log.
+    pub correlation_id: String,
 }
@@ -0,0 +1,62 @@
+use std::future::Future;
+
+use actix_web::{
+    dev::{ServiceRequest, ServiceResponse},
+    http::header::{HeaderName, HeaderValue},
+};
+use rand::{distributions::Alphanumeric, Rng};
+
+/// Header a caller may set to correlate its own logs with ours. It is echoed on every response.
+pub const CORRELATION_ID_HEADER: &str = "x-correlation-id";
+const CORRELATION_ID_LENGTH: usize = 32;
+const MAX_CORRELATION_ID_LENGTH: usize = 128;
+
+tokio::task_local! {
+    static CORRELATION_ID: String;
+}
+
+/// The correlation id of the request being handled, or a fresh one outside of a request.
+pub fn current() -> String {
+    CORRELATION_ID
+        .try_with(|correlation_id| correlation_id.clone())
+        .unwrap_or_else(|_| generate())
+}
+
+/// Takes the correlation id from the request header when it is sane, otherwise generates one.
+pub fn from_request(req: &ServiceRequest) -> String {
+    req.headers()
+        .get(CORRELATION_ID_HEADER)
+        .and_then(|value| value.to_str().ok())
+        .filter(|value| {
+            !value.is_empty()
+                && value.len() <= MAX_CORRELATION_ID_LENGTH
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+        })
+        .map(str::to_owned)
+        .unwrap_or_else(generate)
+}
+
+/// Runs the rest of the request with `correlation_id` as the current one.
+pub async fn scope<B>(
+    correlation_id: String,
+    response: impl Future<Output = Result<ServiceResponse<B>, actix_web::Error>>,
+) -> Result<ServiceResponse<B>, actix_web::Error> {
+    let header = HeaderValue::from_str(&correlation_id);
+    let mut response = CORRELATION_ID.scope(correlation_id, response).await?;
+    if let Ok(header) = header {
+        response
+            .headers_mut()
+            .insert(HeaderName::from_static(CORRELATION_ID_HEADER), header);
+    }
+    Ok(response)
+}
+
+fn generate() -> String {
+    rand::thread_rng()
+        .sample_iter(&Alphanumeric)
+        .take(CORRELATION_ID_LENGTH)
+        .map(char::from)
+        .collect()
+}
@@ -0,0 +1,337 @@
+use anyhow::Result;
+use chrono::{DateTime, Datelike, Duration, Offset, TimeZone, Utc};
+use chrono_tz::{OffsetComponents, Tz};
+
+use crate::domain::efforts::Effort;
+use crate::dto::EffortExportFormat;
+
+const CALENDAR_LINE_OCTETS: usize = 75;
+/// Exports without `from` describe the time zone since this year.
+const FIRST_CALENDAR_YEAR: i32 = 1970;
+
+/// Renders exported efforts one at a time, so an export can be streamed.
+pub struct EffortExportWriter {
+    format: EffortExportFormat,
+    timezone: Tz,
+    stamp: DateTime<Utc>,
+}
+
+impl EffortExportWriter {
+    pub fn new(format: EffortExportFormat, timezone: Tz) -> Self {
+        Self {
+            format,
+            timezone,
+            stamp: Utc::now(),
+        }
+    }
+
+    pub fn content_type(format: EffortExportFormat) -> &'static str {
+        match format {
+            EffortExportFormat::Csv => "text/csv; charset=utf-8",
+            EffortExportFormat::Jsonl => "application/x-ndjson",
+            EffortExportFormat::Ics => "text/calendar; charset=utf-8",
+        }
+    }
+
+    pub fn file_name(format: EffortExportFormat) -> &'static str {
+        match format {
+            EffortExportFormat::Csv => "efforts.csv",
+            EffortExportFormat::Jsonl => "efforts.jsonl",
+            EffortExportFormat::Ics => "efforts.ics",
+        }
+    }
+
+    /// What precedes the efforts. A calendar describes its time zone for the years from
+    /// `first_year` to `last_year`.
+    pub fn header(&self, first_year: Option<i32>, last_year: Option<i32>) -> Result<Vec<u8>> {
+        match self.format {
+            EffortExportFormat::Csv => csv_record(&[
+                "id",
+                "title",
+                "started_at",
+                "ended_at",
+                "duration_seconds",
+                "notes",
+                "category_id",
+                "tags",
+            ]),
+            EffortExportFormat::Jsonl => Ok(vec![]),
+            EffortExportFormat::Ics => {
+                let mut lines = vec![
+                    "BEGIN:VCALENDAR".to_owned(),
+                    "VERSION:2.0".to_owned(),
+                    "PRODID:-//effort_visualizer//Effort export//EN".to_owned(),
+                    "CALSCALE:GREGORIAN".to_owned(),
+                    "X-WR-CALNAME:Efforts".to_owned(),
+                    format!("X-WR-TIMEZONE:{}", self.timezone.name()),
+                ];
+                if self.timezone != Tz::UTC {
+                    let first_year = first_year.unwrap_or(FIRST_CALENDAR_YEAR);
+                    let last_year = last_year.unwrap_or_else(|| Utc::now().year() + 1);
+                    lines.extend(vtimezone(self.timezone, first_year, last_year));
+                }
+                Ok(calendar_lines(&lines))
+            }
+        }
+    }
+
+    pub fn effort(&self, effort: &Effort) -> Result<Vec<u8>> {
+        match self.format {
+            EffortExportFormat::Csv => csv_record(&[
+                &effort.id.to_string(),
+                &effort.title,
+                &effort.started_at.with_timezone(&self.timezone).to_rfc3339(),
+                &effort.ended_at.with_timezone(&self.timezone).to_rfc3339(),
+                &effort.duration_seconds.to_string(),
+                effort.notes.as_deref().unwrap_or_default(),
+                &effort
+                    .category_id
+                    .map(|id| id.to_string())
+                    .unwrap_or_default(),
+                &effort.tags.join(","),
+            ]),
+            EffortExportFormat::Jsonl => {
+                let mut line = serde_json::to_vec(effort)?;
+                line.push(b'\n');
+                Ok(line)
+            }
+            EffortExportFormat::Ics => {
+                let mut lines = vec![
+                    "BEGIN:VEVENT".to_owned(),
+                    format!("UID:effort-{}@effort_visualizer", effort.id),
+                    format!("DTSTAMP:{}", self.stamp.format("%Y%m%dT%H%M%SZ")),
+                    self.date_time("DTSTART", effort.started_at),
+                    self.date_time("DTEND", effort.ended_at),
+                    format!("SUMMARY:{}", escape_text(&effort.title)),
+                ];
+                if let Some(notes) = &effort.notes {
+                    lines.push(format!("DESCRIPTION:{}", escape_text(notes)));
+                }
+                if !effort.tags.is_empty() {
+                    let tags: Vec<String> =
+                        effort.tags.iter().map(|tag| escape_text(tag)).collect();
+                    lines.push(format!("CATEGORIES:{}", tags.join(",")));
+                }
+                lines.push("END:VEVENT".to_owned());
+                Ok(calendar_lines(&lines))
+            }
+        }
+    }
+
+    pub fn footer(&self) -> Vec<u8> {
+        match self.format {
+            EffortExportFormat::Ics => calendar_lines(&["END:VCALENDAR".to_owned()]),
+            _ => vec![],
+        }
+    }
+
+    /// UTC times are written as such. Other zones use local times with a `TZID`, so calendar
+    /// apps keep the events at the same wall-clock time.
+    fn date_time(&self, name: &str, date_time: DateTime<Utc>) -> String {
+        if self.timezone == Tz::UTC {
+            return format!("{name}:{}", date_time.format("%Y%m%dT%H%M%SZ"));
+        }
+        format!(
+            "{name};TZID={}:{}",
+            self.timezone.name(),
+            date_time
+                .with_timezone(&self.timezone)
+                .format("%Y%m%dT%H%M%S")
+        )
+    }
+}
+
+fn csv_record(fields: &[&str]) -> Result<Vec<u8>> {
+    let mut writer = csv::Writer::from_writer(vec![]);
+    writer.write_record(fields)?;
+    Ok(writer.into_inner()?)
+}
+
+/// Escapes a TEXT value as RFC 5545 requires.
+fn escape_text(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace(';', "\\;")
+        .replace(',', "\\,")
+        .replace("\r\n", "\\n")
+        .replace('\n', "\\n")
+        .replace('\r', "")
+}
+
+/// Ends each line with CRLF and folds the ones longer than 75 octets.
+fn calendar_lines(lines: &[String]) -> Vec<u8> {
+    let mut output = Vec::new();
+    for line in lines {
+        let mut octets = 0;
+        for c in line.chars() {
+            if octets + c.len_utf8() > CALENDAR_LINE_OCTETS {
+                output.extend_from_slice(b"\r\n ");
+                octets = 1;
+            }
+            let mut buffer = [0; 4];
+            output.extend_from_slice(c.encode_utf8(&mut buffer).as_bytes());
+            octets += c.len_utf8();
+        }
+        output.extend_from_slice(b"\r\n");
+    }
+    output
+}
+
+#[derive(PartialEq)]
+struct Observance {
+    offset_seconds: i32,
+    dst: bool,
+    name: String,
+}
+
+fn observance(timezone: Tz, at: DateTime<Utc>) -> Observance {
+    let offset = timezone.offset_from_utc_datetime(&at.naive_utc());
+    Observance {
+        offset_seconds: offset.fix().local_minus_utc(),
+        dst: !offset.dst_offset().is_zero(),
+        name: offset.to_string(),
+    }
+}
+
+fn utc_offset(seconds: i32) -> String {
+    let sign = if seconds < 0 { '-' } else { '+' };
+    let seconds = seconds.abs();
+    format!("{sign}{:02}{:02}", seconds / 3600, seconds / 60 % 60)
+}
+
+/// Describes `timezone` from the start of `first_year` to the end of `last_year` with one
+/// observance per transition, which is exact without knowing the zone's rules.
+fn vtimezone(timezone: Tz, first_year: i32, last_year: i32) -> Vec<String> {
+    let start = Utc
+        .with_ymd_and_hms(first_year, 1, 1, 0, 0, 0)
+        .single()
+        .unwrap_or_default();
+    let end = Utc
+        .with_ymd_and_hms(last_year + 1, 1, 1, 0, 0, 0)
+        .single()
+        .unwrap_or_default();
+    let mut lines = vec![
+        "BEGIN:VTIMEZONE".to_owned(),
+        format!("TZID:{}", timezone.name()),
+    ];
+    let mut push = |onset: DateTime<Utc>, from: &Observance, to: &Observance| {
+        let kind = if to.dst { "DAYLIGHT" } else { "STANDARD" };
+        let local_onset = onset.naive_utc() + Duration::seconds(from.offset_seconds.into());
+        lines.extend([
+            format!("BEGIN:{kind}"),
+            format!("DTSTART:{}", local_onset.format("%Y%m%dT%H%M%S")),
+            format!("TZOFFSETFROM:{}", utc_offset(from.offset_seconds)),
+            format!("TZOFFSETTO:{}", utc_offset(to.offset_seconds)),
+            format!("TZNAME:{}", to.name),
+            format!("END:{kind}"),
+        ]);
+    };
+
+    let mut current = observance(timezone, start);
+    push(start, &current, &current);
+    let mut day = start;
+    while day < end {
+        let next_day = day + Duration::days(1);
+        let next = observance(timezone, next_day);
+        if next != current {
+            // Narrow the day down to the second the new observance starts.
+            let (mut before, mut after) = (day, next_day);
+            while after - before > Duration::seconds(1) {
+                let middle = before + (after - before) / 2;
+                if observance(timezone, middle) == current {
+                    before = middle;
+                } else {
+                    after = middle;
+                }
+            }
+            let onset_observance = observance(timezone, after);
+            push(after, &current, &onset_observance);
+            current = onset_observance;
+        }
+        day = next_day;
+    }
+    lines.push("END:VTIMEZONE".to_owned());
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{calendar_lines, EffortExportWriter};
+    use crate::domain::efforts::Effort;
+    use crate::dto::EffortExportFormat;
+    use chrono::{TimeZone, Utc};
+
+    fn effort() -> Effort {
+        Effort {
+            id: 7,
+            owner: "test@example.com".to_owned(),
+            title: "Reading, writing; and more".to_owned(),
+            duration_seconds: 5400,
+            started_at: Utc.with_ymd_and_hms(2023, 5, 1, 0, 0, 0).unwrap(),
+            ended_at: Utc.with_ymd_and_hms(2023, 5, 1, 1, 30, 0).unwrap(),
+            notes: Some("line 1\nline 2".to_owned()),
+            category_id: None,
+            tags: vec!["books".to_owned(), "rust".to_owned()],
+            pomodoro: false,
+        }
+    }
+
+    fn text(bytes: Vec<u8>) -> String {
+        String::from_utf8(bytes).unwrap()
+    }
+
+    #[test]
+    fn utcの予定はzを付けた時刻で書き出す() {
+        let writer = EffortExportWriter::new(EffortExportFormat::Ics, chrono_tz::UTC);
+
+        let header = text(writer.header(None, None).unwrap());
+        let event = text(writer.effort(&effort()).unwrap());
+
+        assert!(!header.contains("VTIMEZONE"));
+        assert!(event.contains("DTSTART:20230501T000000Z\r\n"));
+        assert!(event.contains("SUMMARY:Reading\\, writing\\; and more\r\n"));
+        assert!(event.contains("DESCRIPTION:line 1\\nline 2\r\n"));
+        assert!(event.contains("CATEGORIES:books,rust\r\n"));
+    }
+
+    #[test]
+    fn 他のタイムゾーンはtzidと夏時間の切り替えを書き出す() {
+        let writer = EffortExportWriter::new(EffortExportFormat::Ics, chrono_tz::Europe::Berlin);
+
+        let header = text(writer.header(Some(2023), Some(2023)).unwrap());
+        let event = text(writer.effort(&effort()).unwrap());
+
+        assert!(header.contains("TZID:Europe/Berlin\r\n"));
+        assert!(header.contains(
+            "BEGIN:DAYLIGHT\r\nDTSTART:20230326T020000\r\nTZOFFSETFROM:+0100\r\nTZOFFSETTO:+0200\r\nTZNAME:CEST\r\n"
+        ));
+        assert!(header.contains(
+            "BEGIN:STANDARD\r\nDTSTART:20231029T030000\r\nTZOFFSETFROM:+0200\r\nTZOFFSETTO:+0100\r\n"
+        ));
+        assert!(event.contains("DTSTART;TZID=Europe/Berlin:20230501T020000\r\n"));
+    }
+
+    #[test]
+    fn 長い行は75オクテットで折り返す() {
+        let folded = text(calendar_lines(&[format!("SUMMARY:{}", "あ".repeat(40))]));
+
+        for line in folded.split("\r\n") {
+            assert!(line.len() <= 75);
+        }
+        assert_eq!(
+            format!("SUMMARY:{}", "あ".repeat(40)),
+            folded.replace("\r\n ", "").trim_end()
+        );
+    }
+
+    #[test]
+    fn csvはローカル時刻で書き出す() {
+        let writer = EffortExportWriter::new(EffortExportFormat::Csv, chrono_tz::Asia::Tokyo);
+
+        let row = text(writer.effort(&effort()).unwrap());
+
+        assert_eq!(
+            "7,\"Reading, writing; and more\",2023-05-01T09:00:00+09:00,2023-05-01T10:30:00+09:00,5400,\"line 1\nline 2\",,\"books,rust\"\n",
+            row
+        );
+    }
+}
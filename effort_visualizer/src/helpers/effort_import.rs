@@ -0,0 +1,395 @@
+use chrono::{DateTime, Duration, LocalResult, NaiveDate, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use sha2::{Digest, Sha256};
+
+use crate::dto::{ColumnMapping, EffortRequest, ImportPreset};
+
+const DATE_FORMATS: [&str; 2] = ["%Y-%m-%d", "%m/%d/%Y"];
+const TIME_FORMATS: [&str; 4] = ["%H:%M:%S", "%H:%M", "%I:%M:%S %p", "%I:%M %p"];
+const DATE_TIME_FORMATS: [&str; 4] = [
+    "%Y-%m-%d %H:%M:%S",
+    "%Y-%m-%dT%H:%M:%S",
+    "%Y-%m-%d %H:%M",
+    "%Y-%m-%dT%H:%M",
+];
+
+/// The columns of the detailed reports time trackers export.
+pub fn preset_mapping(preset: ImportPreset) -> ColumnMapping {
+    let (start_date, start_time, end_date, end_time, duration) = match preset {
+        ImportPreset::Toggl => (
+            "Start date",
+            "Start time",
+            "End date",
+            "End time",
+            "Duration",
+        ),
+        ImportPreset::Clockify => (
+            "Start Date",
+            "Start Time",
+            "End Date",
+            "End Time",
+            "Duration (h)",
+        ),
+    };
+    ColumnMapping {
+        title: "Description".to_owned(),
+        title_fallback: Some("Project".to_owned()),
+        start_date: start_date.to_owned(),
+        start_time: Some(start_time.to_owned()),
+        end_date: Some(end_date.to_owned()),
+        end_time: Some(end_time.to_owned()),
+        duration: Some(duration.to_owned()),
+        notes: None,
+        tags: Some("Tags".to_owned()),
+        date_format: None,
+    }
+}
+
+/// A data row of the file. `effort` holds the reasons when the row can't be read.
+pub struct ParsedRow {
+    pub line: u64,
+    /// Hash of the mapped cells as written in the file.
+    pub import_hash: String,
+    pub effort: Result<EffortRequest, Vec<String>>,
+}
+
+struct Columns {
+    title: usize,
+    title_fallback: Option<usize>,
+    start_date: usize,
+    start_time: Option<usize>,
+    end_date: Option<usize>,
+    end_time: Option<usize>,
+    duration: Option<usize>,
+    notes: Option<usize>,
+    tags: Option<usize>,
+}
+
+impl Columns {
+    /// Every mapped column, unmapped optional ones included, in a fixed order.
+    fn all(&self) -> [Option<usize>; 9] {
+        [
+            Some(self.title),
+            self.title_fallback,
+            Some(self.start_date),
+            self.start_time,
+            self.end_date,
+            self.end_time,
+            self.duration,
+            self.notes,
+            self.tags,
+        ]
+    }
+}
+
+/// Reads every data row of the CSV. Dates without an offset are taken as local times in
+/// `timezone`. Fails when the file can't be read as CSV or lacks a mapped column.
+pub fn read_efforts(
+    data: &[u8],
+    mapping: &ColumnMapping,
+    timezone: Tz,
+) -> Result<Vec<ParsedRow>, String> {
+    if mapping.end_date.is_none() && mapping.end_time.is_none() && mapping.duration.is_none() {
+        return Err("Map the end or the duration of efforts.".to_owned());
+    }
+    let mut reader = csv::ReaderBuilder::new().flexible(true).from_reader(data);
+    let headers = reader
+        .headers()
+        .map_err(|e| format!("The header can't be read: {e}"))?
+        .clone();
+    let find = |name: &str| {
+        headers
+            .iter()
+            .position(|header| {
+                header
+                    .trim_start_matches('\u{feff}')
+                    .trim()
+                    .eq_ignore_ascii_case(name.trim())
+            })
+            .ok_or_else(|| format!("The file has no `{name}` column."))
+    };
+    let find_optional = |name: &Option<String>| name.as_deref().map(find).transpose();
+    let columns = Columns {
+        title: find(&mapping.title)?,
+        title_fallback: find_optional(&mapping.title_fallback)?,
+        start_date: find(&mapping.start_date)?,
+        start_time: find_optional(&mapping.start_time)?,
+        end_date: find_optional(&mapping.end_date)?,
+        end_time: find_optional(&mapping.end_time)?,
+        duration: find_optional(&mapping.duration)?,
+        notes: find_optional(&mapping.notes)?,
+        tags: find_optional(&mapping.tags)?,
+    };
+    let date_formats: Vec<&str> = match &mapping.date_format {
+        Some(format) => vec![format.as_str()],
+        None => DATE_FORMATS.to_vec(),
+    };
+
+    let mut rows = Vec::new();
+    for record in reader.records() {
+        let record = record.map_err(|e| format!("The file is not valid CSV: {e}"))?;
+        let line = record.position().map(|p| p.line()).unwrap_or_default();
+        if record.iter().all(|cell| cell.trim().is_empty()) {
+            continue;
+        }
+        let cell = |index: usize| record.get(index).unwrap_or_default().trim();
+        let optional_cell = |index: Option<usize>| index.map(cell).filter(|v| !v.is_empty());
+        let cells: Vec<&str> = columns
+            .all()
+            .into_iter()
+            .map(|index| index.map(cell).unwrap_or_default())
+            .collect();
+        let effort = read_effort(&columns, &date_formats, timezone, cell, optional_cell);
+        rows.push(ParsedRow {
+            line,
+            import_hash: import_hash(&cells),
+            effort,
+        });
+    }
+    Ok(rows)
+}
+
+fn read_effort<'a>(
+    columns: &Columns,
+    date_formats: &[&str],
+    timezone: Tz,
+    cell: impl Fn(usize) -> &'a str,
+    optional_cell: impl Fn(Option<usize>) -> Option<&'a str>,
+) -> Result<EffortRequest, Vec<String>> {
+    let mut errors = Vec::new();
+    let title = match cell(columns.title) {
+        "" => optional_cell(columns.title_fallback).unwrap_or_default(),
+        title => title,
+    };
+    let start_date = cell(columns.start_date);
+    let started_at = read_date_time(
+        start_date,
+        optional_cell(columns.start_time),
+        date_formats,
+        timezone,
+    )
+    .map_err(|e| errors.push(format!("The start is invalid: {e}")))
+    .ok();
+    let ended_at = match (
+        optional_cell(columns.end_date),
+        optional_cell(columns.end_time),
+    ) {
+        (None, None) => None,
+        (end_date, end_time) => read_date_time(
+            end_date.unwrap_or(start_date),
+            end_time,
+            date_formats,
+            timezone,
+        )
+        .map_err(|e| errors.push(format!("The end is invalid: {e}")))
+        .ok(),
+    };
+    let duration = optional_cell(columns.duration).and_then(|duration| {
+        read_duration(duration)
+            .map_err(|e| errors.push(format!("The duration is invalid: {e}")))
+            .ok()
+    });
+    let started_at = match started_at {
+        Some(started_at) if errors.is_empty() => started_at,
+        _ => return Err(errors),
+    };
+    let (ended_at, duration_seconds) = match (ended_at, duration) {
+        (Some(ended_at), Some(duration)) => (ended_at, duration),
+        (Some(ended_at), None) => (ended_at, (ended_at - started_at).num_seconds()),
+        (None, Some(duration)) => (started_at + Duration::seconds(duration), duration),
+        (None, None) => return Err(vec!["Neither the end nor the duration is given.".to_owned()]),
+    };
+    Ok(EffortRequest {
+        title: title.to_owned(),
+        duration_seconds,
+        started_at,
+        ended_at,
+        notes: optional_cell(columns.notes).map(str::to_owned),
+        category_id: None,
+        tags: optional_cell(columns.tags)
+            .map(|tags| {
+                tags.split(',')
+                    .map(str::trim)
+                    .filter(|tag| !tag.is_empty())
+                    .map(str::to_owned)
+                    .collect()
+            })
+            .unwrap_or_default(),
+    })
+}
+
+fn read_date_time(
+    date: &str,
+    time: Option<&str>,
+    date_formats: &[&str],
+    timezone: Tz,
+) -> Result<DateTime<Utc>, String> {
+    let local = match time {
+        Some(time) => {
+            let date = date_formats
+                .iter()
+                .find_map(|format| NaiveDate::parse_from_str(date, format).ok())
+                .ok_or_else(|| format!("`{date}` is not a date."))?;
+            let time = TIME_FORMATS
+                .iter()
+                .find_map(|format| NaiveTime::parse_from_str(time, format).ok())
+                .ok_or_else(|| format!("`{time}` is not a time."))?;
+            date.and_time(time)
+        }
+        None => {
+            if let Ok(date_time) = DateTime::parse_from_rfc3339(date) {
+                return Ok(date_time.with_timezone(&Utc));
+            }
+            DATE_TIME_FORMATS
+                .iter()
+                .find_map(|format| NaiveDateTime::parse_from_str(date, format).ok())
+                .ok_or_else(|| format!("`{date}` is not a date and time."))?
+        }
+    };
+    match timezone.from_local_datetime(&local) {
+        LocalResult::Single(date_time) => Ok(date_time.with_timezone(&Utc)),
+        // Repeated when the clocks go back. The first occurrence is as good a guess as any.
+        LocalResult::Ambiguous(earliest, _) => Ok(earliest.with_timezone(&Utc)),
+        LocalResult::None => Err(format!("`{local}` does not exist in {timezone}.")),
+    }
+}
+
+/// Reads `HH:MM:SS`, `HH:MM` or decimal hours such as `1.50`.
+fn read_duration(duration: &str) -> Result<i64, String> {
+    let invalid = || format!("`{duration}` is not a duration.");
+    if !duration.contains(':') {
+        let hours: f64 = duration.parse().map_err(|_| invalid())?;
+        if !hours.is_finite() || hours < 0.0 {
+            return Err(invalid());
+        }
+        return Ok((hours * 3600.0).round() as i64);
+    }
+    let parts: Vec<i64> = duration
+        .split(':')
+        .map(|part| part.parse::<u32>().map(i64::from))
+        .collect::<Result<_, _>>()
+        .map_err(|_| invalid())?;
+    match parts[..] {
+        [hours, minutes, seconds] if minutes < 60 && seconds < 60 => {
+            Ok(hours * 3600 + minutes * 60 + seconds)
+        }
+        [hours, minutes] if minutes < 60 => Ok(hours * 3600 + minutes * 60),
+        _ => Err(invalid()),
+    }
+}
+
+/// Identifies an imported effort by the cells it was read from, so uploading the same row twice
+/// is noticed even when the time zone it is read in has changed since.
+fn import_hash(cells: &[&str]) -> String {
+    let mut hasher = Sha256::new();
+    for cell in cells {
+        hasher.update(cell.as_bytes());
+        hasher.update([0x1f]);
+    }
+    hasher
+        .finalize()
+        .iter()
+        .map(|byte| format!("{byte:02x}"))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{preset_mapping, read_duration, read_efforts};
+    use crate::dto::{ColumnMapping, ImportPreset};
+    use chrono::{TimeZone, Utc};
+
+    const TOGGL: &str = "\u{feff}User,Email,Client,Project,Task,Description,Billable,Start date,Start time,End date,End time,Duration,Tags,Amount ()
+Taro,taro@example.com,,Study,,Rust book,No,2023-05-01,09:00:00,2023-05-01,10:30:00,01:30:00,\"rust, books\",
+Taro,taro@example.com,,Study,,,No,2023-05-02,23:30:00,2023-05-03,00:15:00,00:45:00,,
+Taro,taro@example.com,,Study,,Broken,No,2023-05-03,25:00:00,2023-05-03,10:00:00,01:00:00,,
+";
+
+    #[test]
+    fn togglの書き出しをタイムゾーン付きで読む() {
+        let rows = read_efforts(
+            TOGGL.as_bytes(),
+            &preset_mapping(ImportPreset::Toggl),
+            chrono_tz::Asia::Tokyo,
+        )
+        .unwrap();
+
+        assert_eq!(3, rows.len());
+        let first = rows[0].effort.as_ref().unwrap();
+        assert_eq!(2, rows[0].line);
+        assert_eq!("Rust book", first.title);
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 5, 1, 0, 0, 0).unwrap(),
+            first.started_at
+        );
+        assert_eq!(5400, first.duration_seconds);
+        assert_eq!(vec!["rust".to_owned(), "books".to_owned()], first.tags);
+        let second = rows[1].effort.as_ref().unwrap();
+        assert_eq!("Study", second.title);
+        assert_eq!(2700, second.duration_seconds);
+        assert_eq!(1, rows[2].effort.as_ref().unwrap_err().len());
+    }
+
+    #[test]
+    fn 対応付けた列がないときファイルを拒否する() {
+        let mapping = ColumnMapping {
+            title: "Title".to_owned(),
+            start_date: "Start".to_owned(),
+            duration: Some("Hours".to_owned()),
+            ..Default::default()
+        };
+
+        let result = read_efforts(
+            b"Title,Begin,Hours\nA,2023-05-01 09:00,1.5\n",
+            &mapping,
+            chrono_tz::UTC,
+        );
+
+        assert_eq!(
+            Err("The file has no `Start` column.".to_owned()),
+            result.map(|_| ())
+        );
+    }
+
+    #[test]
+    fn 時間は時分秒か小数の時間で読む() {
+        assert_eq!(Ok(5400), read_duration("01:30:00"));
+        assert_eq!(Ok(5400), read_duration("1:30"));
+        assert_eq!(Ok(5400), read_duration("1.50"));
+        assert!(read_duration("1:75").is_err());
+        assert!(read_duration("-1").is_err());
+    }
+
+    #[test]
+    fn 同じ内容の行は同じハッシュになる() {
+        let rows = read_efforts(
+            TOGGL.as_bytes(),
+            &preset_mapping(ImportPreset::Toggl),
+            chrono_tz::UTC,
+        )
+        .unwrap();
+        let again = read_efforts(
+            TOGGL.as_bytes(),
+            &preset_mapping(ImportPreset::Toggl),
+            chrono_tz::UTC,
+        )
+        .unwrap();
+
+        assert_eq!(rows[0].import_hash, again[0].import_hash);
+        assert_ne!(rows[0].import_hash, rows[1].import_hash);
+        assert_eq!(64, rows[0].import_hash.len());
+    }
+
+    #[test]
+    fn タイムゾーンを変えても同じ行は同じハッシュになる() {
+        let mapping = preset_mapping(ImportPreset::Toggl);
+        let rows = read_efforts(TOGGL.as_bytes(), &mapping, chrono_tz::UTC).unwrap();
+        let again = read_efforts(TOGGL.as_bytes(), &mapping, chrono_tz::Asia::Tokyo).unwrap();
+
+        assert_ne!(
+            rows[0].effort.as_ref().unwrap().started_at,
+            again[0].effort.as_ref().unwrap().started_at
+        );
+        assert_eq!(rows[0].import_hash, again[0].import_hash);
+    }
+}
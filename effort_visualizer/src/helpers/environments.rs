@@ -1,8 +1,65 @@
+use actix_web::cookie::SameSite;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SessionStoreKind {
+    /// The whole session state lives in the encrypted cookie.
+    Cookie,
+    /// The cookie only holds a key to a row of the `sessions` table.
+    Postgres,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdentityProviderKind {
+    /// Credentials are OpenID Connect ID tokens verified against the provider's JWKS.
+    Oidc,
+    /// Credentials are OAuth access tokens checked with the GitHub API.
+    Github,
+}
+
+pub struct IdentityProviderSettings {
+    /// The id clients send as `provider` and identities are stored under.
+    pub id: String,
+    pub kind: IdentityProviderKind,
+    /// The OAuth client id, which OIDC tokens must carry as their audience.
+    pub client_id: String,
+    pub client_secret: Option<String>,
+    pub issuers: Vec<String>,
+    pub jwks_url: Option<String>,
+    /// Verifies HS256 tokens signed with this secret instead of using the JWKS endpoint.
+    /// Only for local runs and integration tests.
+    pub static_key: Option<String>,
+}
+
+/// The prefix of the variables a provider is configured with, e.g. `MY_OIDC` for `my-oidc`.
+pub fn identity_provider_prefix(id: &str) -> String {
+    id.to_uppercase().replace('-', "_")
+}
+
 pub struct EnvVariables {
     pub db_server: String,
     pub db_port: String,
     pub db_name: String,
     pub db_user_id: String,
     pub db_password: String,
-    pub google_client_id: String,
+    pub db_pool_max_size: usize,
+    /// Connections unused for this long are closed.
+    pub db_pool_idle_timeout_seconds: u64,
+    /// How long a request waits for a free connection before failing with `PoolExhausted`.
+    pub db_pool_checkout_timeout_seconds: u64,
+    /// Applies pending schema migrations before the server starts.
+    pub db_migrate_on_startup: bool,
+    /// The providers users can sign in with, in the order of `IDENTITY_PROVIDERS`.
+    pub identity_providers: Vec<IdentityProviderSettings>,
+    /// Base64 encoded session key. Takes precedence over `session_key_file`.
+    pub session_key: Option<String>,
+    /// Path to a file containing the raw session key bytes.
+    pub session_key_file: Option<String>,
+    /// Base64 encoded keys that were used before the current one.
+    pub session_previous_keys: Vec<String>,
+    pub session_cookie_secure: bool,
+    pub session_cookie_same_site: SameSite,
+    /// Lifetime of the session cookie in seconds. The cookie lasts for the browser session when absent.
+    pub session_cookie_max_age: Option<i64>,
+    pub session_cookie_domain: Option<String>,
+    pub session_store: SessionStoreKind,
 }
@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use futures::{stream, StreamExt};
+use tokio::sync::{broadcast, mpsc};
+use tokio_postgres::{AsyncMessage, NoTls};
+use tracing::{error, info, warn};
+
+use crate::domain::events::UserEvent;
+use crate::repositories::events_repository::EVENT_CHANNEL;
+
+/// Events a slow client may fall behind by before it misses some.
+const CHANNEL_CAPACITY: usize = 64;
+const RECONNECT_DELAY: Duration = Duration::from_secs(5);
+
+/// Hands the events of each user to the clients of that user connected to this instance.
+#[derive(Default)]
+pub struct EventHub {
+    channels: Mutex<HashMap<String, broadcast::Sender<Arc<UserEvent>>>>,
+}
+
+impl EventHub {
+    /// Subscribes to the events of `owner`, forgetting the owners whose clients are all gone.
+    pub fn subscribe(&self, owner: &str) -> broadcast::Receiver<Arc<UserEvent>> {
+        let mut channels = self.channels.lock().unwrap();
+        channels.retain(|_, sender| sender.receiver_count() > 0);
+        match channels.get(owner) {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = broadcast::channel(CHANNEL_CAPACITY);
+                channels.insert(owner.to_owned(), sender);
+                receiver
+            }
+        }
+    }
+
+    /// Sends `event` to the clients of its owner, forgetting the owner once none is left.
+    pub fn dispatch(&self, event: UserEvent) {
+        let mut channels = self.channels.lock().unwrap();
+        let Some(sender) = channels.get(&event.owner) else {
+            return;
+        };
+        let owner = event.owner.to_owned();
+        if sender.send(Arc::new(event)).is_err() {
+            channels.remove(&owner);
+        }
+    }
+}
+
+/// Listens for the events published by every server instance and dispatches them to `hub`,
+/// connecting again whenever the connection is lost.
+pub fn spawn_event_listener(config: tokio_postgres::Config, hub: Arc<EventHub>) {
+    tokio::spawn(async move {
+        loop {
+            if let Err(e) = listen(&config, &hub).await {
+                error!("Lost the connection listening for events: {:?}", e);
+            }
+            tokio::time::sleep(RECONNECT_DELAY).await;
+        }
+    });
+}
+
+async fn listen(config: &tokio_postgres::Config, hub: &EventHub) -> Result<()> {
+    let (client, mut connection) = config
+        .connect(NoTls)
+        .await
+        .context("Failed to connect to the database.")?;
+    // The connection has to be polled for the client to work, so it runs on its own task
+    // and hands the notifications over.
+    let (sender, mut notifications) = mpsc::unbounded_channel();
+    let connection = tokio::spawn(async move {
+        let mut messages = stream::poll_fn(move |cx| connection.poll_message(cx));
+        while let Some(message) = messages.next().await {
+            if let AsyncMessage::Notification(notification) = message? {
+                let _ = sender.send(notification);
+            }
+        }
+        Ok::<_, tokio_postgres::Error>(())
+    });
+    client
+        .batch_execute(&format!("LISTEN {EVENT_CHANNEL}"))
+        .await?;
+    info!("Listening for events on `{}`.", EVENT_CHANNEL);
+    while let Some(notification) = notifications.recv().await {
+        match serde_json::from_str(notification.payload()) {
+            Ok(event) => hub.dispatch(event),
+            Err(e) => warn!("Ignored a malformed event: {}", e),
+        }
+    }
+    connection.await??;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::EventHub;
+    use crate::domain::events::{EventKind, UserEvent};
+
+    fn event(owner: &str) -> UserEvent {
+        UserEvent {
+            id: Some(1),
+            owner: owner.to_owned(),
+            kind: EventKind::TimerTick,
+            data: serde_json::Value::Null,
+        }
+    }
+
+    #[actix_web::test]
+    async fn イベントは持ち主のクライアントだけに届く() {
+        let hub = EventHub::default();
+        let mut desktop = hub.subscribe("test@example.com");
+        let mut phone = hub.subscribe("test@example.com");
+        let mut other = hub.subscribe("other@example.com");
+
+        hub.dispatch(event("test@example.com"));
+
+        assert_eq!("test@example.com", desktop.recv().await.unwrap().owner);
+        assert_eq!("test@example.com", phone.recv().await.unwrap().owner);
+        assert!(other.try_recv().is_err());
+    }
+
+    #[test]
+    fn クライアントがいなくなった持ち主を忘れる() {
+        let hub = EventHub::default();
+        drop(hub.subscribe("other@example.com"));
+
+        let _desktop = hub.subscribe("test@example.com");
+
+        let channels = hub.channels.lock().unwrap();
+        assert_eq!(
+            vec!["test@example.com"],
+            channels.keys().collect::<Vec<_>>()
+        );
+    }
+}
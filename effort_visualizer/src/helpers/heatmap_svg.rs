@@ -0,0 +1,204 @@
+use std::collections::HashMap;
+use std::fmt::Write;
+
+use anyhow::{bail, Context, Result};
+use chrono::{Datelike, Duration, Locale, NaiveDate, Weekday};
+
+use crate::dto::{Heatmap, HeatmapSvgQuery};
+use crate::helpers::locales::parse_locale;
+
+const DEFAULT_PALETTE: [&str; 5] = ["#ebedf0", "#9be9a8", "#40c463", "#30a14e", "#216e39"];
+const CELL_SIZE: i64 = 10;
+const CELL_STEP: i64 = 13;
+const TOP_MARGIN: i64 = 15;
+const FONT_SIZE: i64 = 9;
+
+pub struct SvgOptions {
+    pub palette: Vec<String>,
+    pub week_start: Weekday,
+    /// Falls back to the locale of the heatmap's owner when unset.
+    pub locale: Option<Locale>,
+}
+
+impl SvgOptions {
+    pub fn from_query(query: &HeatmapSvgQuery) -> Result<Self> {
+        let palette = match &query.palette {
+            Some(palette) => {
+                let colours = palette
+                    .split(',')
+                    .map(|colour| {
+                        let colour = colour.trim().trim_start_matches('#');
+                        let is_hex = matches!(colour.len(), 3 | 6)
+                            && colour.chars().all(|c| c.is_ascii_hexdigit());
+                        if !is_hex {
+                            bail!("`{colour}` is not a hex colour.");
+                        }
+                        Ok(format!("#{colour}"))
+                    })
+                    .collect::<Result<Vec<_>>>()?;
+                if colours.len() != DEFAULT_PALETTE.len() {
+                    bail!("The palette must have {} colours.", DEFAULT_PALETTE.len());
+                }
+                colours
+            }
+            None => DEFAULT_PALETTE.iter().map(|c| c.to_string()).collect(),
+        };
+        let locale = match &query.locale {
+            Some(locale) => Some(
+                parse_locale(locale)
+                    .with_context(|| format!("`{locale}` is not a supported locale."))?,
+            ),
+            None => None,
+        };
+        Ok(Self {
+            palette,
+            week_start: query.week_start.unwrap_or(Weekday::Sun),
+            locale,
+        })
+    }
+}
+
+/// Renders a day bucketed heatmap as a calendar grid with one column per week.
+pub fn render(heatmap: &Heatmap, options: &SvgOptions) -> String {
+    let levels: HashMap<NaiveDate, (u8, i64)> = heatmap
+        .cells
+        .iter()
+        .map(|cell| (cell.date, (cell.level, cell.total_seconds)))
+        .collect();
+    let locale = options
+        .locale
+        .or_else(|| parse_locale(&heatmap.locale))
+        .unwrap_or(Locale::en_US);
+    let grid_start =
+        heatmap.from - Duration::days(heatmap.from.weekday().days_since(options.week_start) as i64);
+    let columns = (heatmap.to - grid_start).num_days() / 7 + 1;
+
+    let mut body = String::new();
+    let mut labelled_month = None;
+    for column in 0..columns {
+        let first_day = (grid_start + Duration::days(column * 7)).max(heatmap.from);
+        let month = (first_day.year(), first_day.month());
+        if labelled_month != Some(month) {
+            labelled_month = Some(month);
+            let _ = write!(
+                body,
+                r#"<text x="{}" y="{}" font-size="{FONT_SIZE}">{}</text>"#,
+                column * CELL_STEP,
+                TOP_MARGIN - 5,
+                escape(&first_day.format_localized("%b", locale).to_string())
+            );
+        }
+    }
+
+    let mut date = heatmap.from;
+    while date <= heatmap.to {
+        let offset = (date - grid_start).num_days();
+        let (level, total_seconds) = levels.get(&date).copied().unwrap_or((0, 0));
+        let colour = &options.palette[(level as usize).min(options.palette.len() - 1)];
+        let _ = write!(
+            body,
+            r#"<rect x="{}" y="{}" width="{CELL_SIZE}" height="{CELL_SIZE}" rx="2" fill="{colour}"><title>{date}: {}h {}m</title></rect>"#,
+            offset / 7 * CELL_STEP,
+            TOP_MARGIN + offset % 7 * CELL_STEP,
+            total_seconds / 3600,
+            total_seconds % 3600 / 60
+        );
+        date += Duration::days(1);
+    }
+
+    format!(
+        r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif">{body}</svg>"#,
+        columns * CELL_STEP,
+        TOP_MARGIN + 7 * CELL_STEP
+    )
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{render, SvgOptions};
+    use crate::domain::heatmap::HeatmapBucket;
+    use crate::dto::{Heatmap, HeatmapCell, HeatmapSvgQuery};
+    use chrono::{NaiveDate, Weekday};
+
+    fn query() -> HeatmapSvgQuery {
+        HeatmapSvgQuery {
+            from: None,
+            to: None,
+            palette: None,
+            week_start: None,
+            locale: None,
+            category: None,
+            tag: None,
+            match_mode: None,
+        }
+    }
+
+    #[test]
+    fn 期間内の日数だけセルを描画する() {
+        let from = NaiveDate::from_ymd_opt(2023, 3, 29).unwrap();
+        let heatmap = Heatmap {
+            from,
+            to: NaiveDate::from_ymd_opt(2023, 4, 11).unwrap(),
+            bucket: HeatmapBucket::Day,
+            timezone: "UTC".to_owned(),
+            locale: "en_US".to_owned(),
+            cells: vec![HeatmapCell {
+                date: from,
+                total_seconds: 5400,
+                effort_count: 1,
+                level: 4,
+            }],
+        };
+        let options = SvgOptions {
+            week_start: Weekday::Mon,
+            ..SvgOptions::from_query(&query()).unwrap()
+        };
+
+        let svg = render(&heatmap, &options);
+
+        assert_eq!(14, svg.matches("<rect").count());
+        assert!(svg.contains(r##"fill="#216e39"><title>2023-03-29: 1h 30m</title>"##));
+        assert!(svg.contains(">Mar</text>"));
+        assert!(svg.contains(">Apr</text>"));
+    }
+
+    #[test]
+    fn ロケールに応じた月名を描画する() {
+        let heatmap = Heatmap {
+            from: NaiveDate::from_ymd_opt(2023, 4, 1).unwrap(),
+            to: NaiveDate::from_ymd_opt(2023, 4, 1).unwrap(),
+            bucket: HeatmapBucket::Day,
+            timezone: "Asia/Tokyo".to_owned(),
+            locale: "ja_JP".to_owned(),
+            cells: vec![],
+        };
+        let options = SvgOptions::from_query(&HeatmapSvgQuery {
+            locale: Some("fr-FR".to_owned()),
+            ..query()
+        })
+        .unwrap();
+
+        let svg = render(&heatmap, &options);
+        assert!(svg.contains(">avril</text>"));
+
+        // Without a locale in the query, the one of the heatmap's owner is used.
+        let svg = render(&heatmap, &SvgOptions::from_query(&query()).unwrap());
+        assert!(svg.contains("> 4月</text>"));
+    }
+
+    #[test]
+    fn 色の数が足りないパレットはエラーになる() {
+        let result = SvgOptions::from_query(&HeatmapSvgQuery {
+            palette: Some("ffffff,000000".to_owned()),
+            ..query()
+        });
+
+        assert!(result.is_err());
+    }
+}
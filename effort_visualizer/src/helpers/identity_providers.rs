@@ -0,0 +1,67 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use anyhow::{Context, Result};
+use tracing::warn;
+
+use super::environments::{
+    identity_provider_prefix, IdentityProviderKind, IdentityProviderSettings,
+};
+use super::token_verifier::{
+    GithubTokenVerifier, JwksTokenVerifier, StaticKeyTokenVerifier, TokenVerifier,
+};
+
+/// The identity providers users can sign in with, keyed by provider id.
+pub struct IdentityProviders {
+    verifiers: HashMap<String, Arc<dyn TokenVerifier>>,
+}
+
+impl IdentityProviders {
+    pub fn new(verifiers: HashMap<String, Arc<dyn TokenVerifier>>) -> Self {
+        Self { verifiers }
+    }
+
+    pub fn from_settings(settings: &[IdentityProviderSettings]) -> Result<Self> {
+        let verifiers = settings
+            .iter()
+            .map(|provider| Ok((provider.id.to_owned(), verifier(provider)?)))
+            .collect::<Result<_>>()?;
+        Ok(Self::new(verifiers))
+    }
+
+    pub fn get(&self, provider: &str) -> Option<&Arc<dyn TokenVerifier>> {
+        self.verifiers.get(provider)
+    }
+}
+
+fn verifier(provider: &IdentityProviderSettings) -> Result<Arc<dyn TokenVerifier>> {
+    let prefix = identity_provider_prefix(&provider.id);
+    if let Some(secret) = &provider.static_key {
+        warn!(
+            "ID tokens of {} are verified with {prefix}_STATIC_KEY. Never do this in production.",
+            provider.id
+        );
+        return Ok(Arc::new(StaticKeyTokenVerifier::new(
+            secret.as_bytes(),
+            provider.issuers.first().cloned().unwrap_or_default(),
+            provider.client_id.to_owned(),
+        )));
+    }
+    Ok(match provider.kind {
+        IdentityProviderKind::Oidc => Arc::new(JwksTokenVerifier::new(
+            provider
+                .jwks_url
+                .to_owned()
+                .with_context(|| format!("{prefix}_JWKS_URL must be set."))?,
+            provider.issuers.to_owned(),
+            provider.client_id.to_owned(),
+        )),
+        IdentityProviderKind::Github => Arc::new(GithubTokenVerifier::new(
+            provider.client_id.to_owned(),
+            provider
+                .client_secret
+                .to_owned()
+                .with_context(|| format!("{prefix}_CLIENT_SECRET must be set."))?,
+        )),
+    })
+}
@@ -0,0 +1,6 @@
+use chrono::Locale;
+
+/// Reads a locale such as `ja_JP` or `ja-JP`.
+pub fn parse_locale(name: &str) -> Option<Locale> {
+    Locale::try_from(name.replace('-', "_").as_str()).ok()
+}
@@ -1 +1,12 @@
+pub mod correlation_id;
+pub mod effort_export;
+pub mod effort_import;
 pub mod environments;
+pub mod event_hub;
+pub mod heatmap_svg;
+pub mod identity_providers;
+pub mod locales;
+pub mod session_keys;
+pub mod session_store;
+pub mod time_zones;
+pub mod token_verifier;
@@ -0,0 +1,217 @@
+use actix_web::{
+    cookie::{Cookie, CookieJar, Key},
+    dev::ServiceRequest,
+    http::header::{HeaderValue, COOKIE},
+};
+use anyhow::{bail, Context, Result};
+use base64::{engine::general_purpose::STANDARD, Engine};
+
+use super::environments::EnvVariables;
+
+/// Name of the cookie holding the encrypted session state.
+pub const SESSION_COOKIE_NAME: &str = "id";
+const MIN_KEY_LENGTH: usize = 64;
+
+/// The key sessions are encrypted with, plus the keys that were used before a rotation.
+#[derive(Clone)]
+pub struct SessionKeys {
+    pub current: Key,
+    pub previous: Vec<Key>,
+}
+
+impl SessionKeys {
+    pub fn load(env: &EnvVariables) -> Result<Self> {
+        let current = match (&env.session_key, &env.session_key_file) {
+            (Some(encoded), _) => decode_key("SESSION_KEY", encoded)?,
+            (None, Some(path)) => {
+                let bytes = std::fs::read(path)
+                    .with_context(|| format!("Failed to read SESSION_KEY_FILE `{path}`."))?;
+                to_key("SESSION_KEY_FILE", &bytes)?
+            }
+            (None, None) => bail!("Either SESSION_KEY or SESSION_KEY_FILE must be set."),
+        };
+        let previous = env
+            .session_previous_keys
+            .iter()
+            .map(|encoded| decode_key("SESSION_PREVIOUS_KEYS", encoded))
+            .collect::<Result<Vec<_>>>()?;
+        Ok(Self { current, previous })
+    }
+
+    /// Re-encrypts a session cookie that was issued with one of the previous keys
+    /// so that the session middleware, which only knows the current key, accepts it.
+    pub fn upgrade_session_cookie(&self, req: &mut ServiceRequest) {
+        if self.previous.is_empty() {
+            return;
+        }
+        let mut cookies: Vec<Cookie<'static>> = req
+            .headers()
+            .get_all(COOKIE)
+            .filter_map(|header| header.to_str().ok())
+            .flat_map(|header| header.split(';'))
+            .map(str::trim)
+            .filter(|cookie| !cookie.is_empty())
+            .filter_map(|cookie| Cookie::parse_encoded(cookie).ok())
+            .map(Cookie::into_owned)
+            .collect();
+        let session_cookie = match cookies
+            .iter_mut()
+            .find(|cookie| cookie.name() == SESSION_COOKIE_NAME)
+        {
+            Some(session_cookie) => session_cookie,
+            None => return,
+        };
+
+        let mut jar = CookieJar::new();
+        jar.add_original(session_cookie.clone());
+        if jar
+            .private(&self.current)
+            .get(SESSION_COOKIE_NAME)
+            .is_some()
+        {
+            return;
+        }
+        let decrypted = match self
+            .previous
+            .iter()
+            .find_map(|key| jar.private(key).get(SESSION_COOKIE_NAME))
+        {
+            Some(decrypted) => decrypted,
+            None => return,
+        };
+        let mut upgraded = CookieJar::new();
+        upgraded.private_mut(&self.current).add(decrypted);
+        if let Some(encrypted) = upgraded.get(SESSION_COOKIE_NAME) {
+            session_cookie.set_value(encrypted.value().to_owned());
+        }
+
+        let header = cookies
+            .iter()
+            .map(|cookie| cookie.encoded().to_string())
+            .collect::<Vec<_>>()
+            .join("; ");
+        if let Ok(header) = HeaderValue::from_str(&header) {
+            req.headers_mut().insert(COOKIE, header);
+        }
+    }
+}
+
+fn decode_key(name: &str, encoded: &str) -> Result<Key> {
+    let bytes = STANDARD
+        .decode(encoded.trim())
+        .with_context(|| format!("{name} is not valid base64."))?;
+    to_key(name, &bytes)
+}
+
+fn to_key(name: &str, bytes: &[u8]) -> Result<Key> {
+    if bytes.len() < MIN_KEY_LENGTH {
+        bail!(
+            "{name} must be at least {MIN_KEY_LENGTH} bytes long, but it is {} bytes.",
+            bytes.len()
+        );
+    }
+    Ok(Key::from(bytes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{SessionKeys, SESSION_COOKIE_NAME};
+    use crate::helpers::environments::{EnvVariables, SessionStoreKind};
+    use actix_session::{storage::CookieSessionStore, Session, SessionMiddleware};
+    use actix_web::{
+        cookie::{Key, SameSite},
+        dev::Service,
+        get, http,
+        test::{call_service, init_service, read_body, TestRequest},
+        App, HttpResponse,
+    };
+    use base64::{engine::general_purpose::STANDARD, Engine};
+
+    fn env(session_key: Option<String>, session_previous_keys: Vec<String>) -> EnvVariables {
+        EnvVariables {
+            db_server: "".to_owned(),
+            db_port: "".to_owned(),
+            db_name: "".to_owned(),
+            db_user_id: "".to_owned(),
+            db_password: "".to_owned(),
+            db_pool_max_size: 1,
+            db_pool_idle_timeout_seconds: 1,
+            db_pool_checkout_timeout_seconds: 1,
+            db_migrate_on_startup: false,
+            identity_providers: vec![],
+            session_key,
+            session_key_file: None,
+            session_previous_keys,
+            session_cookie_secure: true,
+            session_cookie_same_site: SameSite::Lax,
+            session_cookie_max_age: None,
+            session_cookie_domain: None,
+            session_store: SessionStoreKind::Cookie,
+        }
+    }
+
+    #[get("/count")]
+    async fn count(session: Session) -> Result<HttpResponse, actix_web::Error> {
+        let count = session.get::<i32>("count")?.unwrap_or(0) + 1;
+        session.insert("count", count)?;
+        Ok(HttpResponse::Ok().body(count.to_string()))
+    }
+
+    #[test]
+    fn 短すぎる鍵はエラーになる() {
+        let result = SessionKeys::load(&env(Some(STANDARD.encode([0; 32])), vec![]));
+
+        assert!(result.is_err());
+    }
+
+    #[test]
+    fn 鍵が設定されていないときエラーになる() {
+        let result = SessionKeys::load(&env(None, vec![]));
+
+        assert!(result.is_err());
+    }
+
+    #[actix_web::test]
+    async fn 以前の鍵で暗号化されたセッションを引き継ぐ() {
+        let old_key = [1; 64];
+        let new_key = [2; 64];
+
+        let old_app = init_service(
+            App::new()
+                .wrap(
+                    SessionMiddleware::builder(CookieSessionStore::default(), Key::from(&old_key))
+                        .cookie_name(SESSION_COOKIE_NAME.to_owned())
+                        .build(),
+                )
+                .service(count),
+        )
+        .await;
+        let resp = call_service(&old_app, TestRequest::get().uri("/count").to_request()).await;
+        let cookie = resp.response().cookies().next().unwrap().into_owned();
+
+        let keys = SessionKeys::load(&env(
+            Some(STANDARD.encode(new_key)),
+            vec![STANDARD.encode(old_key)],
+        ))
+        .unwrap();
+        let new_app = init_service(
+            App::new()
+                .wrap(
+                    SessionMiddleware::builder(CookieSessionStore::default(), keys.current.clone())
+                        .cookie_name(SESSION_COOKIE_NAME.to_owned())
+                        .build(),
+                )
+                .wrap_fn(move |mut req, srv| {
+                    keys.upgrade_session_cookie(&mut req);
+                    srv.call(req)
+                })
+                .service(count),
+        )
+        .await;
+        let req = TestRequest::get().uri("/count").cookie(cookie).to_request();
+
+        let resp = call_service(&new_app, req).await;
+        assert_eq!(http::StatusCode::OK, resp.status());
+        assert_eq!("2", read_body(resp).await);
+    }
+}
@@ -0,0 +1,231 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use actix_session::storage::{
+    CookieSessionStore, LoadError, SaveError, SessionKey, SessionStore, UpdateError,
+};
+use actix_web::cookie::time::Duration;
+use async_trait::async_trait;
+use chrono::Utc;
+use rand::{distributions::Alphanumeric, Rng};
+use tracing::{error, info};
+
+use crate::controllers::extractors::CURRENT_USER;
+use crate::domain::users::User;
+use crate::repositories::sessions_repository::SessionRepository;
+
+type SessionState = HashMap<String, String>;
+
+const SESSION_KEY_LENGTH: usize = 64;
+const REAPER_INTERVAL: std::time::Duration = std::time::Duration::from_secs(10 * 60);
+
+/// Keeps session state in the `sessions` table so that sessions can be listed and revoked.
+pub struct PostgresSessionStore {
+    session_repository: Box<dyn SessionRepository + Send + Sync>,
+}
+
+impl PostgresSessionStore {
+    pub fn new(session_repository: Box<dyn SessionRepository + Send + Sync>) -> Self {
+        Self { session_repository }
+    }
+
+    fn user_email(&self, session_state: &SessionState) -> Option<String> {
+        session_state
+            .get(CURRENT_USER)
+            .and_then(|user| serde_json::from_str::<User>(user).ok())
+            .map(|user| user.email)
+    }
+
+    fn expires_at(&self, ttl: &Duration) -> chrono::DateTime<Utc> {
+        Utc::now() + chrono::Duration::seconds(ttl.whole_seconds())
+    }
+}
+
+#[async_trait(?Send)]
+impl SessionStore for PostgresSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        let state = self
+            .session_repository
+            .find_state(session_key.as_ref())
+            .await
+            .map_err(LoadError::Other)?;
+        state
+            .map(|state| serde_json::from_str(&state))
+            .transpose()
+            .map_err(|e| LoadError::Deserialization(e.into()))
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        let state = serde_json::to_string(&session_state)
+            .map_err(|e| SaveError::Serialization(e.into()))?;
+        let session_key: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(SESSION_KEY_LENGTH)
+            .map(char::from)
+            .collect();
+        self.session_repository
+            .add(
+                &session_key,
+                self.user_email(&session_state),
+                &state,
+                self.expires_at(ttl),
+            )
+            .await
+            .map_err(SaveError::Other)?;
+        SessionKey::try_from(session_key).map_err(|e| SaveError::Other(e.into()))
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        let state = serde_json::to_string(&session_state)
+            .map_err(|e| UpdateError::Serialization(e.into()))?;
+        // A session revoked or expired in the meantime stays gone, as saving its state again
+        // would undo the revocation. The cookie then refers to nothing and the client is no
+        // longer logged in.
+        self.session_repository
+            .update(
+                session_key.as_ref(),
+                self.user_email(&session_state),
+                &state,
+                self.expires_at(ttl),
+            )
+            .await
+            .map_err(UpdateError::Other)?;
+        Ok(session_key)
+    }
+
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> anyhow::Result<()> {
+        self.session_repository
+            .update_expiry(session_key.as_ref(), self.expires_at(ttl))
+            .await
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> anyhow::Result<()> {
+        self.session_repository.delete(session_key.as_ref()).await
+    }
+}
+
+/// The session store selected by `SESSION_STORE`.
+pub enum AppSessionStore {
+    Cookie(CookieSessionStore),
+    Postgres(PostgresSessionStore),
+}
+
+#[async_trait(?Send)]
+impl SessionStore for AppSessionStore {
+    async fn load(&self, session_key: &SessionKey) -> Result<Option<SessionState>, LoadError> {
+        match self {
+            AppSessionStore::Cookie(store) => store.load(session_key).await,
+            AppSessionStore::Postgres(store) => store.load(session_key).await,
+        }
+    }
+
+    async fn save(
+        &self,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, SaveError> {
+        match self {
+            AppSessionStore::Cookie(store) => store.save(session_state, ttl).await,
+            AppSessionStore::Postgres(store) => store.save(session_state, ttl).await,
+        }
+    }
+
+    async fn update(
+        &self,
+        session_key: SessionKey,
+        session_state: SessionState,
+        ttl: &Duration,
+    ) -> Result<SessionKey, UpdateError> {
+        match self {
+            AppSessionStore::Cookie(store) => store.update(session_key, session_state, ttl).await,
+            AppSessionStore::Postgres(store) => store.update(session_key, session_state, ttl).await,
+        }
+    }
+
+    async fn update_ttl(&self, session_key: &SessionKey, ttl: &Duration) -> anyhow::Result<()> {
+        match self {
+            AppSessionStore::Cookie(store) => store.update_ttl(session_key, ttl).await,
+            AppSessionStore::Postgres(store) => store.update_ttl(session_key, ttl).await,
+        }
+    }
+
+    async fn delete(&self, session_key: &SessionKey) -> anyhow::Result<()> {
+        match self {
+            AppSessionStore::Cookie(store) => store.delete(session_key).await,
+            AppSessionStore::Postgres(store) => store.delete(session_key).await,
+        }
+    }
+}
+
+/// Periodically removes expired sessions from the `sessions` table.
+pub fn spawn_session_reaper(session_repository: Arc<dyn SessionRepository + Send + Sync>) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(REAPER_INTERVAL);
+        loop {
+            interval.tick().await;
+            match session_repository.delete_expired().await {
+                Ok(0) => {}
+                Ok(deleted) => info!("Removed {} expired sessions.", deleted),
+                Err(e) => error!("Failed to remove expired sessions: {}", e),
+            }
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+
+    use super::PostgresSessionStore;
+    use crate::repositories::sessions_repository::MockSessionRepository;
+    use actix_session::storage::{SessionKey, SessionStore};
+    use actix_web::cookie::time::Duration;
+
+    #[actix_web::test]
+    async fn ログインユーザのメールアドレスと共にセッションを保存する() {
+        let mut mock_repository = MockSessionRepository::new();
+        mock_repository
+            .expect_add()
+            .withf(|session_key, user_email, _, _| {
+                session_key.len() == 64 && user_email.as_deref() == Some("test@example.com")
+            })
+            .times(1)
+            .returning(|_, _, _, _| Ok(()));
+        let store = PostgresSessionStore::new(Box::new(mock_repository));
+        let state = HashMap::from([(
+            "current_user".to_owned(),
+            r#"{"email":"test@example.com","external_id":"","user_name":"","registered_date":{"secs_since_epoch":0,"nanos_since_epoch":0},"updated_date":{"secs_since_epoch":0,"nanos_since_epoch":0}}"#.to_owned(),
+        )]);
+
+        let result = store.save(state, &Duration::days(1)).await;
+
+        assert!(result.is_ok());
+    }
+
+    #[actix_web::test]
+    async fn 失効したセッションを更新しても作り直さない() {
+        let mut mock_repository = MockSessionRepository::new();
+        mock_repository
+            .expect_update()
+            .returning(|_, _, _, _| Ok(false));
+        mock_repository.expect_add().never();
+        let store = PostgresSessionStore::new(Box::new(mock_repository));
+        let old_key = SessionKey::try_from("revoked".to_owned()).unwrap();
+
+        let key = store
+            .update(old_key, HashMap::new(), &Duration::days(1))
+            .await
+            .unwrap();
+
+        assert_eq!("revoked", key.as_ref());
+    }
+}
@@ -0,0 +1,52 @@
+use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+use chrono_tz::Tz;
+
+/// The time zone named by a user's profile. Names are validated when the profile is saved,
+/// so an unknown one only comes from a stale session and falls back to UTC.
+pub fn parse_time_zone(name: &str) -> Tz {
+    name.parse().unwrap_or(Tz::UTC)
+}
+
+/// The instant `date` starts in `timezone`. Where the clocks skip midnight, the day starts at
+/// the first minute that exists.
+pub fn start_of_day(date: NaiveDate, timezone: Tz) -> DateTime<Utc> {
+    let midnight = date.and_hms_opt(0, 0, 0).unwrap_or_default();
+    let mut local = midnight;
+    loop {
+        if let Some(start) = timezone.from_local_datetime(&local).earliest() {
+            return start.with_timezone(&Utc);
+        }
+        local += Duration::minutes(1);
+        // Gaps are at most a day long, even when a zone skipped a whole date.
+        if local - midnight > Duration::days(1) {
+            return Utc.from_utc_datetime(&midnight);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::start_of_day;
+    use chrono::{NaiveDate, TimeZone, Utc};
+
+    #[test]
+    fn 日付の始まりをそのタイムゾーンで求める() {
+        let date = NaiveDate::from_ymd_opt(2023, 5, 1).unwrap();
+
+        assert_eq!(
+            Utc.with_ymd_and_hms(2023, 4, 30, 15, 0, 0).unwrap(),
+            start_of_day(date, chrono_tz::Asia::Tokyo)
+        );
+    }
+
+    #[test]
+    fn 真夜中が存在しない日は最初に存在する時刻から始まる() {
+        // Clocks in Santiago skipped from 00:00 to 01:00 on 2022-09-11.
+        let date = NaiveDate::from_ymd_opt(2022, 9, 11).unwrap();
+
+        assert_eq!(
+            Utc.with_ymd_and_hms(2022, 9, 11, 4, 0, 0).unwrap(),
+            start_of_day(date, chrono_tz::America::Santiago)
+        );
+    }
+}
@@ -0,0 +1,406 @@
+use std::time::{Duration, Instant};
+
+use anyhow::{anyhow, bail, Context, Result};
+use async_trait::async_trait;
+use jsonwebtoken::{
+    decode, decode_header,
+    jwk::{Jwk, JwkSet},
+    Algorithm, DecodingKey, Validation,
+};
+use mockall::automock;
+use reqwest::{
+    header::{ACCEPT, CACHE_CONTROL, USER_AGENT},
+    StatusCode,
+};
+use serde::Deserialize;
+use tokio::sync::RwLock;
+use tracing::info;
+
+/// Seconds of clock skew tolerated when checking `exp` and `nbf`.
+const LEEWAY_SECONDS: u64 = 60;
+/// How long a key set is used when the JWKS endpoint does not send `Cache-Control: max-age`.
+const DEFAULT_KEY_SET_TTL: Duration = Duration::from_secs(60 * 60);
+/// An unknown `kid` triggers a refetch at most this often, so forged tokens can't hammer the endpoint.
+const MIN_REFRESH_INTERVAL: Duration = Duration::from_secs(60);
+const ALGORITHMS: [Algorithm; 3] = [Algorithm::RS256, Algorithm::ES256, Algorithm::PS256];
+const GITHUB_API_URL: &str = "https://api.github.com";
+const GITHUB_MEDIA_TYPE: &str = "application/vnd.github+json";
+const GITHUB_USER_AGENT: &str = "effort_visualizer";
+
+/// The claims of a verified ID token the application relies on.
+#[derive(Clone, Debug, Deserialize, PartialEq)]
+pub struct IdTokenClaims {
+    pub sub: String,
+    /// Only set when the provider has verified that the user owns the address.
+    pub email: Option<String>,
+}
+
+/// The claims as an ID token carries them.
+#[derive(Deserialize)]
+struct RawIdTokenClaims {
+    sub: String,
+    email: Option<String>,
+    #[serde(default)]
+    email_verified: bool,
+}
+
+impl From<RawIdTokenClaims> for IdTokenClaims {
+    fn from(claims: RawIdTokenClaims) -> Self {
+        // Accounts are keyed by email, so an address nobody proved to own could take one over.
+        Self {
+            sub: claims.sub,
+            email: claims.email.filter(|_| claims.email_verified),
+        }
+    }
+}
+
+#[automock]
+#[async_trait]
+pub trait TokenVerifier: Send + Sync {
+    /// Checks the signature, `iss`, `aud`, `exp` and `nbf` of the token and returns its claims.
+    async fn verify(&self, token: &str) -> Result<IdTokenClaims>;
+}
+
+fn validation(algorithm: Algorithm, issuers: &[String], audience: &str) -> Validation {
+    let mut validation = Validation::new(algorithm);
+    validation.set_issuer(issuers);
+    validation.set_audience(&[audience]);
+    validation.set_required_spec_claims(&["exp", "iss", "aud", "sub"]);
+    validation.validate_nbf = true;
+    validation.leeway = LEEWAY_SECONDS;
+    validation
+}
+
+struct CachedKeySet {
+    keys: JwkSet,
+    fetched_at: Instant,
+    ttl: Duration,
+}
+
+/// Verifies tokens against the public keys an identity provider publishes as a JWKS document.
+pub struct JwksTokenVerifier {
+    jwks_url: String,
+    issuers: Vec<String>,
+    audience: String,
+    client: reqwest::Client,
+    key_set: RwLock<Option<CachedKeySet>>,
+}
+
+impl JwksTokenVerifier {
+    pub fn new(jwks_url: String, issuers: Vec<String>, audience: String) -> Self {
+        Self {
+            jwks_url,
+            issuers,
+            audience,
+            client: reqwest::Client::new(),
+            key_set: RwLock::new(None),
+        }
+    }
+
+    async fn find_key(&self, kid: &str) -> Result<Jwk> {
+        if let Some(key_set) = &*self.key_set.read().await {
+            if key_set.fetched_at.elapsed() < key_set.ttl {
+                if let Some(jwk) = key_set.keys.find(kid) {
+                    return Ok(jwk.clone());
+                }
+                if key_set.fetched_at.elapsed() < MIN_REFRESH_INTERVAL {
+                    bail!("The signing key `{kid}` is unknown.");
+                }
+            }
+        }
+
+        let mut key_set = self.key_set.write().await;
+        // Another request may have refreshed the key set while this one waited for the lock.
+        let refreshed =
+            matches!(&*key_set, Some(cached) if cached.fetched_at.elapsed() < MIN_REFRESH_INTERVAL);
+        if !refreshed {
+            *key_set = Some(self.fetch_key_set().await?);
+        }
+        key_set
+            .as_ref()
+            .and_then(|cached| cached.keys.find(kid))
+            .cloned()
+            .ok_or_else(|| anyhow!("The signing key `{kid}` is unknown."))
+    }
+
+    async fn fetch_key_set(&self) -> Result<CachedKeySet> {
+        let response = self
+            .client
+            .get(&self.jwks_url)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .with_context(|| format!("Failed to fetch the key set from `{}`.", self.jwks_url))?;
+        let ttl = response
+            .headers()
+            .get(CACHE_CONTROL)
+            .and_then(|value| value.to_str().ok())
+            .and_then(max_age)
+            .unwrap_or(DEFAULT_KEY_SET_TTL);
+        let keys: JwkSet = response.json().await.context("The key set is malformed.")?;
+        info!(
+            "Fetched {} signing keys from {}.",
+            keys.keys.len(),
+            self.jwks_url
+        );
+        Ok(CachedKeySet {
+            keys,
+            fetched_at: Instant::now(),
+            ttl,
+        })
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for JwksTokenVerifier {
+    async fn verify(&self, token: &str) -> Result<IdTokenClaims> {
+        let header = decode_header(token).context("The token is malformed.")?;
+        if !ALGORITHMS.contains(&header.alg) {
+            bail!(
+                "The token is signed with an unsupported algorithm {:?}.",
+                header.alg
+            );
+        }
+        let kid = header.kid.context("The token has no `kid`.")?;
+        let jwk = self.find_key(&kid).await?;
+        let key = DecodingKey::from_jwk(&jwk).context("The signing key is malformed.")?;
+        let claims = decode::<RawIdTokenClaims>(
+            token,
+            &key,
+            &validation(header.alg, &self.issuers, &self.audience),
+        )
+        .context("Token verification failed.")?
+        .claims;
+        Ok(claims.into())
+    }
+}
+
+fn max_age(cache_control: &str) -> Option<Duration> {
+    cache_control
+        .split(',')
+        .filter_map(|directive| directive.trim().strip_prefix("max-age="))
+        .find_map(|seconds| seconds.parse().ok())
+        .map(Duration::from_secs)
+}
+
+/// Verifies HS256 tokens signed with a shared secret. Meant for local runs and integration
+/// tests, which can mint their own tokens with the same secret.
+pub struct StaticKeyTokenVerifier {
+    key: DecodingKey,
+    validation: Validation,
+}
+
+impl StaticKeyTokenVerifier {
+    pub fn new(secret: &[u8], issuer: String, audience: String) -> Self {
+        Self {
+            key: DecodingKey::from_secret(secret),
+            validation: validation(Algorithm::HS256, &[issuer], &audience),
+        }
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for StaticKeyTokenVerifier {
+    async fn verify(&self, token: &str) -> Result<IdTokenClaims> {
+        Ok(
+            decode::<RawIdTokenClaims>(token, &self.key, &self.validation)
+                .context("Token verification failed.")?
+                .claims
+                .into(),
+        )
+    }
+}
+
+#[derive(Deserialize)]
+struct GithubTokenCheck {
+    user: GithubUser,
+}
+
+#[derive(Deserialize)]
+struct GithubUser {
+    id: i64,
+}
+
+#[derive(Deserialize)]
+struct GithubEmail {
+    email: String,
+    primary: bool,
+    verified: bool,
+}
+
+/// Verifies GitHub OAuth access tokens. GitHub does not issue ID tokens, so the token is checked
+/// with the API, which also confirms it was issued to our OAuth app rather than another one.
+pub struct GithubTokenVerifier {
+    client_id: String,
+    client_secret: String,
+    api_url: String,
+    client: reqwest::Client,
+}
+
+impl GithubTokenVerifier {
+    pub fn new(client_id: String, client_secret: String) -> Self {
+        Self {
+            client_id,
+            client_secret,
+            api_url: GITHUB_API_URL.to_owned(),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    async fn primary_email(&self, token: &str) -> Result<Option<String>> {
+        let emails: Vec<GithubEmail> = self
+            .client
+            .get(format!("{}/user/emails", self.api_url))
+            .header(ACCEPT, GITHUB_MEDIA_TYPE)
+            .header(USER_AGENT, GITHUB_USER_AGENT)
+            .bearer_auth(token)
+            .send()
+            .await
+            .and_then(|response| response.error_for_status())
+            .context("Failed to fetch the email addresses from GitHub.")?
+            .json()
+            .await?;
+        Ok(emails
+            .into_iter()
+            .find(|email| email.primary && email.verified)
+            .map(|email| email.email))
+    }
+}
+
+#[async_trait]
+impl TokenVerifier for GithubTokenVerifier {
+    async fn verify(&self, token: &str) -> Result<IdTokenClaims> {
+        let response = self
+            .client
+            .post(format!(
+                "{}/applications/{}/token",
+                self.api_url, self.client_id
+            ))
+            .header(ACCEPT, GITHUB_MEDIA_TYPE)
+            .header(USER_AGENT, GITHUB_USER_AGENT)
+            .basic_auth(&self.client_id, Some(&self.client_secret))
+            .json(&serde_json::json!({ "access_token": token }))
+            .send()
+            .await
+            .context("Failed to reach GitHub.")?;
+        if response.status() == StatusCode::NOT_FOUND
+            || response.status() == StatusCode::UNPROCESSABLE_ENTITY
+        {
+            bail!("Token verification failed.");
+        }
+        let check: GithubTokenCheck = response
+            .error_for_status()
+            .context("Failed to check the token with GitHub.")?
+            .json()
+            .await?;
+        // The public email of the profile may be unverified, so only the primary one is used.
+        Ok(IdTokenClaims {
+            sub: check.user.id.to_string(),
+            email: self.primary_email(token).await?,
+        })
+    }
+}
+
+#[cfg(test)]
+pub mod test_tokens {
+    use super::StaticKeyTokenVerifier;
+    use jsonwebtoken::{encode, EncodingKey, Header};
+    use serde_json::json;
+
+    const SECRET: &[u8] = b"secret for tests";
+    const ISSUER: &str = "https://issuer.example.com";
+    const AUDIENCE: &str = "effort_visualizer";
+
+    pub fn verifier() -> StaticKeyTokenVerifier {
+        StaticKeyTokenVerifier::new(SECRET, ISSUER.to_owned(), AUDIENCE.to_owned())
+    }
+
+    /// Mints a token that `verifier()` accepts unless the claims are overridden.
+    pub fn mint(overrides: serde_json::Value) -> String {
+        let now = chrono::Utc::now().timestamp();
+        let mut claims = json!({
+            "iss": ISSUER,
+            "aud": AUDIENCE,
+            "sub": "external-id",
+            "email": "test@example.com",
+            "email_verified": true,
+            "iat": now,
+            "nbf": now,
+            "exp": now + 600,
+        });
+        for (name, value) in overrides.as_object().unwrap() {
+            claims[name] = value.clone();
+        }
+        encode(
+            &Header::default(),
+            &claims,
+            &EncodingKey::from_secret(SECRET),
+        )
+        .unwrap()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::test_tokens::{mint, verifier};
+    use super::{max_age, TokenVerifier};
+    use serde_json::json;
+    use std::time::Duration;
+
+    #[actix_web::test]
+    async fn 正しいトークンのクレームを返す() {
+        let claims = verifier().verify(&mint(json!({}))).await.unwrap();
+
+        assert_eq!("external-id", claims.sub);
+        assert_eq!(Some("test@example.com".to_owned()), claims.email);
+    }
+
+    #[actix_web::test]
+    async fn 確認されていないメールアドレスは使わない() {
+        let claims = verifier()
+            .verify(&mint(json!({ "email_verified": false })))
+            .await
+            .unwrap();
+
+        assert_eq!("external-id", claims.sub);
+        assert_eq!(None, claims.email);
+    }
+
+    #[actix_web::test]
+    async fn 発行者やオーディエンスが異なるトークンを拒否する() {
+        let verifier = verifier();
+
+        assert!(verifier
+            .verify(&mint(json!({ "iss": "https://evil.example.com" })))
+            .await
+            .is_err());
+        assert!(verifier
+            .verify(&mint(json!({ "aud": "another_app" })))
+            .await
+            .is_err());
+    }
+
+    #[actix_web::test]
+    async fn 有効期間外のトークンを拒否する() {
+        let now = chrono::Utc::now().timestamp();
+        let verifier = verifier();
+
+        assert!(verifier
+            .verify(&mint(json!({ "exp": now - 3600 })))
+            .await
+            .is_err());
+        assert!(verifier
+            .verify(&mint(json!({ "nbf": now + 3600 })))
+            .await
+            .is_err());
+    }
+
+    #[test]
+    fn max_ageを鍵セットの有効期間にする() {
+        assert_eq!(
+            Some(Duration::from_secs(21600)),
+            max_age("public, max-age=21600, must-revalidate")
+        );
+        assert_eq!(None, max_age("no-cache"));
+    }
+}
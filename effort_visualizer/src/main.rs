@@ -1,90 +1,537 @@
+mod cli;
 mod controllers;
 mod domain;
 mod dto;
 mod helpers;
+mod migrations;
 mod repositories;
 mod usecases;
 
 use actix_cors::Cors;
-use actix_session::{storage::CookieSessionStore, SessionMiddleware};
-use actix_web::{cookie::Key, http, middleware::Logger, web::Data, App, HttpServer};
-use anyhow::Result;
+use actix_session::{
+    config::{BrowserSession, PersistentSession, SessionLifecycle},
+    storage::CookieSessionStore,
+    SessionMiddleware,
+};
+use actix_web::{
+    cookie::{time::Duration, SameSite},
+    dev::Service,
+    http,
+    middleware::Logger,
+    web::{self, Data},
+    App, HttpServer,
+};
+use anyhow::{anyhow, bail, Context, Result};
+use clap::Parser;
 
+use cli::{Cli, Command, EmitCommand, EmitOpenApiArgs, MigrateCommand, ServeArgs, UserCommand};
 use controllers::{
     api_doc::ApiDoc,
-    authentication_controllers::{login, signup},
+    authentication_controllers::{login, logout, me, signup},
+    category_controllers::{add_category, delete_category, get_categories, update_category},
+    effort_controllers::{
+        add_effort, delete_effort, export_efforts, get_effort, get_efforts, import_efforts,
+        update_effort,
+    },
+    errors::{configure_extractors, route_not_found},
+    event_controllers::{connect_events, stream_events},
+    goal_controllers::{add_goal, delete_goal, get_goal_progress, get_goals, update_goal},
+    heatmap_controllers::{
+        get_heatmap, get_heatmap_svg, get_public_heatmap, get_public_heatmap_svg,
+    },
+    identity_controllers::{get_identities, link_identity, unlink_identity},
+    pomodoro_controllers::get_pomodoro_stats,
+    session_controllers::{get_sessions, revoke_session},
+    streak_controllers::{get_public_streaks, get_streaks},
+    tag_controllers::{add_tag, delete_tag, get_tags, merge_tag, rename_tag},
+    timer_controllers::{get_timer, pause_timer, resume_timer, start_timer, stop_timer},
+    user_controllers::{
+        delete_account, export_data, publish_calendar, unpublish_calendar, update_profile,
+    },
+};
+use helpers::correlation_id::{self, CORRELATION_ID_HEADER};
+use helpers::environments::{
+    identity_provider_prefix, EnvVariables, IdentityProviderKind, IdentityProviderSettings,
+    SessionStoreKind,
 };
-use helpers::environments::EnvVariables;
+use helpers::event_hub::{spawn_event_listener, EventHub};
+use helpers::identity_providers::IdentityProviders;
+use helpers::session_keys::{SessionKeys, SESSION_COOKIE_NAME};
+use helpers::session_store::{spawn_session_reaper, AppSessionStore, PostgresSessionStore};
+use migrations::{migrate_down, migrate_up, migration_status};
+use repositories::categories_repository::CategoryRepositoryImpl;
+use repositories::database::{
+    connection_config, create_pool, get_client, spawn_idle_connection_reaper,
+};
+use repositories::efforts_repository::{EffortRepository, EffortRepositoryImpl};
+use repositories::events_repository::EventRepositoryImpl;
+use repositories::goals_repository::GoalRepositoryImpl;
+use repositories::identities_repository::UserIdentityRepositoryImpl;
+use repositories::pomodoros_repository::PomodoroRepositoryImpl;
+use repositories::sessions_repository::SessionRepositoryImpl;
+use repositories::tags_repository::TagRepositoryImpl;
+use repositories::timers_repository::TimerRepositoryImpl;
 use repositories::users_repository::{UserRepository, UserRepositoryImpl};
 use usecases::authentication_usecase::{AuthenticationUsecase, AuthenticationUsecaseImpl};
+use usecases::category_usecase::{CategoryUsecase, CategoryUsecaseImpl};
+use usecases::effort_usecase::{EffortUsecase, EffortUsecaseImpl};
+use usecases::event_usecase::{EventUsecase, EventUsecaseImpl};
+use usecases::export_usecase::{ExportUsecase, ExportUsecaseImpl};
+use usecases::goal_usecase::{GoalUsecase, GoalUsecaseImpl};
+use usecases::heatmap_usecase::{HeatmapUsecase, HeatmapUsecaseImpl};
+use usecases::pomodoro_usecase::{PomodoroUsecase, PomodoroUsecaseImpl};
+use usecases::session_usecase::{SessionUsecase, SessionUsecaseImpl};
+use usecases::streak_usecase::{StreakUsecase, StreakUsecaseImpl};
+use usecases::tag_usecase::{TagUsecase, TagUsecaseImpl};
+use usecases::timer_usecase::{TimerUsecase, TimerUsecaseImpl};
+use usecases::user_usecase::{UserUsecase, UserUsecaseImpl};
 use utoipa::OpenApi;
 use utoipa_swagger_ui::SwaggerUi;
 
 use std::env;
+use std::str::FromStr;
+use std::sync::Arc;
 
 #[actix_web::main]
 async fn main() -> Result<()> {
-    let args: Vec<String> = env::args().collect();
-    if args.contains(&"emit".to_string()) && args.contains(&"open-api-file".to_string()) {
-        println!("{}", ApiDoc::openapi().to_pretty_json().unwrap());
-        return Ok(());
+    let cli = Cli::parse();
+    match cli
+        .command
+        .unwrap_or_else(|| Command::Serve(ServeArgs::default()))
+    {
+        Command::Serve(args) => serve(args).await,
+        Command::Emit(EmitCommand::Openapi(args)) => emit_openapi(args),
+        Command::Migrate(command) => migrate(command).await,
+        Command::CheckConfig => check_config().await,
+        Command::User(command) => manage_users(command).await,
     }
+}
 
+async fn serve(args: ServeArgs) -> Result<()> {
     std::env::set_var("RUST_BACKTRACE", "1");
     init_logger();
-    let secret_key = Key::generate();
     let env = Data::new(get_env_settings()?);
+    let session_keys = SessionKeys::load(&env)?;
+    let pool = create_pool(&env)?;
+    if env.db_migrate_on_startup {
+        migrate_up(&pool).await?;
+    }
+    spawn_idle_connection_reaper(
+        pool.clone(),
+        std::time::Duration::from_secs(env.db_pool_idle_timeout_seconds),
+    );
+    if env.session_store == SessionStoreKind::Postgres {
+        spawn_session_reaper(Arc::new(SessionRepositoryImpl::new(pool.clone())));
+    }
+    let event_hub = Data::new(EventHub::default());
+    spawn_event_listener(connection_config(&env)?, event_hub.clone().into_inner());
+    let identity_providers = Arc::new(IdentityProviders::from_settings(&env.identity_providers)?);
     HttpServer::new(move || {
-        let repository: Box<dyn UserRepository + Send + Sync> = Box::new(UserRepositoryImpl::new(
-            env.db_server.to_owned(),
-            env.db_port.to_owned(),
-            env.db_name.to_owned(),
-            env.db_user_id.to_owned(),
-            env.db_password.to_owned(),
+        let repository: Box<dyn UserRepository + Send + Sync> =
+            Box::new(UserRepositoryImpl::new(pool.clone()));
+        let authentication_usecase: Data<Box<dyn AuthenticationUsecase>> =
+            Data::new(Box::new(AuthenticationUsecaseImpl::new(
+                identity_providers.clone(),
+                repository,
+                Box::new(UserIdentityRepositoryImpl::new(pool.clone())),
+            )));
+        let effort_repository: Box<dyn EffortRepository + Send + Sync> =
+            Box::new(EffortRepositoryImpl::new(pool.clone()));
+        let effort_usecase: Data<Box<dyn EffortUsecase>> =
+            Data::new(Box::new(EffortUsecaseImpl::new(
+                effort_repository,
+                Box::new(CategoryRepositoryImpl::new(pool.clone())),
+            )));
+        let category_usecase: Data<Box<dyn CategoryUsecase>> = Data::new(Box::new(
+            CategoryUsecaseImpl::new(Box::new(CategoryRepositoryImpl::new(pool.clone()))),
         ));
-        let authentication_usecase: Data<Box<dyn AuthenticationUsecase>> = Data::new(Box::new(
-            AuthenticationUsecaseImpl::new(env.clone().into_inner(), repository),
+        let tag_usecase: Data<Box<dyn TagUsecase>> = Data::new(Box::new(TagUsecaseImpl::new(
+            Box::new(TagRepositoryImpl::new(pool.clone())),
+        )));
+        let goal_usecase: Data<Box<dyn GoalUsecase>> = Data::new(Box::new(GoalUsecaseImpl::new(
+            Box::new(GoalRepositoryImpl::new(pool.clone())),
+            Box::new(CategoryRepositoryImpl::new(pool.clone())),
+            Box::new(EffortRepositoryImpl::new(pool.clone())),
+        )));
+        let timer_usecase: Data<Box<dyn TimerUsecase>> =
+            Data::new(Box::new(TimerUsecaseImpl::new(
+                Box::new(TimerRepositoryImpl::new(pool.clone())),
+                Box::new(CategoryRepositoryImpl::new(pool.clone())),
+            )));
+        let event_usecase: Data<Box<dyn EventUsecase>> =
+            Data::new(Box::new(EventUsecaseImpl::new(
+                Box::new(EventRepositoryImpl::new(pool.clone())),
+                Box::new(GoalRepositoryImpl::new(pool.clone())),
+                Box::new(EffortRepositoryImpl::new(pool.clone())),
+            )));
+        let pomodoro_usecase: Data<Box<dyn PomodoroUsecase>> = Data::new(Box::new(
+            PomodoroUsecaseImpl::new(Box::new(PomodoroRepositoryImpl::new(pool.clone()))),
         ));
+        let heatmap_usecase: Data<Box<dyn HeatmapUsecase>> =
+            Data::new(Box::new(HeatmapUsecaseImpl::new(
+                Box::new(UserRepositoryImpl::new(pool.clone())),
+                Box::new(EffortRepositoryImpl::new(pool.clone())),
+            )));
+        let streak_usecase: Data<Box<dyn StreakUsecase>> =
+            Data::new(Box::new(StreakUsecaseImpl::new(
+                Box::new(UserRepositoryImpl::new(pool.clone())),
+                Box::new(EffortRepositoryImpl::new(pool.clone())),
+            )));
+        let session_usecase: Data<Box<dyn SessionUsecase>> =
+            Data::new(Box::new(SessionUsecaseImpl::new(
+                env.session_store,
+                Box::new(SessionRepositoryImpl::new(pool.clone())),
+            )));
+        let user_usecase: Data<Box<dyn UserUsecase>> = Data::new(Box::new(UserUsecaseImpl::new(
+            Box::new(UserRepositoryImpl::new(pool.clone())),
+        )));
+        let export_usecase: Data<Box<dyn ExportUsecase>> =
+            Data::new(Box::new(ExportUsecaseImpl::new(
+                Box::new(UserRepositoryImpl::new(pool.clone())),
+                Box::new(UserIdentityRepositoryImpl::new(pool.clone())),
+                Box::new(CategoryRepositoryImpl::new(pool.clone())),
+                Box::new(TagRepositoryImpl::new(pool.clone())),
+                Box::new(EffortRepositoryImpl::new(pool.clone())),
+                Box::new(GoalRepositoryImpl::new(pool.clone())),
+                Box::new(TimerRepositoryImpl::new(pool.clone())),
+                Box::new(PomodoroRepositoryImpl::new(pool.clone())),
+                Box::new(SessionRepositoryImpl::new(pool.clone())),
+                Box::new(EventRepositoryImpl::new(pool.clone())),
+            )));
+        let session_store = match env.session_store {
+            SessionStoreKind::Cookie => AppSessionStore::Cookie(CookieSessionStore::default()),
+            SessionStoreKind::Postgres => AppSessionStore::Postgres(PostgresSessionStore::new(
+                Box::new(SessionRepositoryImpl::new(pool.clone())),
+            )),
+        };
         let cors = Cors::default()
             .allowed_origin("http://localhost:8081")
-            .allowed_methods(vec!["GET", "POST"])
+            .allowed_methods(vec!["GET", "POST", "PUT", "PATCH", "DELETE"])
             .allowed_headers(vec![http::header::AUTHORIZATION, http::header::ACCEPT])
             .allowed_header(http::header::CONTENT_TYPE)
+            .allowed_header(CORRELATION_ID_HEADER)
+            .expose_headers(vec![CORRELATION_ID_HEADER])
             .max_age(3600);
         App::new()
             .wrap(Logger::default())
             .wrap(cors)
-            .wrap(SessionMiddleware::new(
-                CookieSessionStore::default(),
-                secret_key.clone(),
-            ))
+            .wrap(
+                SessionMiddleware::builder(session_store, session_keys.current.clone())
+                    .cookie_name(SESSION_COOKIE_NAME.to_owned())
+                    .cookie_secure(env.session_cookie_secure)
+                    .cookie_same_site(env.session_cookie_same_site)
+                    .cookie_domain(env.session_cookie_domain.to_owned())
+                    .session_lifecycle(session_lifecycle(&env))
+                    .build(),
+            )
+            .wrap_fn({
+                let session_keys = session_keys.clone();
+                move |mut req, srv| {
+                    session_keys.upgrade_session_cookie(&mut req);
+                    srv.call(req)
+                }
+            })
+            .wrap_fn(|req, srv| {
+                let correlation_id = correlation_id::from_request(&req);
+                correlation_id::scope(correlation_id, srv.call(req))
+            })
+            .configure(configure_extractors)
             .app_data(env.clone())
             .app_data(authentication_usecase)
+            .app_data(effort_usecase)
+            .app_data(heatmap_usecase)
+            .app_data(streak_usecase)
+            .app_data(session_usecase)
+            .app_data(user_usecase)
+            .app_data(export_usecase)
+            .app_data(category_usecase)
+            .app_data(tag_usecase)
+            .app_data(goal_usecase)
+            .app_data(timer_usecase)
+            .app_data(pomodoro_usecase)
+            .app_data(event_usecase)
+            .app_data(event_hub.clone())
             .service(login)
             .service(signup)
+            .service(me)
+            .service(logout)
+            .service(update_profile)
+            .service(delete_account)
+            .service(export_data)
+            .service(publish_calendar)
+            .service(unpublish_calendar)
+            .service(get_efforts)
+            // Registered before `/efforts/{id}`, which would take `export` for an id.
+            .service(export_efforts)
+            .service(get_effort)
+            .service(add_effort)
+            .service(update_effort)
+            .service(delete_effort)
+            .service(import_efforts)
+            .service(get_categories)
+            .service(add_category)
+            .service(update_category)
+            .service(delete_category)
+            .service(get_tags)
+            .service(add_tag)
+            .service(rename_tag)
+            .service(merge_tag)
+            .service(delete_tag)
+            .service(get_goals)
+            .service(add_goal)
+            .service(update_goal)
+            .service(delete_goal)
+            .service(get_goal_progress)
+            .service(get_timer)
+            .service(start_timer)
+            .service(pause_timer)
+            .service(resume_timer)
+            .service(stop_timer)
+            .service(get_pomodoro_stats)
+            .service(connect_events)
+            .service(stream_events)
+            .service(get_heatmap)
+            .service(get_heatmap_svg)
+            .service(get_public_heatmap)
+            .service(get_public_heatmap_svg)
+            .service(get_streaks)
+            .service(get_public_streaks)
+            .service(get_identities)
+            .service(link_identity)
+            .service(unlink_identity)
+            .service(get_sessions)
+            .service(revoke_session)
             .service(
                 SwaggerUi::new("/swagger-ui/{_:.*}")
                     .url("/api-doc/opanapi.json", ApiDoc::openapi()),
             )
+            .default_service(web::to(route_not_found))
     })
-    .bind(("0.0.0.0", 8080))
-    .expect("Can't running HTTP Server")
+    .bind((args.bind.as_str(), args.port))
+    .with_context(|| format!("Can't bind the HTTP server to {}:{}.", args.bind, args.port))?
     .run()
     .await?;
     Ok(())
 }
 
+fn emit_openapi(args: EmitOpenApiArgs) -> Result<()> {
+    let document = if args.yaml {
+        ApiDoc::openapi().to_yaml()?
+    } else {
+        ApiDoc::openapi().to_pretty_json()?
+    };
+    match args.out {
+        Some(path) => std::fs::write(&path, document)
+            .with_context(|| format!("Failed to write `{}`.", path.display()))?,
+        None => println!("{document}"),
+    }
+    Ok(())
+}
+
+async fn migrate(command: MigrateCommand) -> Result<()> {
+    init_logger();
+    let pool = create_pool(&get_env_settings()?)?;
+    match command {
+        MigrateCommand::Up => {
+            let applied = migrate_up(&pool).await?;
+            if applied.is_empty() {
+                println!("The schema is up to date.");
+            }
+            for migration in applied {
+                println!("Applied {:04} {}", migration.version, migration.name);
+            }
+        }
+        MigrateCommand::Down => match migrate_down(&pool).await? {
+            Some(migration) => println!("Reverted {:04} {}", migration.version, migration.name),
+            None => println!("No migration has been applied."),
+        },
+        MigrateCommand::Status => {
+            for status in migration_status(&pool).await? {
+                let applied_at = status
+                    .applied_at
+                    .map(|applied_at| applied_at.to_rfc3339())
+                    .unwrap_or_else(|| "pending".to_owned());
+                println!("{:04} {:<24} {}", status.version, status.name, applied_at);
+            }
+        }
+    }
+    Ok(())
+}
+
+async fn check_config() -> Result<()> {
+    let env = get_env_settings()?;
+    SessionKeys::load(&env)?;
+    let pool = create_pool(&env)?;
+    get_client(&pool).await?.execute("SELECT 1", &[]).await?;
+    let pending = migration_status(&pool)
+        .await?
+        .iter()
+        .filter(|status| status.applied_at.is_none())
+        .count();
+    println!("The configuration is valid.");
+    if pending > 0 {
+        println!("{pending} migrations are pending. Run `migrate up` to apply them.");
+    }
+    Ok(())
+}
+
+async fn manage_users(command: UserCommand) -> Result<()> {
+    let repository = UserRepositoryImpl::new(create_pool(&get_env_settings()?)?);
+    match command {
+        UserCommand::List => {
+            for user in repository.find_all().await? {
+                println!("{}\t{}", user.email, user.user_name);
+            }
+        }
+        UserCommand::Show { email } => match repository.find(&email).await? {
+            Some(user) => println!("{}", serde_json::to_string_pretty(&user)?),
+            None => bail!("The user `{email}` is not found."),
+        },
+        UserCommand::Delete { email } => {
+            if !repository.delete(&email).await? {
+                bail!("The user `{email}` is not found.");
+            }
+            println!("Deleted `{email}`.");
+        }
+    }
+    Ok(())
+}
+
 fn init_logger() {
     tracing_subscriber::fmt().json().flatten_event(true).init();
 }
 
+fn session_lifecycle(env: &EnvVariables) -> SessionLifecycle {
+    match env.session_cookie_max_age {
+        Some(max_age) => PersistentSession::default()
+            .session_ttl(Duration::seconds(max_age))
+            .into(),
+        None => BrowserSession::default().into(),
+    }
+}
+
 pub fn get_env_settings() -> Result<EnvVariables> {
+    let db_pool_max_size = parse_env_var("DB_POOL_MAX_SIZE", 16)?;
+    if db_pool_max_size == 0 {
+        bail!("DB_POOL_MAX_SIZE must be at least 1.");
+    }
+    // Idle connections are looked for every half of the timeout, which must not be zero.
+    let db_pool_idle_timeout_seconds = parse_env_var("DB_POOL_IDLE_TIMEOUT_SECONDS", 10 * 60)?;
+    if db_pool_idle_timeout_seconds < 2 {
+        bail!("DB_POOL_IDLE_TIMEOUT_SECONDS must be at least 2.");
+    }
     Ok(EnvVariables {
         db_server: env::var("DB_SERVERNAME")?,
         db_port: env::var("DB_PORT")?,
         db_name: env::var("DB_NAME")?,
         db_user_id: env::var("DB_USERID")?,
         db_password: env::var("DB_PASSWORD")?,
-        google_client_id: env::var("GOOGLE_CLIENT_ID")?,
+        db_pool_max_size,
+        db_pool_idle_timeout_seconds,
+        db_pool_checkout_timeout_seconds: parse_env_var("DB_POOL_CHECKOUT_TIMEOUT_SECONDS", 5)?,
+        db_migrate_on_startup: parse_env_var("DB_MIGRATE_ON_STARTUP", false)?,
+        identity_providers: env::var("IDENTITY_PROVIDERS")
+            .unwrap_or_else(|_| "google".to_owned())
+            .split(',')
+            .map(str::trim)
+            .filter(|id| !id.is_empty())
+            .map(identity_provider_settings)
+            .collect::<Result<_>>()?,
+        session_key: env::var("SESSION_KEY").ok(),
+        session_key_file: env::var("SESSION_KEY_FILE").ok(),
+        session_previous_keys: env::var("SESSION_PREVIOUS_KEYS")
+            .map(|keys| {
+                keys.split(',')
+                    .filter(|key| !key.trim().is_empty())
+                    .map(|key| key.trim().to_owned())
+                    .collect()
+            })
+            .unwrap_or_default(),
+        session_cookie_secure: parse_env_var("SESSION_COOKIE_SECURE", true)?,
+        session_cookie_same_site: match env::var("SESSION_COOKIE_SAME_SITE")
+            .unwrap_or_else(|_| "lax".to_owned())
+            .to_lowercase()
+            .as_str()
+        {
+            "strict" => SameSite::Strict,
+            "lax" => SameSite::Lax,
+            "none" => SameSite::None,
+            other => {
+                bail!("SESSION_COOKIE_SAME_SITE must be strict, lax or none, but it is `{other}`.")
+            }
+        },
+        session_cookie_max_age: match env::var("SESSION_COOKIE_MAX_AGE") {
+            Ok(max_age) => Some(
+                max_age
+                    .parse()
+                    .context("SESSION_COOKIE_MAX_AGE must be a number of seconds.")?,
+            ),
+            Err(_) => None,
+        },
+        session_cookie_domain: env::var("SESSION_COOKIE_DOMAIN").ok(),
+        session_store: match env::var("SESSION_STORE")
+            .unwrap_or_else(|_| "cookie".to_owned())
+            .to_lowercase()
+            .as_str()
+        {
+            "cookie" => SessionStoreKind::Cookie,
+            "postgres" => SessionStoreKind::Postgres,
+            other => bail!("SESSION_STORE must be cookie or postgres, but it is `{other}`."),
+        },
     })
 }
+
+/// Reads the settings of a provider from variables prefixed with its upper-cased id,
+/// e.g. `GITLAB_CLIENT_ID`. Google and GitLab come with their issuer and JWKS URL.
+fn identity_provider_settings(id: &str) -> Result<IdentityProviderSettings> {
+    let prefix = identity_provider_prefix(id);
+    let var = |name: &str| env::var(format!("{prefix}_{name}")).ok();
+    let (default_issuers, default_jwks_url) = match id {
+        "google" => (
+            Some("accounts.google.com,https://accounts.google.com"),
+            Some("https://www.googleapis.com/oauth2/v3/certs"),
+        ),
+        "gitlab" => (
+            Some("https://gitlab.com"),
+            Some("https://gitlab.com/oauth/discovery/keys"),
+        ),
+        _ => (None, None),
+    };
+    let kind =
+        match var("KIND")
+            .as_deref()
+            .unwrap_or(if id == "github" { "github" } else { "oidc" })
+        {
+            "oidc" => IdentityProviderKind::Oidc,
+            "github" => IdentityProviderKind::Github,
+            other => bail!("{prefix}_KIND must be oidc or github, but it is `{other}`."),
+        };
+    let issuers: Vec<String> = var("ISSUER")
+        .or_else(|| default_issuers.map(str::to_owned))
+        .unwrap_or_default()
+        .split(',')
+        .map(|issuer| issuer.trim().to_owned())
+        .filter(|issuer| !issuer.is_empty())
+        .collect();
+    if kind == IdentityProviderKind::Oidc && issuers.is_empty() {
+        bail!("{prefix}_ISSUER must be set.");
+    }
+    Ok(IdentityProviderSettings {
+        id: id.to_owned(),
+        kind,
+        client_id: var("CLIENT_ID").with_context(|| format!("{prefix}_CLIENT_ID must be set."))?,
+        client_secret: var("CLIENT_SECRET"),
+        issuers,
+        jwks_url: var("JWKS_URL").or_else(|| default_jwks_url.map(str::to_owned)),
+        static_key: var("STATIC_KEY"),
+    })
+}
+
+fn parse_env_var<T: FromStr>(name: &str, default: T) -> Result<T> {
+    match env::var(name) {
+        Ok(value) => value
+            .parse()
+            .map_err(|_| anyhow!("{name} has an invalid value `{value}`.")),
+        Err(_) => Ok(default),
+    }
+}
@@ -0,0 +1,7 @@
+create table if not exists users (
+  email varchar primary key,
+  external_id varchar not null,
+  user_name varchar not null,
+  registered_date TIMESTAMP not null,
+  updated_date TIMESTAMP not null
+);
@@ -0,0 +1,11 @@
+create table if not exists efforts (
+  id bigserial primary key,
+  owner varchar not null references users(email) on delete cascade,
+  title varchar not null,
+  duration_seconds bigint not null,
+  started_at TIMESTAMPTZ not null,
+  ended_at TIMESTAMPTZ not null,
+  notes varchar
+);
+
+create index if not exists efforts_owner_started_at_idx on efforts (owner, started_at);
@@ -0,0 +1,12 @@
+create table if not exists sessions (
+  id bigserial primary key,
+  session_key varchar not null unique,
+  user_email varchar references users(email) on delete cascade,
+  state varchar not null,
+  created_at TIMESTAMPTZ not null,
+  updated_at TIMESTAMPTZ not null,
+  expires_at TIMESTAMPTZ not null
+);
+
+create index if not exists sessions_user_email_idx on sessions (user_email);
+create index if not exists sessions_expires_at_idx on sessions (expires_at);
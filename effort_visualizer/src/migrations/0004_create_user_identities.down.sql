@@ -0,0 +1,17 @@
+alter table users add column external_id varchar;
+
+update users
+set external_id = coalesce(
+  (
+    select i.external_id
+    from user_identities i
+    where i.user_email = users.email
+    order by i.provider = 'google' desc, i.linked_at
+    limit 1
+  ),
+  ''
+);
+
+alter table users alter column external_id set not null;
+
+drop table user_identities;
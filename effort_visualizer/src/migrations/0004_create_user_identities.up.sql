@@ -0,0 +1,14 @@
+create table user_identities (
+  provider varchar not null,
+  external_id varchar not null,
+  user_email varchar not null references users(email) on delete cascade,
+  linked_at TIMESTAMPTZ not null,
+  primary key (provider, external_id),
+  unique (user_email, provider)
+);
+
+insert into user_identities (provider, external_id, user_email, linked_at)
+select 'google', external_id, email, registered_date at time zone 'UTC'
+from users;
+
+alter table users drop column external_id;
@@ -0,0 +1,2 @@
+alter table users drop column timezone;
+alter table users drop column avatar_url;
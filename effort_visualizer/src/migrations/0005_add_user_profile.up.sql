@@ -0,0 +1,2 @@
+alter table users add column avatar_url varchar;
+alter table users add column timezone varchar not null default 'UTC';
@@ -0,0 +1,2 @@
+alter table efforts drop constraint efforts_owner_import_hash_key;
+alter table efforts drop column import_hash;
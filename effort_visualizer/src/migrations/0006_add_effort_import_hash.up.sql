@@ -0,0 +1,2 @@
+alter table efforts add column import_hash varchar;
+alter table efforts add constraint efforts_owner_import_hash_key unique (owner, import_hash);
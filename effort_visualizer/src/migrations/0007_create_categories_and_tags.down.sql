@@ -0,0 +1,4 @@
+drop table effort_tags;
+drop table tags;
+alter table efforts drop column category_id;
+drop table categories;
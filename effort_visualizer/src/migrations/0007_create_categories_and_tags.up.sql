@@ -0,0 +1,25 @@
+create table categories (
+  id bigserial primary key,
+  owner varchar not null references users(email) on delete cascade,
+  name varchar not null,
+  colour varchar not null,
+  icon varchar,
+  unique (owner, name)
+);
+
+alter table efforts add column category_id bigint references categories(id) on delete set null;
+
+create table tags (
+  id bigserial primary key,
+  owner varchar not null references users(email) on delete cascade,
+  name varchar not null,
+  unique (owner, name)
+);
+
+create table effort_tags (
+  effort_id bigint not null references efforts(id) on delete cascade,
+  tag_id bigint not null references tags(id) on delete cascade,
+  primary key (effort_id, tag_id)
+);
+
+create index effort_tags_tag_id_idx on effort_tags (tag_id);
@@ -0,0 +1,13 @@
+create table goals (
+  id bigserial primary key,
+  owner varchar not null references users(email) on delete cascade,
+  title varchar not null,
+  metric varchar not null check (metric in ('duration', 'count', 'distinct_days')),
+  target bigint not null check (target > 0),
+  period varchar not null check (period in ('daily', 'weekly', 'monthly', 'custom')),
+  period_days integer check ((period = 'custom') = (period_days is not null)),
+  starts_on date not null,
+  category_id bigint references categories(id) on delete cascade
+);
+
+create index goals_owner_idx on goals (owner);
@@ -0,0 +1,5 @@
+alter table users drop column locale;
+
+alter table users
+  alter column registered_date type TIMESTAMP using registered_date at time zone 'UTC',
+  alter column updated_date type TIMESTAMP using updated_date at time zone 'UTC';
@@ -0,0 +1,5 @@
+alter table users
+  alter column registered_date type TIMESTAMPTZ using registered_date at time zone 'UTC',
+  alter column updated_date type TIMESTAMPTZ using updated_date at time zone 'UTC';
+
+alter table users add column locale varchar not null default 'en_US';
@@ -0,0 +1,11 @@
+create table timers (
+  owner varchar primary key references users(email) on delete cascade,
+  title varchar not null,
+  notes varchar,
+  category_id bigint references categories(id) on delete set null,
+  tags varchar[] not null default '{}',
+  started_at TIMESTAMPTZ not null,
+  running_since TIMESTAMPTZ,
+  accumulated_seconds bigint not null default 0 check (accumulated_seconds >= 0),
+  revision bigint not null default 0
+);
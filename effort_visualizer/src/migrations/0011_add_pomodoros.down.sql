@@ -0,0 +1,12 @@
+drop table pomodoro_interruptions;
+
+alter table timers
+  drop constraint timers_pomodoro_check,
+  drop column pomodoro_work_seconds,
+  drop column pomodoro_short_break_seconds,
+  drop column pomodoro_long_break_seconds,
+  drop column pomodoro_cycles,
+  drop column pomodoro_settled_seconds,
+  drop column work_started_at;
+
+alter table efforts drop column pomodoro;
@@ -0,0 +1,25 @@
+alter table efforts add column pomodoro boolean not null default false;
+
+alter table timers
+  add column pomodoro_work_seconds integer,
+  add column pomodoro_short_break_seconds integer,
+  add column pomodoro_long_break_seconds integer,
+  add column pomodoro_cycles integer,
+  add column pomodoro_settled_seconds bigint not null default 0,
+  add column work_started_at TIMESTAMPTZ,
+  add constraint timers_pomodoro_check check (num_nulls(
+    pomodoro_work_seconds,
+    pomodoro_short_break_seconds,
+    pomodoro_long_break_seconds,
+    pomodoro_cycles) in (0, 4));
+
+create table pomodoro_interruptions (
+  id bigserial primary key,
+  owner varchar not null references users(email) on delete cascade,
+  title varchar not null,
+  started_at TIMESTAMPTZ not null,
+  interrupted_at TIMESTAMPTZ not null,
+  worked_seconds bigint not null
+);
+
+create index pomodoro_interruptions_owner_started_at_idx on pomodoro_interruptions (owner, started_at);
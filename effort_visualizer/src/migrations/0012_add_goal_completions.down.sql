@@ -0,0 +1 @@
+drop table goal_completions;
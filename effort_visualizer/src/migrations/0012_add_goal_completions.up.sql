@@ -0,0 +1,6 @@
+create table goal_completions (
+  goal_id bigint not null references goals(id) on delete cascade,
+  first_day date not null,
+  completed_at TIMESTAMPTZ not null default now(),
+  primary key (goal_id, first_day)
+);
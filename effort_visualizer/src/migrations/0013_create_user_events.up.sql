@@ -0,0 +1,9 @@
+create table user_events (
+  id bigserial primary key,
+  owner varchar not null references users(email) on delete cascade,
+  type varchar not null,
+  data jsonb not null,
+  created_at TIMESTAMPTZ not null default now()
+);
+
+create index user_events_owner_id_idx on user_events (owner, id);
@@ -0,0 +1 @@
+alter table users add column public_slug varchar unique;
@@ -0,0 +1,11 @@
+create table session_history (
+  id bigserial primary key,
+  session_id bigint not null,
+  user_email varchar not null references users(email) on delete cascade,
+  created_at TIMESTAMPTZ not null,
+  last_seen_at TIMESTAMPTZ not null,
+  ended_at TIMESTAMPTZ not null,
+  end_reason varchar not null
+);
+
+create index session_history_user_email_idx on session_history (user_email);
@@ -0,0 +1,266 @@
+use std::collections::HashMap;
+
+use anyhow::{bail, Result};
+use chrono::{DateTime, Utc};
+use deadpool_postgres::{Client, Pool};
+use tracing::info;
+
+use crate::repositories::database::get_client;
+
+/// Key of the advisory lock that keeps two instances from migrating at the same time.
+const MIGRATION_LOCK_ID: i64 = 0x6566_666f_7274;
+
+/// A schema change. Each one runs in its own transaction.
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    pub up: &'static str,
+    pub down: &'static str,
+}
+
+/// Every migration of the schema, in the order they are applied.
+pub static MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "create_users",
+        up: include_str!("0001_create_users.up.sql"),
+        down: include_str!("0001_create_users.down.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "create_efforts",
+        up: include_str!("0002_create_efforts.up.sql"),
+        down: include_str!("0002_create_efforts.down.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "create_sessions",
+        up: include_str!("0003_create_sessions.up.sql"),
+        down: include_str!("0003_create_sessions.down.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "create_user_identities",
+        up: include_str!("0004_create_user_identities.up.sql"),
+        down: include_str!("0004_create_user_identities.down.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "add_user_profile",
+        up: include_str!("0005_add_user_profile.up.sql"),
+        down: include_str!("0005_add_user_profile.down.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "add_effort_import_hash",
+        up: include_str!("0006_add_effort_import_hash.up.sql"),
+        down: include_str!("0006_add_effort_import_hash.down.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "create_categories_and_tags",
+        up: include_str!("0007_create_categories_and_tags.up.sql"),
+        down: include_str!("0007_create_categories_and_tags.down.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "create_goals",
+        up: include_str!("0008_create_goals.up.sql"),
+        down: include_str!("0008_create_goals.down.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "add_user_locale_and_timestamptz",
+        up: include_str!("0009_add_user_locale_and_timestamptz.up.sql"),
+        down: include_str!("0009_add_user_locale_and_timestamptz.down.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "create_timers",
+        up: include_str!("0010_create_timers.up.sql"),
+        down: include_str!("0010_create_timers.down.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "add_pomodoros",
+        up: include_str!("0011_add_pomodoros.up.sql"),
+        down: include_str!("0011_add_pomodoros.down.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "add_goal_completions",
+        up: include_str!("0012_add_goal_completions.up.sql"),
+        down: include_str!("0012_add_goal_completions.down.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "create_user_events",
+        up: include_str!("0013_create_user_events.up.sql"),
+        down: include_str!("0013_create_user_events.down.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "add_user_public_slug",
+        up: include_str!("0014_add_user_public_slug.up.sql"),
+        down: include_str!("0014_add_user_public_slug.down.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "create_session_history",
+        up: include_str!("0015_create_session_history.up.sql"),
+        down: include_str!("0015_create_session_history.down.sql"),
+    },
+];
+
+pub struct MigrationStatus {
+    pub version: i64,
+    pub name: &'static str,
+    /// `None` while the migration is pending.
+    pub applied_at: Option<DateTime<Utc>>,
+}
+
+/// Applies every pending migration and returns the ones that were applied.
+pub async fn migrate_up(pool: &Pool) -> Result<Vec<&'static Migration>> {
+    let mut client = lock(pool).await?;
+    let result = apply_pending(&mut client).await;
+    unlock(&client).await?;
+    result
+}
+
+/// Reverts the latest applied migration, if any.
+pub async fn migrate_down(pool: &Pool) -> Result<Option<&'static Migration>> {
+    let mut client = lock(pool).await?;
+    let result = revert_latest(&mut client).await;
+    unlock(&client).await?;
+    result
+}
+
+pub async fn migration_status(pool: &Pool) -> Result<Vec<MigrationStatus>> {
+    let client = get_client(pool).await?;
+    create_migrations_table(&client).await?;
+    let applied = applied_migrations(&client).await?;
+    Ok(MIGRATIONS
+        .iter()
+        .map(|migration| MigrationStatus {
+            version: migration.version,
+            name: migration.name,
+            applied_at: applied.get(&migration.version).copied(),
+        })
+        .collect())
+}
+
+async fn lock(pool: &Pool) -> Result<Client> {
+    let client = get_client(pool).await?;
+    client
+        .execute("SELECT pg_advisory_lock($1)", &[&MIGRATION_LOCK_ID])
+        .await?;
+    create_migrations_table(&client).await?;
+    Ok(client)
+}
+
+async fn unlock(client: &Client) -> Result<()> {
+    client
+        .execute("SELECT pg_advisory_unlock($1)", &[&MIGRATION_LOCK_ID])
+        .await?;
+    Ok(())
+}
+
+async fn create_migrations_table(client: &Client) -> Result<()> {
+    client
+        .batch_execute(
+            "
+            CREATE TABLE IF NOT EXISTS schema_migrations (
+                version bigint PRIMARY KEY,
+                name varchar NOT NULL,
+                applied_at TIMESTAMPTZ NOT NULL
+            )",
+        )
+        .await?;
+    Ok(())
+}
+
+async fn applied_migrations(client: &Client) -> Result<HashMap<i64, DateTime<Utc>>> {
+    let query_result = client
+        .query("SELECT version, applied_at FROM schema_migrations", &[])
+        .await?;
+    Ok(query_result
+        .iter()
+        .map(|r| (r.get("version"), r.get("applied_at")))
+        .collect())
+}
+
+async fn apply_pending(client: &mut Client) -> Result<Vec<&'static Migration>> {
+    let applied = applied_migrations(client).await?;
+    if let Some(unknown) = applied
+        .keys()
+        .filter(|version| !MIGRATIONS.iter().any(|m| m.version == **version))
+        .max()
+    {
+        bail!("The applied migration {unknown} is unknown to this binary.");
+    }
+
+    let mut newly_applied = vec![];
+    for migration in MIGRATIONS
+        .iter()
+        .filter(|m| !applied.contains_key(&m.version))
+    {
+        info!(
+            "Applying migration {} {}.",
+            migration.version, migration.name
+        );
+        let transaction = client.transaction().await?;
+        transaction.batch_execute(migration.up).await?;
+        transaction
+            .execute(
+                "
+                INSERT INTO schema_migrations (
+                    version,
+                    name,
+                    applied_at)
+                VALUES ($1, $2, now())",
+                &[&migration.version, &migration.name],
+            )
+            .await?;
+        transaction.commit().await?;
+        newly_applied.push(migration);
+    }
+    Ok(newly_applied)
+}
+
+async fn revert_latest(client: &mut Client) -> Result<Option<&'static Migration>> {
+    let latest = match applied_migrations(client).await?.keys().max() {
+        Some(latest) => *latest,
+        None => return Ok(None),
+    };
+    let migration = match MIGRATIONS.iter().find(|m| m.version == latest) {
+        Some(migration) => migration,
+        None => bail!("The latest applied migration {latest} is unknown to this binary."),
+    };
+
+    info!(
+        "Reverting migration {} {}.",
+        migration.version, migration.name
+    );
+    let transaction = client.transaction().await?;
+    transaction.batch_execute(migration.down).await?;
+    transaction
+        .execute(
+            "DELETE FROM schema_migrations WHERE version = $1",
+            &[&migration.version],
+        )
+        .await?;
+    transaction.commit().await?;
+    Ok(Some(migration))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::MIGRATIONS;
+
+    #[test]
+    fn マイグレーションのバージョンは昇順に並んでいる() {
+        assert!(MIGRATIONS
+            .windows(2)
+            .all(|pair| pair[0].version < pair[1].version));
+    }
+}
@@ -0,0 +1,189 @@
+use super::database::get_client;
+use crate::domain::categories::Category;
+use anyhow::Result;
+use async_trait::async_trait;
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::{types::ToSql, Row};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum CategoryWrite {
+    Saved(Category),
+    NotFound,
+    /// The owner has another category with the name.
+    NameTaken,
+}
+
+#[automock]
+#[async_trait]
+pub trait CategoryRepository: Send {
+    /// Stores `data` and returns it with the id assigned by the database.
+    async fn add(&self, data: &Category) -> Result<CategoryWrite>;
+    async fn find(&self, owner: &str, id: i64) -> Result<Option<Category>>;
+    async fn find_all(&self, owner: &str) -> Result<Vec<Category>>;
+    /// Overwrites the category identified by `data.id` and `data.owner`.
+    async fn update(&self, data: &Category) -> Result<CategoryWrite>;
+    /// Deletes the category along with its goals. Its efforts are left without a category.
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool>;
+}
+
+pub struct CategoryRepositoryImpl {
+    pool: Pool,
+}
+
+impl CategoryRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_row(&self, row: &Row) -> Category {
+        Category {
+            id: row.get("id"),
+            owner: row.get("owner"),
+            name: row.get("name"),
+            colour: row.get("colour"),
+            icon: row.get("icon"),
+        }
+    }
+}
+
+#[async_trait]
+impl CategoryRepository for CategoryRepositoryImpl {
+    async fn add(&self, data: &Category) -> Result<CategoryWrite> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> =
+            vec![&data.owner, &data.name, &data.colour, &data.icon];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                INSERT INTO categories (
+                    owner,
+                    name,
+                    colour,
+                    icon)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT (owner, name) DO NOTHING
+                RETURNING
+                    id,
+                    owner,
+                    name,
+                    colour,
+                    icon",
+                &row,
+            )
+            .await?;
+        Ok(match query_result {
+            Some(r) => CategoryWrite::Saved(self.parse_row(&r)),
+            None => CategoryWrite::NameTaken,
+        })
+    }
+
+    async fn find(&self, owner: &str, id: i64) -> Result<Option<Category>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                SELECT
+                    id,
+                    owner,
+                    name,
+                    colour,
+                    icon
+                FROM categories
+                WHERE
+                    owner = $1
+                    AND id = $2",
+                &row,
+            )
+            .await?;
+        Ok(query_result.map(|r| self.parse_row(&r)))
+    }
+
+    async fn find_all(&self, owner: &str) -> Result<Vec<Category>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    id,
+                    owner,
+                    name,
+                    colour,
+                    icon
+                FROM categories
+                WHERE
+                    owner = $1
+                ORDER BY name",
+                &row,
+            )
+            .await?;
+        Ok(query_result.iter().map(|r| self.parse_row(r)).collect())
+    }
+
+    async fn update(&self, data: &Category) -> Result<CategoryWrite> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&data.owner, &data.id, &data.name];
+        let taken = transaction
+            .query_opt(
+                "
+                SELECT 1
+                FROM categories
+                WHERE
+                    owner = $1
+                    AND id <> $2
+                    AND name = $3",
+                &row,
+            )
+            .await?
+            .is_some();
+        if taken {
+            return Ok(CategoryWrite::NameTaken);
+        }
+        let row: Vec<&'_ (dyn ToSql + Sync)> =
+            vec![&data.owner, &data.id, &data.name, &data.colour, &data.icon];
+        let query_result = transaction
+            .query_opt(
+                "
+                UPDATE categories
+                SET
+                    name = $3,
+                    colour = $4,
+                    icon = $5
+                WHERE
+                    owner = $1
+                    AND id = $2
+                RETURNING
+                    id,
+                    owner,
+                    name,
+                    colour,
+                    icon",
+                &row,
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(match query_result {
+            Some(r) => CategoryWrite::Saved(self.parse_row(&r)),
+            None => CategoryWrite::NotFound,
+        })
+    }
+
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id];
+        let deleted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                DELETE FROM categories
+                WHERE
+                    owner = $1
+                    AND id = $2",
+                &row,
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
+}
@@ -0,0 +1,74 @@
+use std::time::Duration;
+
+use anyhow::{Context, Result};
+use deadpool_postgres::{
+    Client, Manager, ManagerConfig, Pool, PoolError, RecyclingMethod, Runtime, TimeoutType,
+};
+use derive_more::Display;
+use tokio_postgres::NoTls;
+use tracing::debug;
+
+use crate::helpers::environments::EnvVariables;
+
+/// No connection was returned to the pool within the check-out timeout.
+#[derive(Debug, Display)]
+#[display(fmt = "The database connection pool is exhausted.")]
+pub struct PoolExhausted;
+
+impl std::error::Error for PoolExhausted {}
+
+/// Settings for connecting to the database of `env`.
+pub fn connection_config(env: &EnvVariables) -> Result<tokio_postgres::Config> {
+    let mut config = tokio_postgres::Config::new();
+    config
+        .host(&env.db_server)
+        .port(
+            env.db_port
+                .parse()
+                .context("DB_PORT must be a port number.")?,
+        )
+        .dbname(&env.db_name)
+        .user(&env.db_user_id)
+        .password(&env.db_password);
+    Ok(config)
+}
+
+pub fn create_pool(env: &EnvVariables) -> Result<Pool> {
+    let manager = Manager::from_config(
+        connection_config(env)?,
+        NoTls,
+        ManagerConfig {
+            recycling_method: RecyclingMethod::Fast,
+        },
+    );
+    let checkout_timeout = Duration::from_secs(env.db_pool_checkout_timeout_seconds);
+    Pool::builder(manager)
+        .max_size(env.db_pool_max_size)
+        .runtime(Runtime::Tokio1)
+        .wait_timeout(Some(checkout_timeout))
+        .create_timeout(Some(checkout_timeout))
+        .build()
+        .context("Failed to create the database connection pool.")
+}
+
+/// Checks out a connection, reporting a full pool as `PoolExhausted`.
+pub async fn get_client(pool: &Pool) -> Result<Client> {
+    pool.get().await.map_err(|e| match e {
+        PoolError::Timeout(TimeoutType::Wait) => anyhow::Error::new(PoolExhausted),
+        e => anyhow::Error::new(e).context("Failed to connect to the database."),
+    })
+}
+
+/// Periodically closes connections that have not been used for `idle_timeout`.
+pub fn spawn_idle_connection_reaper(pool: Pool, idle_timeout: Duration) {
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(idle_timeout / 2);
+        loop {
+            interval.tick().await;
+            let result = pool.retain(|_, metrics| metrics.last_used() < idle_timeout);
+            if !result.removed.is_empty() {
+                debug!("Closed {} idle database connections.", result.removed.len());
+            }
+        }
+    });
+}
@@ -0,0 +1,652 @@
+use super::database::get_client;
+use crate::domain::efforts::{Effort, EffortFilter, FilterMatch, ImportedEffort};
+use crate::domain::goals::PeriodTotals;
+use crate::domain::heatmap::{EffortAggregate, HeatmapBucket};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, NaiveDate, Utc};
+use deadpool_postgres::{GenericClient, Pool, Transaction};
+use futures::{channel::mpsc, stream::BoxStream, SinkExt, StreamExt};
+use mockall::automock;
+use tokio_postgres::{types::ToSql, Row};
+
+#[automock]
+#[async_trait]
+pub trait EffortRepository: Send {
+    /// Stores `data` and returns it with the id assigned by the database.
+    /// Tags the owner doesn't have yet are created.
+    async fn add(&self, data: &Effort) -> Result<Effort>;
+    async fn find(&self, owner: &str, id: i64) -> Result<Option<Effort>>;
+    async fn find_all(&self, owner: &str, filter: &EffortFilter) -> Result<Vec<Effort>>;
+    /// Streams the efforts started in `[from, to)` in the order they started. Rows are read
+    /// through a cursor a batch at a time, as fast as the stream is consumed.
+    async fn stream_all(
+        &self,
+        owner: &str,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<BoxStream<'static, Result<Effort>>>;
+    /// Overwrites the effort identified by `data.id` and `data.owner`, tags included.
+    /// Returns `None` when no such effort exists.
+    async fn update(&self, data: &Effort) -> Result<Option<Effort>>;
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool>;
+    /// Returns the days of `timezone` the owner's efforts started on, in order and without
+    /// duplicates.
+    async fn find_effort_days(&self, owner: &str, timezone: &str) -> Result<Vec<NaiveDate>>;
+    /// Returns those of `import_hashes` the owner has imported before.
+    async fn find_imported(&self, owner: &str, import_hashes: &[String]) -> Result<Vec<String>>;
+    /// Stores the efforts with their tags in one transaction, skipping the ones whose hash
+    /// their owner has imported before. Returns the stored efforts.
+    async fn add_imported(&self, efforts: &[ImportedEffort]) -> Result<Vec<Effort>>;
+    /// Sums the efforts `filter` keeps per bucket between `from` and `to` (both inclusive).
+    /// Buckets are made of the days of `timezone`, and the ones without any effort are
+    /// returned with zero totals.
+    async fn aggregate(
+        &self,
+        owner: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        bucket: HeatmapBucket,
+        timezone: &str,
+        filter: &EffortFilter,
+    ) -> Result<Vec<EffortAggregate>>;
+    /// Returns the 25th, 50th and 75th percentiles of the non-empty bucket
+    /// totals over the whole history, or an empty vector when there is none. Buckets are made
+    /// of the days of `timezone`.
+    async fn quartiles(
+        &self,
+        owner: &str,
+        bucket: HeatmapBucket,
+        timezone: &str,
+        filter: &EffortFilter,
+    ) -> Result<Vec<f64>>;
+    /// Sums the efforts `filter` keeps that started within each `[start, end)` period, in
+    /// the order of `periods`. Days are counted in `timezone`.
+    async fn totals(
+        &self,
+        owner: &str,
+        periods: &[(DateTime<Utc>, DateTime<Utc>)],
+        timezone: &str,
+        filter: &EffortFilter,
+    ) -> Result<Vec<PeriodTotals>>;
+}
+
+/// Rows fetched from the cursor of `stream_all` at a time.
+const STREAM_BATCH_SIZE: i32 = 500;
+
+pub struct EffortRepositoryImpl {
+    pool: Pool,
+}
+
+impl EffortRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+fn parse_effort(row: &Row) -> Effort {
+    Effort {
+        id: row.get("id"),
+        owner: row.get("owner"),
+        title: row.get("title"),
+        duration_seconds: row.get("duration_seconds"),
+        started_at: row.get("started_at"),
+        ended_at: row.get("ended_at"),
+        notes: row.get("notes"),
+        category_id: row.get("category_id"),
+        tags: row.get("tags"),
+        pomodoro: row.get("pomodoro"),
+    }
+}
+
+/// SQL condition keeping the efforts `filter` matches. The category ids, the tag names and
+/// whether every condition must hold are read from the three parameters from `$first`.
+fn filter_condition(first: usize) -> String {
+    let (categories, tags, match_all) = (first, first + 1, first + 2);
+    format!(
+        "
+        ((cardinality(${categories}::bigint[]) = 0 AND cardinality(${tags}::varchar[]) = 0)
+        OR CASE WHEN ${match_all}::boolean
+            THEN efforts.category_id = ALL(${categories})
+                AND ${tags} <@ ARRAY(
+                    SELECT tags.name
+                    FROM effort_tags
+                    JOIN tags ON tags.id = effort_tags.tag_id
+                    WHERE effort_tags.effort_id = efforts.id)
+            ELSE efforts.category_id = ANY(${categories})
+                OR ${tags} && ARRAY(
+                    SELECT tags.name
+                    FROM effort_tags
+                    JOIN tags ON tags.id = effort_tags.tag_id
+                    WHERE effort_tags.effort_id = efforts.id)
+        END)"
+    )
+}
+
+async fn select_effort(
+    client: &impl GenericClient,
+    owner: &str,
+    id: i64,
+) -> Result<Option<Effort>> {
+    let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id];
+    let query_result = client
+        .query_opt(
+            "
+            SELECT
+                id,
+                owner,
+                title,
+                duration_seconds,
+                started_at,
+                ended_at,
+                notes,
+                category_id,
+                pomodoro,
+                ARRAY(
+                    SELECT tags.name
+                    FROM effort_tags
+                    JOIN tags ON tags.id = effort_tags.tag_id
+                    WHERE effort_tags.effort_id = efforts.id
+                    ORDER BY tags.name) AS tags
+            FROM efforts
+            WHERE
+                owner = $1
+                AND id = $2",
+            &row,
+        )
+        .await?;
+    Ok(query_result.map(|r| parse_effort(&r)))
+}
+
+/// Stores `data` with its tags within `transaction` and returns it with the id assigned by
+/// the database.
+pub(super) async fn insert_effort(transaction: &Transaction<'_>, data: &Effort) -> Result<Effort> {
+    let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+        &data.owner,
+        &data.title,
+        &data.duration_seconds,
+        &data.started_at,
+        &data.ended_at,
+        &data.notes,
+        &data.category_id,
+        &data.pomodoro,
+    ];
+    let id: i64 = transaction
+        .query_one(
+            "
+            INSERT INTO efforts (
+                owner,
+                title,
+                duration_seconds,
+                started_at,
+                ended_at,
+                notes,
+                category_id,
+                pomodoro)
+            VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+            RETURNING id",
+            &row,
+        )
+        .await?
+        .get("id");
+    write_tags(transaction, &data.owner, id, &data.tags).await?;
+    select_effort(transaction, &data.owner, id)
+        .await?
+        .ok_or_else(|| anyhow!("The added effort {id} is missing."))
+}
+
+/// Replaces the tags of the effort, creating the ones the owner doesn't have yet.
+async fn write_tags(
+    transaction: &Transaction<'_>,
+    owner: &str,
+    effort_id: i64,
+    tags: &[String],
+) -> Result<()> {
+    let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&effort_id];
+    transaction
+        .execute("DELETE FROM effort_tags WHERE effort_id = $1", &row)
+        .await?;
+    if tags.is_empty() {
+        return Ok(());
+    }
+    let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &tags];
+    transaction
+        .execute(
+            "
+            INSERT INTO tags (
+                owner,
+                name)
+            SELECT $1, unnest($2::varchar[])
+            ON CONFLICT (owner, name) DO NOTHING",
+            &row,
+        )
+        .await?;
+    let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&effort_id, &owner, &tags];
+    transaction
+        .execute(
+            "
+            INSERT INTO effort_tags (
+                effort_id,
+                tag_id)
+            SELECT $1, id
+            FROM tags
+            WHERE
+                owner = $2
+                AND name = ANY($3)",
+            &row,
+        )
+        .await?;
+    Ok(())
+}
+
+#[async_trait]
+impl EffortRepository for EffortRepositoryImpl {
+    async fn add(&self, data: &Effort) -> Result<Effort> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        let effort = insert_effort(&transaction, data).await?;
+        transaction.commit().await?;
+        Ok(effort)
+    }
+
+    async fn find(&self, owner: &str, id: i64) -> Result<Option<Effort>> {
+        let client = get_client(&self.pool).await?;
+        select_effort(&client, owner, id).await
+    }
+
+    async fn find_all(&self, owner: &str, filter: &EffortFilter) -> Result<Vec<Effort>> {
+        let match_all = filter.match_mode == FilterMatch::All;
+        let row: Vec<&'_ (dyn ToSql + Sync)> =
+            vec![&owner, &filter.category_ids, &filter.tags, &match_all];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                &format!(
+                    "
+                    SELECT
+                        id,
+                        owner,
+                        title,
+                        duration_seconds,
+                        started_at,
+                        ended_at,
+                        notes,
+                        category_id,
+                        pomodoro,
+                        ARRAY(
+                            SELECT tags.name
+                            FROM effort_tags
+                            JOIN tags ON tags.id = effort_tags.tag_id
+                            WHERE effort_tags.effort_id = efforts.id
+                            ORDER BY tags.name) AS tags
+                    FROM efforts
+                    WHERE
+                        owner = $1
+                        AND {}
+                    ORDER BY started_at DESC",
+                    filter_condition(2)
+                ),
+                &row,
+            )
+            .await?;
+        Ok(query_result.iter().map(parse_effort).collect())
+    }
+
+    async fn stream_all(
+        &self,
+        owner: &str,
+        from: Option<DateTime<Utc>>,
+        to: Option<DateTime<Utc>>,
+    ) -> Result<BoxStream<'static, Result<Effort>>> {
+        let mut client = get_client(&self.pool).await?;
+        let owner = owner.to_owned();
+        // The cursor lives in a transaction that borrows the client, so a task owns both and
+        // hands the rows over. The channel holds a single effort, so the cursor is read no
+        // faster than the stream is consumed.
+        let (mut sender, receiver) = mpsc::channel(1);
+        tokio::spawn(async move {
+            let result: Result<()> = async {
+                let transaction = client.build_transaction().read_only(true).start().await?;
+                let statement = transaction
+                    .prepare(
+                        "
+                        SELECT
+                            id,
+                            owner,
+                            title,
+                            duration_seconds,
+                            started_at,
+                            ended_at,
+                            notes,
+                            category_id,
+                            pomodoro,
+                            ARRAY(
+                                SELECT tags.name
+                                FROM effort_tags
+                                JOIN tags ON tags.id = effort_tags.tag_id
+                                WHERE effort_tags.effort_id = efforts.id
+                                ORDER BY tags.name) AS tags
+                        FROM efforts
+                        WHERE
+                            owner = $1
+                            AND ($2::timestamptz IS NULL OR started_at >= $2)
+                            AND ($3::timestamptz IS NULL OR started_at < $3)
+                        ORDER BY started_at, id",
+                    )
+                    .await?;
+                let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &from, &to];
+                let cursor = transaction.bind(&statement, &row).await?;
+                loop {
+                    let rows = transaction.query_portal(&cursor, STREAM_BATCH_SIZE).await?;
+                    if rows.is_empty() {
+                        break;
+                    }
+                    for row in rows {
+                        if sender.send(Ok(parse_effort(&row))).await.is_err() {
+                            // Nobody reads the stream anymore.
+                            return Ok(());
+                        }
+                    }
+                }
+                transaction.commit().await?;
+                Ok(())
+            }
+            .await;
+            if let Err(e) = result {
+                let _ = sender.send(Err(e)).await;
+            }
+        });
+        Ok(receiver.boxed())
+    }
+
+    async fn update(&self, data: &Effort) -> Result<Option<Effort>> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &data.owner,
+            &data.id,
+            &data.title,
+            &data.duration_seconds,
+            &data.started_at,
+            &data.ended_at,
+            &data.notes,
+            &data.category_id,
+        ];
+        let updated = transaction
+            .execute(
+                "
+                UPDATE efforts
+                SET
+                    title = $3,
+                    duration_seconds = $4,
+                    started_at = $5,
+                    ended_at = $6,
+                    notes = $7,
+                    category_id = $8
+                WHERE
+                    owner = $1
+                    AND id = $2",
+                &row,
+            )
+            .await?;
+        if updated == 0 {
+            return Ok(None);
+        }
+        write_tags(&transaction, &data.owner, data.id, &data.tags).await?;
+        let effort = select_effort(&transaction, &data.owner, data.id).await?;
+        transaction.commit().await?;
+        Ok(effort)
+    }
+
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id];
+        let deleted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                DELETE FROM efforts
+                WHERE
+                    owner = $1
+                    AND id = $2",
+                &row,
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    async fn find_effort_days(&self, owner: &str, timezone: &str) -> Result<Vec<NaiveDate>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &timezone];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT DISTINCT
+                    (started_at AT TIME ZONE $2::text)::date AS day
+                FROM efforts
+                WHERE
+                    owner = $1
+                ORDER BY 1",
+                &row,
+            )
+            .await?;
+        Ok(query_result.iter().map(|r| r.get("day")).collect())
+    }
+
+    async fn find_imported(&self, owner: &str, import_hashes: &[String]) -> Result<Vec<String>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &import_hashes];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    import_hash
+                FROM efforts
+                WHERE
+                    owner = $1
+                    AND import_hash = ANY($2)",
+                &row,
+            )
+            .await?;
+        Ok(query_result.iter().map(|r| r.get("import_hash")).collect())
+    }
+
+    async fn add_imported(&self, efforts: &[ImportedEffort]) -> Result<Vec<Effort>> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        let statement = transaction
+            .prepare(
+                "
+                INSERT INTO efforts (
+                    owner,
+                    title,
+                    duration_seconds,
+                    started_at,
+                    ended_at,
+                    notes,
+                    category_id,
+                    import_hash)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                ON CONFLICT (owner, import_hash) DO NOTHING
+                RETURNING id",
+            )
+            .await?;
+        let mut added = Vec::new();
+        for imported in efforts {
+            let data = &imported.effort;
+            let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+                &data.owner,
+                &data.title,
+                &data.duration_seconds,
+                &data.started_at,
+                &data.ended_at,
+                &data.notes,
+                &data.category_id,
+                &imported.import_hash,
+            ];
+            if let Some(inserted) = transaction.query_opt(&statement, &row).await? {
+                let id: i64 = inserted.get("id");
+                write_tags(&transaction, &data.owner, id, &data.tags).await?;
+                added.push(
+                    select_effort(&transaction, &data.owner, id)
+                        .await?
+                        .ok_or_else(|| anyhow!("The imported effort {id} is missing."))?,
+                );
+            }
+        }
+        transaction.commit().await?;
+        Ok(added)
+    }
+
+    async fn aggregate(
+        &self,
+        owner: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        bucket: HeatmapBucket,
+        timezone: &str,
+        filter: &EffortFilter,
+    ) -> Result<Vec<EffortAggregate>> {
+        let bucket = bucket.as_str();
+        let match_all = filter.match_mode == FilterMatch::All;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &owner,
+            &bucket,
+            &from,
+            &to,
+            &timezone,
+            &filter.category_ids,
+            &filter.tags,
+            &match_all,
+        ];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                &format!(
+                    "
+                    SELECT
+                        buckets.bucket_start::date AS bucket_start,
+                        COALESCE(SUM(efforts.duration_seconds), 0)::bigint AS total_seconds,
+                        COUNT(efforts.id) AS effort_count
+                    FROM generate_series(
+                        date_trunc($2::text, $3::date::timestamp),
+                        $4::date::timestamp,
+                        ('1 ' || $2::text)::interval) AS buckets(bucket_start)
+                    LEFT JOIN efforts
+                        ON efforts.owner = $1
+                        AND efforts.started_at >= $3::date::timestamp AT TIME ZONE $5::text
+                        AND efforts.started_at < ($4::date + 1)::timestamp AT TIME ZONE $5::text
+                        AND date_trunc($2::text, efforts.started_at AT TIME ZONE $5::text)
+                            = buckets.bucket_start
+                        AND {}
+                    GROUP BY buckets.bucket_start
+                    ORDER BY buckets.bucket_start",
+                    filter_condition(6)
+                ),
+                &row,
+            )
+            .await?;
+        Ok(query_result
+            .iter()
+            .map(|r| EffortAggregate {
+                bucket_start: r.get("bucket_start"),
+                total_seconds: r.get("total_seconds"),
+                effort_count: r.get("effort_count"),
+            })
+            .collect())
+    }
+
+    async fn quartiles(
+        &self,
+        owner: &str,
+        bucket: HeatmapBucket,
+        timezone: &str,
+        filter: &EffortFilter,
+    ) -> Result<Vec<f64>> {
+        let bucket = bucket.as_str();
+        let match_all = filter.match_mode == FilterMatch::All;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &owner,
+            &bucket,
+            &timezone,
+            &filter.category_ids,
+            &filter.tags,
+            &match_all,
+        ];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_one(
+                &format!(
+                    "
+                    SELECT
+                        percentile_cont(ARRAY[0.25, 0.5, 0.75])
+                            WITHIN GROUP (ORDER BY totals.total_seconds) AS quartiles
+                    FROM (
+                        SELECT SUM(duration_seconds) AS total_seconds
+                        FROM efforts
+                        WHERE
+                            owner = $1
+                            AND {}
+                        GROUP BY date_trunc($2::text, started_at AT TIME ZONE $3::text)
+                        HAVING SUM(duration_seconds) > 0) AS totals",
+                    filter_condition(4)
+                ),
+                &row,
+            )
+            .await?;
+        let quartiles: Option<Vec<f64>> = query_result.get("quartiles");
+        Ok(quartiles.unwrap_or_default())
+    }
+
+    async fn totals(
+        &self,
+        owner: &str,
+        periods: &[(DateTime<Utc>, DateTime<Utc>)],
+        timezone: &str,
+        filter: &EffortFilter,
+    ) -> Result<Vec<PeriodTotals>> {
+        let (starts, ends): (Vec<DateTime<Utc>>, Vec<DateTime<Utc>>) =
+            periods.iter().copied().unzip();
+        let match_all = filter.match_mode == FilterMatch::All;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &owner,
+            &starts,
+            &ends,
+            &timezone,
+            &filter.category_ids,
+            &filter.tags,
+            &match_all,
+        ];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                &format!(
+                    "
+                    SELECT
+                        COALESCE(SUM(efforts.duration_seconds), 0)::bigint AS total_seconds,
+                        COUNT(efforts.id) AS effort_count,
+                        COUNT(DISTINCT (efforts.started_at AT TIME ZONE $4::text)::date)
+                            AS distinct_days
+                    FROM unnest($2::timestamptz[], $3::timestamptz[])
+                        WITH ORDINALITY AS periods(period_start, period_end, position)
+                    LEFT JOIN efforts
+                        ON efforts.owner = $1
+                        AND efforts.started_at >= periods.period_start
+                        AND efforts.started_at < periods.period_end
+                        AND {}
+                    GROUP BY periods.position
+                    ORDER BY periods.position",
+                    filter_condition(5)
+                ),
+                &row,
+            )
+            .await?;
+        Ok(query_result
+            .iter()
+            .map(|r| PeriodTotals {
+                total_seconds: r.get("total_seconds"),
+                effort_count: r.get("effort_count"),
+                distinct_days: r.get("distinct_days"),
+            })
+            .collect())
+    }
+}
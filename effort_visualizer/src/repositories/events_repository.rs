@@ -0,0 +1,202 @@
+use std::collections::HashSet;
+
+use super::database::get_client;
+use crate::domain::events::{EventKind, UserEvent};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::{types::ToSql, Row};
+
+/// The channel every server instance listens on for the events to push.
+pub const EVENT_CHANNEL: &str = "user_events";
+/// Events kept per user for clients to resume from. Older ones are dropped.
+pub const MAX_LOGGED_EVENTS: i64 = 500;
+/// Postgres refuses notifications with a payload of 8000 bytes or more.
+const MAX_PAYLOAD_BYTES: usize = 7999;
+
+#[automock]
+#[async_trait]
+pub trait EventRepository: Send {
+    /// Appends `events` to the event logs of their owners and notifies every server instance
+    /// of them, so that each pushes them to the clients connected to it. Events too large for
+    /// a notification are sent without their data.
+    async fn publish(&self, events: &[UserEvent]) -> Result<()>;
+    /// Notifies every server instance of `events` without logging them, for events that only
+    /// matter to the clients connected right now.
+    async fn notify(&self, events: &[UserEvent]) -> Result<()>;
+    /// Logged events of the owner after the one with the id `after`, the oldest first.
+    async fn find_after(&self, owner: &str, after: i64) -> Result<Vec<UserEvent>>;
+    /// The id of the oldest event still logged for the owner.
+    async fn find_oldest_id(&self, owner: &str) -> Result<Option<i64>>;
+}
+
+pub struct EventRepositoryImpl {
+    pool: Pool,
+}
+
+impl EventRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_row(&self, row: &Row) -> Result<UserEvent> {
+        let kind: &str = row.get("type");
+        Ok(UserEvent {
+            id: Some(row.get("id")),
+            owner: row.get("owner"),
+            kind: EventKind::parse(kind)
+                .ok_or_else(|| anyhow!("The event type `{kind}` is unknown."))?,
+            data: row.get("data"),
+        })
+    }
+}
+
+fn payload(event: &UserEvent) -> Result<String> {
+    let payload = serde_json::to_string(event)?;
+    if payload.len() <= MAX_PAYLOAD_BYTES {
+        return Ok(payload);
+    }
+    Ok(serde_json::to_string(&UserEvent {
+        data: serde_json::Value::Null,
+        ..event.clone()
+    })?)
+}
+
+#[async_trait]
+impl EventRepository for EventRepositoryImpl {
+    async fn publish(&self, events: &[UserEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let mut owners: Vec<&str> = events
+            .iter()
+            .map(|event| event.owner.as_str())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        // Ids are assigned on insert but become visible on commit, so concurrent publishes for
+        // the same owner are serialized to notify and log their events in the order of their
+        // ids. Locking in a fixed order keeps publishes for several owners from deadlocking.
+        owners.sort_unstable();
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        for owner in &owners {
+            let row: Vec<&'_ (dyn ToSql + Sync)> = vec![owner];
+            transaction
+                .execute("SELECT pg_advisory_xact_lock(hashtext($1))", &row)
+                .await?;
+        }
+        let mut payloads = Vec::with_capacity(events.len());
+        for event in events {
+            let kind = event.kind.as_str();
+            let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&event.owner, &kind, &event.data];
+            let inserted = transaction
+                .query_one(
+                    "
+                    INSERT INTO user_events (
+                        owner,
+                        type,
+                        data)
+                    VALUES ($1, $2, $3)
+                    RETURNING id",
+                    &row,
+                )
+                .await?;
+            payloads.push(payload(&UserEvent {
+                id: Some(inserted.get("id")),
+                ..event.clone()
+            })?);
+        }
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owners, &MAX_LOGGED_EVENTS];
+        transaction
+            .execute(
+                "
+                DELETE FROM user_events
+                WHERE id IN (
+                    SELECT id
+                    FROM (
+                        SELECT
+                            id,
+                            row_number() OVER (PARTITION BY owner ORDER BY id DESC) AS position
+                        FROM user_events
+                        WHERE
+                            owner = ANY($1)) AS logged
+                    WHERE
+                        position > $2)",
+                &row,
+            )
+            .await?;
+        // Notifications are delivered on commit, after the events can be read.
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&EVENT_CHANNEL, &payloads];
+        transaction
+            .execute(
+                "
+                SELECT pg_notify($1, payloads.payload)
+                FROM unnest($2::text[]) WITH ORDINALITY AS payloads(payload, position)
+                ORDER BY payloads.position",
+                &row,
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(())
+    }
+
+    async fn notify(&self, events: &[UserEvent]) -> Result<()> {
+        if events.is_empty() {
+            return Ok(());
+        }
+        let payloads = events.iter().map(payload).collect::<Result<Vec<_>>>()?;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&EVENT_CHANNEL, &payloads];
+        get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                SELECT pg_notify($1, payloads.payload)
+                FROM unnest($2::text[]) WITH ORDINALITY AS payloads(payload, position)
+                ORDER BY payloads.position",
+                &row,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn find_after(&self, owner: &str, after: i64) -> Result<Vec<UserEvent>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &after];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    id,
+                    owner,
+                    type,
+                    data
+                FROM user_events
+                WHERE
+                    owner = $1
+                    AND id > $2
+                ORDER BY id",
+                &row,
+            )
+            .await?;
+        query_result.iter().map(|r| self.parse_row(r)).collect()
+    }
+
+    async fn find_oldest_id(&self, owner: &str) -> Result<Option<i64>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner];
+        let result = get_client(&self.pool)
+            .await?
+            .query_one(
+                "
+                SELECT
+                    min(id) AS id
+                FROM user_events
+                WHERE
+                    owner = $1",
+                &row,
+            )
+            .await?;
+        Ok(result.get("id"))
+    }
+}
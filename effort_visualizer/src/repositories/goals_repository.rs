@@ -0,0 +1,259 @@
+use super::database::get_client;
+use crate::domain::goals::{Goal, GoalCompletion, GoalMetric, GoalPeriod};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::{types::ToSql, Row};
+
+#[automock]
+#[async_trait]
+pub trait GoalRepository: Send {
+    /// Stores `data` and returns it with the id assigned by the database.
+    async fn add(&self, data: &Goal) -> Result<Goal>;
+    async fn find(&self, owner: &str, id: i64) -> Result<Option<Goal>>;
+    async fn find_all(&self, owner: &str) -> Result<Vec<Goal>>;
+    /// Overwrites the goal identified by `data.id` and `data.owner`.
+    /// Returns `None` when no such goal exists.
+    async fn update(&self, data: &Goal) -> Result<Option<Goal>>;
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool>;
+    /// Records that the goal was completed in the period starting on `first_day`. Returns
+    /// `false` when that was recorded before.
+    async fn mark_completed(&self, id: i64, first_day: NaiveDate) -> Result<bool>;
+    /// Lists the completions of every goal of the owner, the oldest first.
+    async fn find_completions(&self, owner: &str) -> Result<Vec<GoalCompletion>>;
+}
+
+pub struct GoalRepositoryImpl {
+    pool: Pool,
+}
+
+impl GoalRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_row(&self, row: &Row) -> Result<Goal> {
+        let metric: &str = row.get("metric");
+        let period: &str = row.get("period");
+        Ok(Goal {
+            id: row.get("id"),
+            owner: row.get("owner"),
+            title: row.get("title"),
+            metric: GoalMetric::parse(metric)
+                .ok_or_else(|| anyhow!("The goal metric `{metric}` is unknown."))?,
+            target: row.get("target"),
+            period: GoalPeriod::parse(period)
+                .ok_or_else(|| anyhow!("The goal period `{period}` is unknown."))?,
+            period_days: row.get("period_days"),
+            starts_on: row.get("starts_on"),
+            category_id: row.get("category_id"),
+        })
+    }
+}
+
+#[async_trait]
+impl GoalRepository for GoalRepositoryImpl {
+    async fn add(&self, data: &Goal) -> Result<Goal> {
+        let metric = data.metric.as_str();
+        let period = data.period.as_str();
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &data.owner,
+            &data.title,
+            &metric,
+            &data.target,
+            &period,
+            &data.period_days,
+            &data.starts_on,
+            &data.category_id,
+        ];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_one(
+                "
+                INSERT INTO goals (
+                    owner,
+                    title,
+                    metric,
+                    target,
+                    period,
+                    period_days,
+                    starts_on,
+                    category_id)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+                RETURNING
+                    id,
+                    owner,
+                    title,
+                    metric,
+                    target,
+                    period,
+                    period_days,
+                    starts_on,
+                    category_id",
+                &row,
+            )
+            .await?;
+        self.parse_row(&query_result)
+    }
+
+    async fn find(&self, owner: &str, id: i64) -> Result<Option<Goal>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                SELECT
+                    id,
+                    owner,
+                    title,
+                    metric,
+                    target,
+                    period,
+                    period_days,
+                    starts_on,
+                    category_id
+                FROM goals
+                WHERE
+                    owner = $1
+                    AND id = $2",
+                &row,
+            )
+            .await?;
+        query_result.map(|r| self.parse_row(&r)).transpose()
+    }
+
+    async fn find_all(&self, owner: &str) -> Result<Vec<Goal>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    id,
+                    owner,
+                    title,
+                    metric,
+                    target,
+                    period,
+                    period_days,
+                    starts_on,
+                    category_id
+                FROM goals
+                WHERE
+                    owner = $1
+                ORDER BY id",
+                &row,
+            )
+            .await?;
+        query_result.iter().map(|r| self.parse_row(r)).collect()
+    }
+
+    async fn update(&self, data: &Goal) -> Result<Option<Goal>> {
+        let metric = data.metric.as_str();
+        let period = data.period.as_str();
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &data.owner,
+            &data.id,
+            &data.title,
+            &metric,
+            &data.target,
+            &period,
+            &data.period_days,
+            &data.starts_on,
+            &data.category_id,
+        ];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                UPDATE goals
+                SET
+                    title = $3,
+                    metric = $4,
+                    target = $5,
+                    period = $6,
+                    period_days = $7,
+                    starts_on = $8,
+                    category_id = $9
+                WHERE
+                    owner = $1
+                    AND id = $2
+                RETURNING
+                    id,
+                    owner,
+                    title,
+                    metric,
+                    target,
+                    period,
+                    period_days,
+                    starts_on,
+                    category_id",
+                &row,
+            )
+            .await?;
+        query_result.map(|r| self.parse_row(&r)).transpose()
+    }
+
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id];
+        let deleted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                DELETE FROM goals
+                WHERE
+                    owner = $1
+                    AND id = $2",
+                &row,
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    async fn mark_completed(&self, id: i64, first_day: NaiveDate) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&id, &first_day];
+        let inserted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                INSERT INTO goal_completions (
+                    goal_id,
+                    first_day)
+                VALUES ($1, $2)
+                ON CONFLICT (goal_id, first_day) DO NOTHING",
+                &row,
+            )
+            .await?;
+        Ok(inserted > 0)
+    }
+
+    async fn find_completions(&self, owner: &str) -> Result<Vec<GoalCompletion>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    goal_completions.goal_id,
+                    goal_completions.first_day,
+                    goal_completions.completed_at
+                FROM goal_completions
+                INNER JOIN goals ON goals.id = goal_completions.goal_id
+                WHERE
+                    goals.owner = $1
+                ORDER BY goal_completions.completed_at, goal_completions.goal_id",
+                &row,
+            )
+            .await?;
+        Ok(query_result
+            .iter()
+            .map(|r| GoalCompletion {
+                goal_id: r.get("goal_id"),
+                first_day: r.get("first_day"),
+                completed_at: r.get("completed_at"),
+            })
+            .collect())
+    }
+}
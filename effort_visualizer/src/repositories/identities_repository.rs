@@ -0,0 +1,154 @@
+use super::database::get_client;
+use crate::domain::identities::UserIdentity;
+use anyhow::Result;
+use async_trait::async_trait;
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::{types::ToSql, Row};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum IdentityDeletion {
+    Deleted,
+    NotFound,
+    /// The identity was kept because the user could not sign in anymore without it.
+    LastIdentity,
+}
+
+#[automock]
+#[async_trait]
+pub trait UserIdentityRepository: Send {
+    /// Stores `data` unless the account or another account of the same provider is linked
+    /// already, e.g. by a concurrent request, in which case `false` is returned.
+    async fn add(&self, data: &UserIdentity) -> Result<bool>;
+    async fn find(&self, provider: &str, external_id: &str) -> Result<Option<UserIdentity>>;
+    async fn find_by_user(&self, user_email: &str) -> Result<Vec<UserIdentity>>;
+    /// Deletes the identity unless it is the last one of the user.
+    async fn delete(&self, user_email: &str, provider: &str) -> Result<IdentityDeletion>;
+}
+
+pub struct UserIdentityRepositoryImpl {
+    pool: Pool,
+}
+
+impl UserIdentityRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_row(&self, row: &Row) -> UserIdentity {
+        UserIdentity {
+            provider: row.get("provider"),
+            external_id: row.get("external_id"),
+            user_email: row.get("user_email"),
+            linked_at: row.get("linked_at"),
+        }
+    }
+}
+
+#[async_trait]
+impl UserIdentityRepository for UserIdentityRepositoryImpl {
+    async fn add(&self, data: &UserIdentity) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &data.provider,
+            &data.external_id,
+            &data.user_email,
+            &data.linked_at,
+        ];
+        let inserted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                INSERT INTO user_identities (
+                    provider,
+                    external_id,
+                    user_email,
+                    linked_at)
+                VALUES ($1, $2, $3, $4)
+                ON CONFLICT DO NOTHING",
+                &row,
+            )
+            .await?;
+        Ok(inserted > 0)
+    }
+
+    async fn find(&self, provider: &str, external_id: &str) -> Result<Option<UserIdentity>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&provider, &external_id];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                SELECT
+                    provider,
+                    external_id,
+                    user_email,
+                    linked_at
+                FROM user_identities
+                WHERE
+                    provider = $1
+                    AND external_id = $2",
+                &row,
+            )
+            .await?;
+        Ok(query_result.map(|r| self.parse_row(&r)))
+    }
+
+    async fn find_by_user(&self, user_email: &str) -> Result<Vec<UserIdentity>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&user_email];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    provider,
+                    external_id,
+                    user_email,
+                    linked_at
+                FROM user_identities
+                WHERE
+                    user_email = $1
+                ORDER BY linked_at",
+                &row,
+            )
+            .await?;
+        Ok(query_result.iter().map(|r| self.parse_row(r)).collect())
+    }
+
+    async fn delete(&self, user_email: &str, provider: &str) -> Result<IdentityDeletion> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        // Locking the user serializes concurrent unlinks, so two of them can't remove
+        // the last two identities at once.
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&user_email];
+        transaction
+            .execute("SELECT 1 FROM users WHERE email = $1 FOR UPDATE", &row)
+            .await?;
+        let providers: Vec<String> = transaction
+            .query(
+                "SELECT provider FROM user_identities WHERE user_email = $1",
+                &row,
+            )
+            .await?
+            .iter()
+            .map(|r| r.get("provider"))
+            .collect();
+        if !providers.iter().any(|p| p == provider) {
+            return Ok(IdentityDeletion::NotFound);
+        }
+        if providers.len() == 1 {
+            return Ok(IdentityDeletion::LastIdentity);
+        }
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&user_email, &provider];
+        transaction
+            .execute(
+                "
+                DELETE FROM user_identities
+                WHERE
+                    user_email = $1
+                    AND provider = $2",
+                &row,
+            )
+            .await?;
+        transaction.commit().await?;
+        Ok(IdentityDeletion::Deleted)
+    }
+}
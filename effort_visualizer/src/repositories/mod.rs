@@ -1 +1,11 @@
+pub mod categories_repository;
+pub mod database;
+pub mod efforts_repository;
+pub mod events_repository;
+pub mod goals_repository;
+pub mod identities_repository;
+pub mod pomodoros_repository;
+pub mod sessions_repository;
+pub mod tags_repository;
+pub mod timers_repository;
 pub mod users_repository;
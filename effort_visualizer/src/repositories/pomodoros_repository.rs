@@ -0,0 +1,123 @@
+use super::database::get_client;
+use crate::domain::pomodoros::{PomodoroDay, PomodoroInterruption};
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::NaiveDate;
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::types::ToSql;
+
+#[automock]
+#[async_trait]
+pub trait PomodoroRepository: Send {
+    /// Counts the completed and abandoned pomodoros of every day from `from` to `to` of
+    /// `timezone`, including the days without any.
+    async fn daily_counts(
+        &self,
+        owner: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: &str,
+    ) -> Result<Vec<PomodoroDay>>;
+    /// Lists the work intervals the owner stopped before they were completed, the oldest first.
+    async fn find_interruptions(&self, owner: &str) -> Result<Vec<PomodoroInterruption>>;
+}
+
+pub struct PomodoroRepositoryImpl {
+    pool: Pool,
+}
+
+impl PomodoroRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+}
+
+#[async_trait]
+impl PomodoroRepository for PomodoroRepositoryImpl {
+    async fn daily_counts(
+        &self,
+        owner: &str,
+        from: NaiveDate,
+        to: NaiveDate,
+        timezone: &str,
+    ) -> Result<Vec<PomodoroDay>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &from, &to, &timezone];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                WITH completed AS (
+                    SELECT
+                        (started_at AT TIME ZONE $4::text)::date AS date,
+                        COUNT(*) AS count
+                    FROM efforts
+                    WHERE
+                        owner = $1
+                        AND pomodoro
+                        AND started_at >= $2::date::timestamp AT TIME ZONE $4::text
+                        AND started_at < ($3::date + 1)::timestamp AT TIME ZONE $4::text
+                    GROUP BY 1),
+                abandoned AS (
+                    SELECT
+                        (started_at AT TIME ZONE $4::text)::date AS date,
+                        COUNT(*) AS count
+                    FROM pomodoro_interruptions
+                    WHERE
+                        owner = $1
+                        AND started_at >= $2::date::timestamp AT TIME ZONE $4::text
+                        AND started_at < ($3::date + 1)::timestamp AT TIME ZONE $4::text
+                    GROUP BY 1)
+                SELECT
+                    days.date::date AS date,
+                    COALESCE(completed.count, 0) AS completed,
+                    COALESCE(abandoned.count, 0) AS abandoned
+                FROM generate_series($2::date::timestamp, $3::date::timestamp, '1 day')
+                    AS days(date)
+                LEFT JOIN completed ON completed.date = days.date::date
+                LEFT JOIN abandoned ON abandoned.date = days.date::date
+                ORDER BY days.date",
+                &row,
+            )
+            .await?;
+        Ok(query_result
+            .iter()
+            .map(|r| PomodoroDay {
+                date: r.get("date"),
+                completed: r.get("completed"),
+                abandoned: r.get("abandoned"),
+            })
+            .collect())
+    }
+
+    async fn find_interruptions(&self, owner: &str) -> Result<Vec<PomodoroInterruption>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    owner,
+                    title,
+                    started_at,
+                    interrupted_at,
+                    worked_seconds
+                FROM pomodoro_interruptions
+                WHERE
+                    owner = $1
+                ORDER BY started_at, id",
+                &row,
+            )
+            .await?;
+        Ok(query_result
+            .iter()
+            .map(|r| PomodoroInterruption {
+                owner: r.get("owner"),
+                title: r.get("title"),
+                started_at: r.get("started_at"),
+                interrupted_at: r.get("interrupted_at"),
+                worked_seconds: r.get("worked_seconds"),
+            })
+            .collect())
+    }
+}
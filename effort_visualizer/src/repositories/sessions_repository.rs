@@ -0,0 +1,317 @@
+use super::database::get_client;
+use crate::domain::sessions::{SessionEndReason, SessionRecord, UserSession};
+use anyhow::{anyhow, Result};
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::{types::ToSql, Row};
+
+#[automock]
+#[async_trait]
+pub trait SessionRepository: Send {
+    async fn add(
+        &self,
+        session_key: &str,
+        user_email: Option<String>,
+        state: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()>;
+    /// Returns the state of the session unless it is missing or expired.
+    async fn find_state(&self, session_key: &str) -> Result<Option<String>>;
+    /// Leaves a session that no longer exists alone and returns `false`.
+    async fn update(
+        &self,
+        session_key: &str,
+        user_email: Option<String>,
+        state: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool>;
+    async fn update_expiry(&self, session_key: &str, expires_at: DateTime<Utc>) -> Result<()>;
+    /// Ends the session as logged out. Sessions of a logged in user are kept in the history.
+    async fn delete(&self, session_key: &str) -> Result<()>;
+    /// Lists the sessions of the user that have not expired yet.
+    async fn find_by_user(&self, user_email: &str) -> Result<Vec<UserSession>>;
+    /// Lists every session of the user, the ones stored now and the ended ones in the history,
+    /// the newest first.
+    async fn find_history(&self, user_email: &str) -> Result<Vec<SessionRecord>>;
+    /// Ends the session as revoked and keeps it in the history.
+    async fn delete_by_user(&self, user_email: &str, id: i64) -> Result<bool>;
+    /// Ends the expired sessions and keeps the ones of logged in users in the history.
+    async fn delete_expired(&self) -> Result<u64>;
+}
+
+pub struct SessionRepositoryImpl {
+    pool: Pool,
+}
+
+impl SessionRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_record(&self, row: &Row) -> Result<SessionRecord> {
+        let end_reason: Option<&str> = row.get("end_reason");
+        Ok(SessionRecord {
+            id: row.get("id"),
+            created_at: row.get("created_at"),
+            last_seen_at: row.get("last_seen_at"),
+            ended_at: row.get("ended_at"),
+            end_reason: end_reason
+                .map(|reason| {
+                    SessionEndReason::parse(reason)
+                        .ok_or_else(|| anyhow!("The end reason `{reason}` is unknown."))
+                })
+                .transpose()?,
+        })
+    }
+}
+
+#[async_trait]
+impl SessionRepository for SessionRepositoryImpl {
+    async fn add(
+        &self,
+        session_key: &str,
+        user_email: Option<String>,
+        state: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<()> {
+        let now = Utc::now();
+        let row: Vec<&'_ (dyn ToSql + Sync)> =
+            vec![&session_key, &user_email, &state, &now, &expires_at];
+        get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                INSERT INTO sessions (
+                    session_key,
+                    user_email,
+                    state,
+                    created_at,
+                    updated_at,
+                    expires_at)
+                VALUES ($1, $2, $3, $4, $4, $5)",
+                &row,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn find_state(&self, session_key: &str) -> Result<Option<String>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&session_key];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                SELECT state
+                FROM sessions
+                WHERE
+                    session_key = $1
+                    AND expires_at > now()",
+                &row,
+            )
+            .await?;
+        Ok(query_result.map(|r| r.get("state")))
+    }
+
+    async fn update(
+        &self,
+        session_key: &str,
+        user_email: Option<String>,
+        state: &str,
+        expires_at: DateTime<Utc>,
+    ) -> Result<bool> {
+        let now = Utc::now();
+        let row: Vec<&'_ (dyn ToSql + Sync)> =
+            vec![&session_key, &user_email, &state, &now, &expires_at];
+        let updated = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                UPDATE sessions
+                SET
+                    user_email = $2,
+                    state = $3,
+                    updated_at = $4,
+                    expires_at = $5
+                WHERE
+                    session_key = $1
+                    AND expires_at > now()",
+                &row,
+            )
+            .await?;
+        Ok(updated > 0)
+    }
+
+    async fn update_expiry(&self, session_key: &str, expires_at: DateTime<Utc>) -> Result<()> {
+        let now = Utc::now();
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&session_key, &now, &expires_at];
+        get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                UPDATE sessions
+                SET
+                    updated_at = $2,
+                    expires_at = $3
+                WHERE
+                    session_key = $1",
+                &row,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn delete(&self, session_key: &str) -> Result<()> {
+        let reason = SessionEndReason::LoggedOut.as_str();
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&session_key, &reason];
+        get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                WITH ended AS (
+                    DELETE FROM sessions
+                    WHERE
+                        session_key = $1
+                    RETURNING *)
+                INSERT INTO session_history (
+                    session_id,
+                    user_email,
+                    created_at,
+                    last_seen_at,
+                    ended_at,
+                    end_reason)
+                SELECT id, user_email, created_at, updated_at, now(), $2
+                FROM ended
+                WHERE
+                    user_email IS NOT NULL",
+                &row,
+            )
+            .await?;
+        Ok(())
+    }
+
+    async fn find_by_user(&self, user_email: &str) -> Result<Vec<UserSession>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&user_email];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    id,
+                    created_at,
+                    updated_at,
+                    expires_at
+                FROM sessions
+                WHERE
+                    user_email = $1
+                    AND expires_at > now()
+                ORDER BY updated_at DESC",
+                &row,
+            )
+            .await?;
+        Ok(query_result
+            .iter()
+            .map(|r| UserSession {
+                id: r.get("id"),
+                created_at: r.get("created_at"),
+                updated_at: r.get("updated_at"),
+                expires_at: r.get("expires_at"),
+            })
+            .collect())
+    }
+
+    async fn find_history(&self, user_email: &str) -> Result<Vec<SessionRecord>> {
+        let expired = SessionEndReason::Expired.as_str();
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&user_email, &expired];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    id,
+                    created_at,
+                    updated_at AS last_seen_at,
+                    CASE WHEN expires_at <= now() THEN expires_at END AS ended_at,
+                    CASE WHEN expires_at <= now() THEN $2 END AS end_reason
+                FROM sessions
+                WHERE
+                    user_email = $1
+                UNION ALL
+                SELECT
+                    session_id,
+                    created_at,
+                    last_seen_at,
+                    ended_at,
+                    end_reason
+                FROM session_history
+                WHERE
+                    user_email = $1
+                ORDER BY created_at DESC",
+                &row,
+            )
+            .await?;
+        query_result.iter().map(|r| self.parse_record(r)).collect()
+    }
+
+    async fn delete_by_user(&self, user_email: &str, id: i64) -> Result<bool> {
+        let reason = SessionEndReason::Revoked.as_str();
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&user_email, &id, &reason];
+        let deleted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                WITH ended AS (
+                    DELETE FROM sessions
+                    WHERE
+                        user_email = $1
+                        AND id = $2
+                    RETURNING *)
+                INSERT INTO session_history (
+                    session_id,
+                    user_email,
+                    created_at,
+                    last_seen_at,
+                    ended_at,
+                    end_reason)
+                SELECT id, user_email, created_at, updated_at, now(), $3
+                FROM ended",
+                &row,
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
+
+    async fn delete_expired(&self) -> Result<u64> {
+        let reason = SessionEndReason::Expired.as_str();
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&reason];
+        let result = get_client(&self.pool)
+            .await?
+            .query_one(
+                "
+                WITH ended AS (
+                    DELETE FROM sessions
+                    WHERE
+                        expires_at <= now()
+                    RETURNING *),
+                kept AS (
+                    INSERT INTO session_history (
+                        session_id,
+                        user_email,
+                        created_at,
+                        last_seen_at,
+                        ended_at,
+                        end_reason)
+                    SELECT id, user_email, created_at, updated_at, expires_at, $1
+                    FROM ended
+                    WHERE
+                        user_email IS NOT NULL)
+                SELECT count(*) AS deleted
+                FROM ended",
+                &row,
+            )
+            .await?;
+        let deleted: i64 = result.get("deleted");
+        Ok(deleted as u64)
+    }
+}
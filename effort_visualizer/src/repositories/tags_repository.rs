@@ -0,0 +1,185 @@
+use super::database::get_client;
+use crate::domain::tags::Tag;
+use anyhow::Result;
+use async_trait::async_trait;
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::{error::SqlState, types::ToSql, Row};
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum TagWrite {
+    Saved(Tag),
+    NotFound,
+    /// The owner has another tag with the name.
+    NameTaken,
+}
+
+#[automock]
+#[async_trait]
+pub trait TagRepository: Send {
+    /// Stores `data` and returns it with the id assigned by the database.
+    async fn add(&self, data: &Tag) -> Result<TagWrite>;
+    async fn find_all(&self, owner: &str) -> Result<Vec<Tag>>;
+    /// Renames the tag. Efforts refer to tags by id, so they carry the new name at once.
+    async fn rename(&self, owner: &str, id: i64, name: &str) -> Result<TagWrite>;
+    /// Moves the efforts tagged `source_id` over to `target_id` and deletes the source tag,
+    /// all in one transaction. Returns the target, or `None` when either tag doesn't exist.
+    async fn merge(&self, owner: &str, source_id: i64, target_id: i64) -> Result<Option<Tag>>;
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool>;
+}
+
+pub struct TagRepositoryImpl {
+    pool: Pool,
+}
+
+impl TagRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_row(&self, row: &Row) -> Tag {
+        Tag {
+            id: row.get("id"),
+            owner: row.get("owner"),
+            name: row.get("name"),
+        }
+    }
+}
+
+#[async_trait]
+impl TagRepository for TagRepositoryImpl {
+    async fn add(&self, data: &Tag) -> Result<TagWrite> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&data.owner, &data.name];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                INSERT INTO tags (
+                    owner,
+                    name)
+                VALUES ($1, $2)
+                ON CONFLICT (owner, name) DO NOTHING
+                RETURNING
+                    id,
+                    owner,
+                    name",
+                &row,
+            )
+            .await?;
+        Ok(match query_result {
+            Some(r) => TagWrite::Saved(self.parse_row(&r)),
+            None => TagWrite::NameTaken,
+        })
+    }
+
+    async fn find_all(&self, owner: &str) -> Result<Vec<Tag>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    id,
+                    owner,
+                    name
+                FROM tags
+                WHERE
+                    owner = $1
+                ORDER BY name",
+                &row,
+            )
+            .await?;
+        Ok(query_result.iter().map(|r| self.parse_row(r)).collect())
+    }
+
+    async fn rename(&self, owner: &str, id: i64, name: &str) -> Result<TagWrite> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id, &name];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                UPDATE tags
+                SET
+                    name = $3
+                WHERE
+                    owner = $1
+                    AND id = $2
+                RETURNING
+                    id,
+                    owner,
+                    name",
+                &row,
+            )
+            .await;
+        Ok(match query_result {
+            Ok(Some(r)) => TagWrite::Saved(self.parse_row(&r)),
+            Ok(None) => TagWrite::NotFound,
+            Err(e) if e.code() == Some(&SqlState::UNIQUE_VIOLATION) => TagWrite::NameTaken,
+            Err(e) => return Err(e.into()),
+        })
+    }
+
+    async fn merge(&self, owner: &str, source_id: i64, target_id: i64) -> Result<Option<Tag>> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        // Locking both tags keeps them from being renamed, merged or deleted meanwhile.
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &source_id, &target_id];
+        let locked = transaction
+            .query(
+                "
+                SELECT
+                    id,
+                    owner,
+                    name
+                FROM tags
+                WHERE
+                    owner = $1
+                    AND id IN ($2, $3)
+                ORDER BY id
+                FOR UPDATE",
+                &row,
+            )
+            .await?;
+        let target = match locked.iter().find(|r| r.get::<_, i64>("id") == target_id) {
+            Some(target) if locked.len() == 2 => self.parse_row(target),
+            _ => return Ok(None),
+        };
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&source_id, &target_id];
+        transaction
+            .execute(
+                "
+                INSERT INTO effort_tags (
+                    effort_id,
+                    tag_id)
+                SELECT effort_id, $2
+                FROM effort_tags
+                WHERE
+                    tag_id = $1
+                ON CONFLICT (effort_id, tag_id) DO NOTHING",
+                &row,
+            )
+            .await?;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&source_id];
+        transaction
+            .execute("DELETE FROM tags WHERE id = $1", &row)
+            .await?;
+        transaction.commit().await?;
+        Ok(Some(target))
+    }
+
+    async fn delete(&self, owner: &str, id: i64) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner, &id];
+        let deleted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                DELETE FROM tags
+                WHERE
+                    owner = $1
+                    AND id = $2",
+                &row,
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
+}
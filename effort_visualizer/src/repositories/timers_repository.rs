@@ -0,0 +1,279 @@
+use super::database::get_client;
+use super::efforts_repository::insert_effort;
+use crate::domain::efforts::Effort;
+use crate::domain::pomodoros::{PomodoroInterruption, PomodoroSettings};
+use crate::domain::timers::Timer;
+use anyhow::Result;
+use async_trait::async_trait;
+use deadpool_postgres::Pool;
+use mockall::automock;
+use tokio_postgres::{types::ToSql, Row};
+
+#[automock]
+#[async_trait]
+pub trait TimerRepository: Send {
+    async fn find(&self, owner: &str) -> Result<Option<Timer>>;
+    /// Stores `data` unless its owner has a timer already, in which case `None` is returned.
+    async fn add(&self, data: &Timer) -> Result<Option<Timer>>;
+    /// Overwrites the owner's timer if it is still at `data.revision`, bumps the revision and
+    /// stores the pomodoros it `completed` in the same transaction. Returns the timer and the
+    /// stored efforts, or `None` when the timer has been changed or stopped since.
+    async fn update(
+        &self,
+        data: &Timer,
+        completed: &[Effort],
+    ) -> Result<Option<(Timer, Vec<Effort>)>>;
+    /// Deletes the owner's timer if it is still at `data.revision`, and stores the efforts it
+    /// recorded and the pomodoro it interrupted in the same transaction. Returns the stored
+    /// efforts, or `None` when the timer has been changed or stopped since.
+    async fn finish(
+        &self,
+        data: &Timer,
+        efforts: &[Effort],
+        interruption: Option<PomodoroInterruption>,
+    ) -> Result<Option<Vec<Effort>>>;
+}
+
+pub struct TimerRepositoryImpl {
+    pool: Pool,
+}
+
+impl TimerRepositoryImpl {
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
+    }
+
+    fn parse_row(&self, row: &Row) -> Timer {
+        let work_seconds: Option<i32> = row.get("pomodoro_work_seconds");
+        Timer {
+            owner: row.get("owner"),
+            title: row.get("title"),
+            notes: row.get("notes"),
+            category_id: row.get("category_id"),
+            tags: row.get("tags"),
+            started_at: row.get("started_at"),
+            running_since: row.get("running_since"),
+            accumulated_seconds: row.get("accumulated_seconds"),
+            pomodoro: work_seconds.map(|work_seconds| PomodoroSettings {
+                work_seconds,
+                short_break_seconds: row.get("pomodoro_short_break_seconds"),
+                long_break_seconds: row.get("pomodoro_long_break_seconds"),
+                cycles: row.get("pomodoro_cycles"),
+            }),
+            settled_seconds: row.get("pomodoro_settled_seconds"),
+            work_started_at: row.get("work_started_at"),
+            revision: row.get("revision"),
+        }
+    }
+}
+
+#[async_trait]
+impl TimerRepository for TimerRepositoryImpl {
+    async fn find(&self, owner: &str) -> Result<Option<Timer>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&owner];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                SELECT
+                    owner,
+                    title,
+                    notes,
+                    category_id,
+                    tags,
+                    started_at,
+                    running_since,
+                    accumulated_seconds,
+                    pomodoro_work_seconds,
+                    pomodoro_short_break_seconds,
+                    pomodoro_long_break_seconds,
+                    pomodoro_cycles,
+                    pomodoro_settled_seconds,
+                    work_started_at,
+                    revision
+                FROM timers
+                WHERE
+                    owner = $1",
+                &row,
+            )
+            .await?;
+        Ok(query_result.map(|r| self.parse_row(&r)))
+    }
+
+    async fn add(&self, data: &Timer) -> Result<Option<Timer>> {
+        let pomodoro = data.pomodoro.as_ref();
+        let work_seconds = pomodoro.map(|settings| settings.work_seconds);
+        let short_break_seconds = pomodoro.map(|settings| settings.short_break_seconds);
+        let long_break_seconds = pomodoro.map(|settings| settings.long_break_seconds);
+        let cycles = pomodoro.map(|settings| settings.cycles);
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &data.owner,
+            &data.title,
+            &data.notes,
+            &data.category_id,
+            &data.tags,
+            &data.started_at,
+            &data.running_since,
+            &data.accumulated_seconds,
+            &work_seconds,
+            &short_break_seconds,
+            &long_break_seconds,
+            &cycles,
+            &data.settled_seconds,
+            &data.work_started_at,
+        ];
+        // The owner is the primary key, so of two tabs starting a timer at once only one wins.
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                INSERT INTO timers (
+                    owner,
+                    title,
+                    notes,
+                    category_id,
+                    tags,
+                    started_at,
+                    running_since,
+                    accumulated_seconds,
+                    pomodoro_work_seconds,
+                    pomodoro_short_break_seconds,
+                    pomodoro_long_break_seconds,
+                    pomodoro_cycles,
+                    pomodoro_settled_seconds,
+                    work_started_at)
+                VALUES ($1, $2, $3, $4, $5, $6, $7, $8, $9, $10, $11, $12, $13, $14)
+                ON CONFLICT (owner) DO NOTHING
+                RETURNING
+                    owner,
+                    title,
+                    notes,
+                    category_id,
+                    tags,
+                    started_at,
+                    running_since,
+                    accumulated_seconds,
+                    pomodoro_work_seconds,
+                    pomodoro_short_break_seconds,
+                    pomodoro_long_break_seconds,
+                    pomodoro_cycles,
+                    pomodoro_settled_seconds,
+                    work_started_at,
+                    revision",
+                &row,
+            )
+            .await?;
+        Ok(query_result.map(|r| self.parse_row(&r)))
+    }
+
+    async fn update(
+        &self,
+        data: &Timer,
+        completed: &[Effort],
+    ) -> Result<Option<(Timer, Vec<Effort>)>> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &data.owner,
+            &data.revision,
+            &data.running_since,
+            &data.accumulated_seconds,
+            &data.settled_seconds,
+            &data.work_started_at,
+        ];
+        let query_result = transaction
+            .query_opt(
+                "
+                UPDATE timers
+                SET
+                    running_since = $3,
+                    accumulated_seconds = $4,
+                    pomodoro_settled_seconds = $5,
+                    work_started_at = $6,
+                    revision = revision + 1
+                WHERE
+                    owner = $1
+                    AND revision = $2
+                RETURNING
+                    owner,
+                    title,
+                    notes,
+                    category_id,
+                    tags,
+                    started_at,
+                    running_since,
+                    accumulated_seconds,
+                    pomodoro_work_seconds,
+                    pomodoro_short_break_seconds,
+                    pomodoro_long_break_seconds,
+                    pomodoro_cycles,
+                    pomodoro_settled_seconds,
+                    work_started_at,
+                    revision",
+                &row,
+            )
+            .await?;
+        let timer = match query_result {
+            Some(row) => self.parse_row(&row),
+            None => return Ok(None),
+        };
+        let mut stored = Vec::with_capacity(completed.len());
+        for effort in completed {
+            stored.push(insert_effort(&transaction, effort).await?);
+        }
+        transaction.commit().await?;
+        Ok(Some((timer, stored)))
+    }
+
+    async fn finish(
+        &self,
+        data: &Timer,
+        efforts: &[Effort],
+        interruption: Option<PomodoroInterruption>,
+    ) -> Result<Option<Vec<Effort>>> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&data.owner, &data.revision];
+        let deleted = transaction
+            .execute(
+                "
+                DELETE FROM timers
+                WHERE
+                    owner = $1
+                    AND revision = $2",
+                &row,
+            )
+            .await?;
+        if deleted == 0 {
+            return Ok(None);
+        }
+        let mut stored = Vec::with_capacity(efforts.len());
+        for effort in efforts {
+            stored.push(insert_effort(&transaction, effort).await?);
+        }
+        if let Some(interruption) = interruption {
+            let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+                &interruption.owner,
+                &interruption.title,
+                &interruption.started_at,
+                &interruption.interrupted_at,
+                &interruption.worked_seconds,
+            ];
+            transaction
+                .execute(
+                    "
+                    INSERT INTO pomodoro_interruptions (
+                        owner,
+                        title,
+                        started_at,
+                        interrupted_at,
+                        worked_seconds)
+                    VALUES ($1, $2, $3, $4, $5)",
+                    &row,
+                )
+                .await?;
+        }
+        transaction.commit().await?;
+        Ok(Some(stored))
+    }
+}
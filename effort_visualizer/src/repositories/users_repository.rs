@@ -1,98 +1,121 @@
-use crate::domain::users::User;
+use super::database::get_client;
+use crate::domain::{identities::UserIdentity, users::User};
 use anyhow::Result;
 use async_trait::async_trait;
+use deadpool_postgres::Pool;
 use mockall::automock;
-use tokio_postgres::{types::ToSql, Client, NoTls, Row};
-use tracing::error;
+use tokio_postgres::{types::ToSql, Row};
 
 #[automock]
 #[async_trait]
 pub trait UserRepository: Send {
-    async fn add(&self, data: &User) -> Result<()>;
+    /// Registers the user together with the identity they signed up with.
+    async fn add(&self, data: &User, identity: &UserIdentity) -> Result<()>;
     async fn find(&self, email: &str) -> Result<Option<User>>;
+    async fn find_by_identity(&self, provider: &str, external_id: &str) -> Result<Option<User>>;
+    async fn find_all(&self) -> Result<Vec<User>>;
+    /// Returns the email of the user whose calendar is public under `slug`.
+    async fn find_email_by_public_slug(&self, slug: &str) -> Result<Option<String>>;
+    /// Makes the user's calendar public under `slug`, or private when it is `None`. Returns
+    /// `false` when the user is not registered.
+    async fn update_public_slug(&self, email: &str, slug: Option<String>) -> Result<bool>;
+    /// Saves the profile of the user. Returns `false` when the user is not registered.
+    async fn update(&self, data: &User) -> Result<bool>;
+    /// Removes the user together with their identities, efforts and sessions. Owned rows are
+    /// removed by `ON DELETE CASCADE` within the same statement, so nothing is left behind
+    /// when it fails.
+    async fn delete(&self, email: &str) -> Result<bool>;
 }
 
 pub struct UserRepositoryImpl {
-    connection_str: String,
+    pool: Pool,
 }
 
 impl UserRepositoryImpl {
-    pub fn new(
-        server: String,
-        port: String,
-        database: String,
-        user_id: String,
-        password: String,
-    ) -> Self {
-        let connection_str = String::from(&format!(
-            "postgresql://{user_id}:{password}@{server}:{port}/{database}"
-        ));
-        Self { connection_str }
+    pub fn new(pool: Pool) -> Self {
+        Self { pool }
     }
 
-    async fn connect_database(&self) -> Result<Client> {
-        let (client, connection) = tokio_postgres::connect(&self.connection_str, NoTls).await?;
-        tokio::spawn(async move {
-            if let Err(e) = connection.await {
-                error!("connection error: {}", e);
-            }
-        });
-        Ok(client)
+    fn parse_query_result(&self, result: Vec<Row>) -> Result<Option<User>> {
+        Ok(result.first().map(|r| self.parse_row(r)))
     }
 
-    fn parse_query_result(&self, result: Vec<Row>) -> Result<Option<User>> {
-        Ok(if result.is_empty() {
-            None
-        } else {
-            Some(User {
-                email: result[0].get("email"),
-                external_id: result[0].get("external_id"),
-                user_name: result[0].get("user_name"),
-                registered_date: result[0].get("registered_date"),
-                updated_date: result[0].get("updated_date"),
-            })
-        })
+    fn parse_row(&self, row: &Row) -> User {
+        User {
+            email: row.get("email"),
+            user_name: row.get("user_name"),
+            avatar_url: row.get("avatar_url"),
+            timezone: row.get("timezone"),
+            locale: row.get("locale"),
+            registered_date: row.get("registered_date"),
+            updated_date: row.get("updated_date"),
+        }
     }
 }
 
 #[async_trait]
 impl UserRepository for UserRepositoryImpl {
-    async fn add(&self, data: &User) -> Result<()> {
+    async fn add(&self, data: &User, identity: &UserIdentity) -> Result<()> {
+        let mut client = get_client(&self.pool).await?;
+        let transaction = client.transaction().await?;
         let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
             &data.email,
-            &data.external_id,
             &data.user_name,
+            &data.avatar_url,
+            &data.timezone,
+            &data.locale,
             &data.registered_date,
             &data.updated_date,
         ];
-        self.connect_database()
-            .await?
-            .query(
+        transaction
+            .execute(
                 "
                 INSERT INTO users (
                     email,
-                    external_id,
                     user_name,
+                    avatar_url,
+                    timezone,
+                    locale,
                     registered_date,
                     updated_date)
-                VALUES ($1, $2, $3, $4, $5)",
+                VALUES ($1, $2, $3, $4, $5, $6, $7)",
+                &row,
+            )
+            .await?;
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &identity.provider,
+            &identity.external_id,
+            &identity.user_email,
+            &identity.linked_at,
+        ];
+        transaction
+            .execute(
+                "
+                INSERT INTO user_identities (
+                    provider,
+                    external_id,
+                    user_email,
+                    linked_at)
+                VALUES ($1, $2, $3, $4)",
                 &row,
             )
             .await?;
+        transaction.commit().await?;
         Ok(())
     }
 
     async fn find(&self, email: &str) -> Result<Option<User>> {
         let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&email];
-        let query_result = self
-            .connect_database()
+        let query_result = get_client(&self.pool)
             .await?
             .query(
                 "
-                SELECT 
+                SELECT
                     email,
-                    external_id,
                     user_name,
+                    avatar_url,
+                    timezone,
+                    locale,
                     registered_date,
                     updated_date
                 FROM users
@@ -103,4 +126,126 @@ impl UserRepository for UserRepositoryImpl {
             .await?;
         self.parse_query_result(query_result)
     }
+
+    async fn find_by_identity(&self, provider: &str, external_id: &str) -> Result<Option<User>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&provider, &external_id];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    u.email,
+                    u.user_name,
+                    u.avatar_url,
+                    u.timezone,
+                    u.locale,
+                    u.registered_date,
+                    u.updated_date
+                FROM users u
+                INNER JOIN user_identities i ON i.user_email = u.email
+                WHERE
+                    i.provider = $1
+                    AND i.external_id = $2",
+                &row,
+            )
+            .await?;
+        self.parse_query_result(query_result)
+    }
+
+    async fn find_all(&self) -> Result<Vec<User>> {
+        let query_result = get_client(&self.pool)
+            .await?
+            .query(
+                "
+                SELECT
+                    email,
+                    user_name,
+                    avatar_url,
+                    timezone,
+                    locale,
+                    registered_date,
+                    updated_date
+                FROM users
+                ORDER BY registered_date",
+                &[],
+            )
+            .await?;
+        Ok(query_result.iter().map(|r| self.parse_row(r)).collect())
+    }
+
+    async fn find_email_by_public_slug(&self, slug: &str) -> Result<Option<String>> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&slug];
+        let query_result = get_client(&self.pool)
+            .await?
+            .query_opt(
+                "
+                SELECT email
+                FROM users
+                WHERE
+                    public_slug = $1",
+                &row,
+            )
+            .await?;
+        Ok(query_result.map(|r| r.get("email")))
+    }
+
+    async fn update_public_slug(&self, email: &str, slug: Option<String>) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&email, &slug];
+        let updated = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                UPDATE users
+                SET
+                    public_slug = $2
+                WHERE
+                    email = $1",
+                &row,
+            )
+            .await?;
+        Ok(updated > 0)
+    }
+
+    async fn update(&self, data: &User) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![
+            &data.email,
+            &data.user_name,
+            &data.avatar_url,
+            &data.timezone,
+            &data.locale,
+            &data.updated_date,
+        ];
+        let updated = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                UPDATE users
+                SET
+                    user_name = $2,
+                    avatar_url = $3,
+                    timezone = $4,
+                    locale = $5,
+                    updated_date = $6
+                WHERE
+                    email = $1",
+                &row,
+            )
+            .await?;
+        Ok(updated > 0)
+    }
+
+    async fn delete(&self, email: &str) -> Result<bool> {
+        let row: Vec<&'_ (dyn ToSql + Sync)> = vec![&email];
+        let deleted = get_client(&self.pool)
+            .await?
+            .execute(
+                "
+                DELETE FROM users
+                WHERE
+                    email = $1",
+                &row,
+            )
+            .await?;
+        Ok(deleted > 0)
+    }
 }
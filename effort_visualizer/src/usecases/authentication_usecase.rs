@@ -2,70 +2,124 @@ use std::sync::Arc;
 
 use anyhow::{Context, Result};
 use async_trait::async_trait;
+use chrono::Utc;
 use mockall::automock;
 
-use crate::domain::users::User;
-use crate::dto::{LoginResult, LoginSituation, SignupRequest, SignupResult, SignupSituation};
-use crate::{helpers::environments::EnvVariables, repositories::users_repository::UserRepository};
+use crate::domain::{
+    identities::UserIdentity,
+    users::{User, DEFAULT_LOCALE, DEFAULT_TIMEZONE},
+};
+use crate::dto::{
+    LinkIdentityResult, LinkIdentitySituation, LoginRequest, LoginResult, LoginSituation,
+    SignupRequest, SignupResult, SignupSituation, UnlinkIdentityResult, UnlinkIdentitySituation,
+};
+use crate::helpers::identity_providers::IdentityProviders;
+use crate::repositories::identities_repository::{IdentityDeletion, UserIdentityRepository};
+use crate::repositories::users_repository::UserRepository;
 
 #[automock]
 #[async_trait]
 pub trait AuthenticationUsecase {
-    async fn login(&self, credential: &str) -> Result<LoginResult>;
+    async fn login(&self, request: &LoginRequest) -> Result<LoginResult>;
     async fn signup(&self, request: &SignupRequest) -> Result<SignupResult>;
+    async fn get_identities(&self, user_email: &str) -> Result<Vec<UserIdentity>>;
+    /// Lets the user also sign in with the account the credential belongs to.
+    async fn link_identity(
+        &self,
+        user_email: &str,
+        request: &LoginRequest,
+    ) -> Result<LinkIdentityResult>;
+    async fn unlink_identity(
+        &self,
+        user_email: &str,
+        provider: &str,
+    ) -> Result<UnlinkIdentityResult>;
 }
 
 pub struct AuthenticationUsecaseImpl {
-    env_variables: Arc<EnvVariables>,
+    identity_providers: Arc<IdentityProviders>,
     user_repository: Box<dyn UserRepository + Send + Sync>,
+    identity_repository: Box<dyn UserIdentityRepository + Send + Sync>,
 }
 
 impl AuthenticationUsecaseImpl {
     pub fn new(
-        env_variables: Arc<EnvVariables>,
+        identity_providers: Arc<IdentityProviders>,
         user_repository: Box<dyn UserRepository + Send + Sync>,
+        identity_repository: Box<dyn UserIdentityRepository + Send + Sync>,
     ) -> Self {
         Self {
-            env_variables,
+            identity_providers,
             user_repository,
+            identity_repository,
         }
     }
 
-    fn verify_token(&self, credential: &str) -> Result<google_signin::IdInfo> {
-        let mut client = google_signin::Client::new();
-        client
-            .audiences
-            .push(self.env_variables.google_client_id.to_owned());
-        client
-            .verify(credential)
-            .context("Token verification failed.")
+    /// The reason the account `external_id` of `provider` can't be linked to the user, if any.
+    async fn link_conflict(
+        &self,
+        user_email: &str,
+        provider: &str,
+        external_id: &str,
+    ) -> Result<Option<LinkIdentityResult>> {
+        if let Some(identity) = self.identity_repository.find(provider, external_id).await? {
+            if identity.user_email != user_email {
+                return Ok(Some(LinkIdentityResult {
+                    situation: LinkIdentitySituation::BelongsToAnotherUser,
+                    identities: None,
+                    description: Some(
+                        "The account is already used to sign in as another user.".to_owned(),
+                    ),
+                }));
+            }
+            return Ok(Some(LinkIdentityResult {
+                situation: LinkIdentitySituation::AlreadyLinked,
+                identities: Some(self.identity_repository.find_by_user(user_email).await?),
+                description: None,
+            }));
+        }
+        let identities = self.identity_repository.find_by_user(user_email).await?;
+        if identities.iter().any(|i| i.provider == provider) {
+            return Ok(Some(LinkIdentityResult {
+                situation: LinkIdentitySituation::ProviderAlreadyLinked,
+                identities: Some(identities),
+                description: Some(format!(
+                    "Another {provider} account is linked. Unlink it first."
+                )),
+            }));
+        }
+        Ok(None)
     }
 }
 
 #[async_trait]
 impl AuthenticationUsecase for AuthenticationUsecaseImpl {
-    async fn login(&self, credential: &str) -> Result<LoginResult> {
-        let id_token = match self.verify_token(credential) {
-            Ok(id_token) => id_token,
-            Err(e) => {
+    async fn login(&self, request: &LoginRequest) -> Result<LoginResult> {
+        let verifier = match self.identity_providers.get(&request.provider) {
+            Some(verifier) => verifier,
+            None => {
                 return Ok(LoginResult {
-                    situation: LoginSituation::VerificationFailed,
+                    situation: LoginSituation::UnknownProvider,
                     login_user: None,
-                    description: Some(e.to_string()),
+                    description: Some(format!("Unknown provider `{}`.", request.provider)),
                 })
             }
         };
-        let email = match id_token.email {
-            Some(email) => email,
-            None => {
+        let id_token = match verifier.verify(&request.credential).await {
+            Ok(id_token) => id_token,
+            Err(e) => {
                 return Ok(LoginResult {
-                    situation: LoginSituation::EmailIsEmpty,
+                    situation: LoginSituation::VerificationFailed,
                     login_user: None,
-                    description: Some("Email is empty.".to_string()),
+                    description: Some(e.to_string()),
                 })
             }
         };
-        let user = match self.user_repository.find(&email).await? {
+        let user = match self
+            .user_repository
+            .find_by_identity(&request.provider, &id_token.sub)
+            .await?
+        {
             Some(user) => user,
             None => {
                 return Ok(LoginResult {
@@ -91,7 +145,18 @@ impl AuthenticationUsecase for AuthenticationUsecaseImpl {
             });
         }
 
-        let id_token = match self.verify_token(&request.token.credential) {
+        let provider = &request.token.provider;
+        let verifier = match self.identity_providers.get(provider) {
+            Some(verifier) => verifier,
+            None => {
+                return Ok(SignupResult {
+                    situation: SignupSituation::UnknownProvider,
+                    login_user: None,
+                    description: Some(format!("Unknown provider `{provider}`.")),
+                })
+            }
+        };
+        let id_token = match verifier.verify(&request.token.credential).await {
             Ok(id_token) => id_token,
             Err(e) => {
                 return Ok(SignupResult {
@@ -113,26 +178,580 @@ impl AuthenticationUsecase for AuthenticationUsecaseImpl {
             }
         };
 
-        if let Some(user) = self.user_repository.find(&email).await? {
+        if let Some(user) = self
+            .user_repository
+            .find_by_identity(provider, &id_token.sub)
+            .await?
+        {
             return Ok(SignupResult {
                 situation: SignupSituation::AlreadyRegistered,
                 login_user: Some(user),
                 description: None,
             });
         }
+        // The token doesn't prove the caller owns that account, so it is not returned.
+        if self.user_repository.find(&email).await?.is_some() {
+            return Ok(SignupResult {
+                situation: SignupSituation::AlreadyRegistered,
+                login_user: None,
+                description: Some(
+                    "The email address is registered with another provider.".to_owned(),
+                ),
+            });
+        }
 
+        let now = chrono::Utc::now();
         let new_user = User {
             email,
-            external_id: id_token.sub,
             user_name: request.user_name.to_owned(),
-            registered_date: std::time::SystemTime::now(),
-            updated_date: std::time::SystemTime::now(),
+            avatar_url: None,
+            timezone: DEFAULT_TIMEZONE.to_owned(),
+            locale: DEFAULT_LOCALE.to_owned(),
+            registered_date: now,
+            updated_date: now,
+        };
+        let identity = UserIdentity {
+            provider: provider.to_owned(),
+            external_id: id_token.sub,
+            user_email: new_user.email.to_owned(),
+            linked_at: now,
         };
-        self.user_repository.add(&new_user).await?;
+        self.user_repository.add(&new_user, &identity).await?;
         Ok(SignupResult {
             situation: SignupSituation::Succeeded,
             login_user: Some(new_user),
             description: None,
         })
     }
+
+    async fn get_identities(&self, user_email: &str) -> Result<Vec<UserIdentity>> {
+        self.identity_repository.find_by_user(user_email).await
+    }
+
+    async fn link_identity(
+        &self,
+        user_email: &str,
+        request: &LoginRequest,
+    ) -> Result<LinkIdentityResult> {
+        let verifier = match self.identity_providers.get(&request.provider) {
+            Some(verifier) => verifier,
+            None => {
+                return Ok(LinkIdentityResult {
+                    situation: LinkIdentitySituation::UnknownProvider,
+                    identities: None,
+                    description: Some(format!("Unknown provider `{}`.", request.provider)),
+                })
+            }
+        };
+        let id_token = match verifier.verify(&request.credential).await {
+            Ok(id_token) => id_token,
+            Err(e) => {
+                return Ok(LinkIdentityResult {
+                    situation: LinkIdentitySituation::VerificationFailed,
+                    identities: None,
+                    description: Some(e.to_string()),
+                })
+            }
+        };
+
+        if let Some(conflict) = self
+            .link_conflict(user_email, &request.provider, &id_token.sub)
+            .await?
+        {
+            return Ok(conflict);
+        }
+
+        let added = self
+            .identity_repository
+            .add(&UserIdentity {
+                provider: request.provider.to_owned(),
+                external_id: id_token.sub.to_owned(),
+                user_email: user_email.to_owned(),
+                linked_at: Utc::now(),
+            })
+            .await?;
+        if !added {
+            // A concurrent request linked the account or the provider in the meantime.
+            return self
+                .link_conflict(user_email, &request.provider, &id_token.sub)
+                .await?
+                .context("The identities changed while the account was being linked.");
+        }
+        Ok(LinkIdentityResult {
+            situation: LinkIdentitySituation::Succeeded,
+            identities: Some(self.identity_repository.find_by_user(user_email).await?),
+            description: None,
+        })
+    }
+
+    async fn unlink_identity(
+        &self,
+        user_email: &str,
+        provider: &str,
+    ) -> Result<UnlinkIdentityResult> {
+        let situation = match self
+            .identity_repository
+            .delete(user_email, provider)
+            .await?
+        {
+            IdentityDeletion::Deleted => UnlinkIdentitySituation::Succeeded,
+            IdentityDeletion::NotFound => {
+                return Ok(UnlinkIdentityResult {
+                    situation: UnlinkIdentitySituation::NotLinked,
+                    identities: None,
+                    description: None,
+                })
+            }
+            IdentityDeletion::LastIdentity => UnlinkIdentitySituation::LastIdentity,
+        };
+        let description = match situation {
+            UnlinkIdentitySituation::LastIdentity => {
+                Some("The last identity can't be unlinked.".to_owned())
+            }
+            _ => None,
+        };
+        Ok(UnlinkIdentityResult {
+            situation,
+            identities: Some(self.identity_repository.find_by_user(user_email).await?),
+            description,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use std::sync::Arc;
+
+    use super::{AuthenticationUsecase, AuthenticationUsecaseImpl};
+    use crate::domain::identities::UserIdentity;
+    use crate::domain::users::User;
+    use crate::dto::{
+        LinkIdentitySituation, LoginRequest, LoginSituation, SignupRequest, SignupSituation,
+        UnlinkIdentitySituation,
+    };
+    use crate::helpers::identity_providers::IdentityProviders;
+    use crate::helpers::token_verifier::{
+        test_tokens::{mint, verifier},
+        IdTokenClaims, MockTokenVerifier, TokenVerifier,
+    };
+    use crate::repositories::identities_repository::{
+        IdentityDeletion, MockUserIdentityRepository,
+    };
+    use crate::repositories::users_repository::MockUserRepository;
+    use serde_json::json;
+
+    fn providers(verifier: impl TokenVerifier + 'static) -> Arc<IdentityProviders> {
+        let verifier: Arc<dyn TokenVerifier> = Arc::new(verifier);
+        Arc::new(IdentityProviders::new(HashMap::from([(
+            "google".to_owned(),
+            verifier,
+        )])))
+    }
+
+    fn user() -> User {
+        User {
+            email: "test@example.com".to_owned(),
+            user_name: "test".to_owned(),
+            avatar_url: None,
+            timezone: "UTC".to_owned(),
+            locale: "en_US".to_owned(),
+            registered_date: chrono::Utc::now(),
+            updated_date: chrono::Utc::now(),
+        }
+    }
+
+    fn login_request(provider: &str, credential: String) -> LoginRequest {
+        LoginRequest {
+            provider: provider.to_owned(),
+            credential,
+        }
+    }
+
+    fn signup_request(credential: String) -> SignupRequest {
+        SignupRequest {
+            token: login_request("google", credential),
+            user_name: "test".to_owned(),
+        }
+    }
+
+    mod login {
+        use super::*;
+
+        #[actix_web::test]
+        async fn 署名済みトークンでログインできる() {
+            let mut mock_repository = MockUserRepository::new();
+            mock_repository
+                .expect_find_by_identity()
+                .withf(|provider, external_id| provider == "google" && external_id == "external-id")
+                .returning(|_, _| Ok(Some(user())));
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(mock_repository),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+
+            let result = usecase
+                .login(&login_request("google", mint(json!({}))))
+                .await
+                .unwrap();
+
+            assert_eq!(LoginSituation::Succeeded, result.situation);
+        }
+
+        #[actix_web::test]
+        async fn 期限切れのトークンは検証失敗になる() {
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+            let expired = mint(json!({ "exp": chrono::Utc::now().timestamp() - 3600 }));
+
+            let result = usecase
+                .login(&login_request("google", expired))
+                .await
+                .unwrap();
+
+            assert_eq!(LoginSituation::VerificationFailed, result.situation);
+            assert!(result.description.is_some());
+        }
+
+        #[actix_web::test]
+        async fn 改ざんされたトークンは検証失敗になる() {
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+            let mut tampered = mint(json!({}));
+            tampered.push('x');
+
+            let result = usecase
+                .login(&login_request("google", tampered))
+                .await
+                .unwrap();
+
+            assert_eq!(LoginSituation::VerificationFailed, result.situation);
+        }
+
+        #[actix_web::test]
+        async fn 設定されていないプロバイダではログインできない() {
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+
+            let result = usecase
+                .login(&login_request("gitlab", mint(json!({}))))
+                .await
+                .unwrap();
+
+            assert_eq!(LoginSituation::UnknownProvider, result.situation);
+        }
+    }
+
+    mod signup {
+        use super::*;
+
+        #[actix_web::test]
+        async fn 検証に失敗したトークンでは登録しない() {
+            let mut mock_verifier = MockTokenVerifier::new();
+            mock_verifier
+                .expect_verify()
+                .returning(|_| Err(anyhow::anyhow!("Token verification failed.")));
+            let mut mock_repository = MockUserRepository::new();
+            mock_repository.expect_add().never();
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(mock_verifier),
+                Box::new(mock_repository),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+
+            let result = usecase
+                .signup(&signup_request("token".to_owned()))
+                .await
+                .unwrap();
+
+            assert!(matches!(
+                result.situation,
+                SignupSituation::VerificationFailed
+            ));
+        }
+
+        #[actix_web::test]
+        async fn メールアドレスがないトークンでは登録しない() {
+            let mut mock_verifier = MockTokenVerifier::new();
+            mock_verifier.expect_verify().returning(|_| {
+                Ok(IdTokenClaims {
+                    sub: "external-id".to_owned(),
+                    email: None,
+                })
+            });
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(mock_verifier),
+                Box::new(MockUserRepository::new()),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+
+            let result = usecase
+                .signup(&signup_request("token".to_owned()))
+                .await
+                .unwrap();
+
+            assert!(matches!(result.situation, SignupSituation::EmailIsEmpty));
+        }
+
+        #[actix_web::test]
+        async fn 署名済みトークンの主体をidentityとして登録する() {
+            let mut mock_repository = MockUserRepository::new();
+            mock_repository
+                .expect_find_by_identity()
+                .returning(|_, _| Ok(None));
+            mock_repository.expect_find().returning(|_| Ok(None));
+            mock_repository
+                .expect_add()
+                .withf(|user, identity| {
+                    identity.provider == "google"
+                        && identity.external_id == "external-id"
+                        && identity.user_email == user.email
+                })
+                .times(1)
+                .returning(|_, _| Ok(()));
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(mock_repository),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+
+            let result = usecase
+                .signup(&signup_request(mint(json!({}))))
+                .await
+                .unwrap();
+
+            assert!(matches!(result.situation, SignupSituation::Succeeded));
+        }
+
+        #[actix_web::test]
+        async fn 他のプロバイダで登録済みのメールアドレスでは登録しない() {
+            let mut mock_repository = MockUserRepository::new();
+            mock_repository
+                .expect_find_by_identity()
+                .returning(|_, _| Ok(None));
+            mock_repository
+                .expect_find()
+                .returning(|_| Ok(Some(user())));
+            mock_repository.expect_add().never();
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(mock_repository),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+
+            let result = usecase
+                .signup(&signup_request(mint(json!({}))))
+                .await
+                .unwrap();
+
+            assert!(matches!(
+                result.situation,
+                SignupSituation::AlreadyRegistered
+            ));
+        }
+
+        #[actix_web::test]
+        async fn 他のプロバイダで登録済みのユーザ情報は返さない() {
+            let mut mock_repository = MockUserRepository::new();
+            mock_repository
+                .expect_find_by_identity()
+                .returning(|_, _| Ok(None));
+            mock_repository
+                .expect_find()
+                .returning(|_| Ok(Some(user())));
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(mock_repository),
+                Box::new(MockUserIdentityRepository::new()),
+            );
+
+            let result = usecase
+                .signup(&signup_request(mint(json!({}))))
+                .await
+                .unwrap();
+
+            assert_eq!(None, result.login_user);
+        }
+    }
+
+    fn identity(provider: &str, user_email: &str) -> UserIdentity {
+        UserIdentity {
+            provider: provider.to_owned(),
+            external_id: "external-id".to_owned(),
+            user_email: user_email.to_owned(),
+            linked_at: chrono::Utc::now(),
+        }
+    }
+
+    mod link_identity {
+        use super::*;
+
+        #[actix_web::test]
+        async fn 他のユーザのアカウントはリンクしない() {
+            let mut mock_identity_repository = MockUserIdentityRepository::new();
+            mock_identity_repository
+                .expect_find()
+                .returning(|provider, _| Ok(Some(identity(provider, "other@example.com"))));
+            mock_identity_repository.expect_add().never();
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(mock_identity_repository),
+            );
+
+            let result = usecase
+                .link_identity(
+                    "test@example.com",
+                    &login_request("google", mint(json!({}))),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(
+                LinkIdentitySituation::BelongsToAnotherUser,
+                result.situation
+            );
+        }
+
+        #[actix_web::test]
+        async fn 同じプロバイダの別アカウントがリンク済みならリンクしない() {
+            let mut mock_identity_repository = MockUserIdentityRepository::new();
+            mock_identity_repository
+                .expect_find()
+                .returning(|_, _| Ok(None));
+            mock_identity_repository
+                .expect_find_by_user()
+                .returning(|user_email| Ok(vec![identity("google", user_email)]));
+            mock_identity_repository.expect_add().never();
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(mock_identity_repository),
+            );
+
+            let result = usecase
+                .link_identity(
+                    "test@example.com",
+                    &login_request("google", mint(json!({}))),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(
+                LinkIdentitySituation::ProviderAlreadyLinked,
+                result.situation
+            );
+        }
+
+        #[actix_web::test]
+        async fn 新しいアカウントをリンクする() {
+            let mut mock_identity_repository = MockUserIdentityRepository::new();
+            mock_identity_repository
+                .expect_find()
+                .returning(|_, _| Ok(None));
+            mock_identity_repository
+                .expect_find_by_user()
+                .returning(|_| Ok(vec![]));
+            mock_identity_repository
+                .expect_add()
+                .withf(|identity| {
+                    identity.provider == "google"
+                        && identity.external_id == "external-id"
+                        && identity.user_email == "test@example.com"
+                })
+                .times(1)
+                .returning(|_| Ok(true));
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(mock_identity_repository),
+            );
+
+            let result = usecase
+                .link_identity(
+                    "test@example.com",
+                    &login_request("google", mint(json!({}))),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(LinkIdentitySituation::Succeeded, result.situation);
+        }
+
+        #[actix_web::test]
+        async fn 同時に他のユーザがリンクしたアカウントはリンクしない() {
+            let mut mock_identity_repository = MockUserIdentityRepository::new();
+            mock_identity_repository
+                .expect_find()
+                .times(1)
+                .returning(|_, _| Ok(None));
+            mock_identity_repository
+                .expect_find()
+                .times(1)
+                .returning(|provider, _| Ok(Some(identity(provider, "other@example.com"))));
+            mock_identity_repository
+                .expect_find_by_user()
+                .returning(|_| Ok(vec![]));
+            mock_identity_repository
+                .expect_add()
+                .times(1)
+                .returning(|_| Ok(false));
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(mock_identity_repository),
+            );
+
+            let result = usecase
+                .link_identity(
+                    "test@example.com",
+                    &login_request("google", mint(json!({}))),
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(
+                LinkIdentitySituation::BelongsToAnotherUser,
+                result.situation
+            );
+        }
+    }
+
+    mod unlink_identity {
+        use super::*;
+
+        #[actix_web::test]
+        async fn 最後のidentityは外せない() {
+            let mut mock_identity_repository = MockUserIdentityRepository::new();
+            mock_identity_repository
+                .expect_delete()
+                .returning(|_, _| Ok(IdentityDeletion::LastIdentity));
+            mock_identity_repository
+                .expect_find_by_user()
+                .returning(|user_email| Ok(vec![identity("google", user_email)]));
+            let usecase = AuthenticationUsecaseImpl::new(
+                providers(verifier()),
+                Box::new(MockUserRepository::new()),
+                Box::new(mock_identity_repository),
+            );
+
+            let result = usecase
+                .unlink_identity("test@example.com", "google")
+                .await
+                .unwrap();
+
+            assert_eq!(UnlinkIdentitySituation::LastIdentity, result.situation);
+            assert_eq!(1, result.identities.unwrap().len());
+        }
+    }
 }
@@ -0,0 +1,201 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use mockall::automock;
+
+use crate::domain::categories::{is_valid_colour, Category};
+use crate::dto::{CategoryRequest, CategoryResult, CategorySituation};
+use crate::repositories::categories_repository::{CategoryRepository, CategoryWrite};
+
+#[automock]
+#[async_trait]
+pub trait CategoryUsecase {
+    async fn get_categories(&self, owner: &str) -> Result<Vec<Category>>;
+    async fn add_category(&self, owner: &str, request: &CategoryRequest) -> Result<CategoryResult>;
+    async fn update_category(
+        &self,
+        owner: &str,
+        id: i64,
+        request: &CategoryRequest,
+    ) -> Result<CategoryResult>;
+    /// Efforts of the deleted category are kept without a category, and its goals are deleted.
+    async fn delete_category(&self, owner: &str, id: i64) -> Result<CategoryResult>;
+}
+
+pub struct CategoryUsecaseImpl {
+    category_repository: Box<dyn CategoryRepository + Send + Sync>,
+}
+
+impl CategoryUsecaseImpl {
+    pub fn new(category_repository: Box<dyn CategoryRepository + Send + Sync>) -> Self {
+        Self {
+            category_repository,
+        }
+    }
+
+    fn validate(&self, request: &CategoryRequest) -> Option<CategoryResult> {
+        let (situation, description) = if request.name.trim().is_empty() {
+            (CategorySituation::NameIsEmpty, None)
+        } else if !is_valid_colour(&request.colour) {
+            (
+                CategorySituation::InvalidColour,
+                Some(format!(
+                    "`{}` is not a colour like `#40c463`.",
+                    request.colour
+                )),
+            )
+        } else {
+            return None;
+        };
+        Some(CategoryResult {
+            situation,
+            category: None,
+            description,
+        })
+    }
+
+    fn to_category(&self, owner: &str, id: i64, request: &CategoryRequest) -> Category {
+        Category {
+            id,
+            owner: owner.to_owned(),
+            name: request.name.trim().to_owned(),
+            colour: request.colour.to_lowercase(),
+            icon: request
+                .icon
+                .as_deref()
+                .map(str::trim)
+                .filter(|icon| !icon.is_empty())
+                .map(str::to_owned),
+        }
+    }
+
+    fn written(&self, write: CategoryWrite) -> CategoryResult {
+        let (situation, category) = match write {
+            CategoryWrite::Saved(category) => (CategorySituation::Succeeded, Some(category)),
+            CategoryWrite::NotFound => (CategorySituation::NotFound, None),
+            CategoryWrite::NameTaken => (CategorySituation::NameAlreadyUsed, None),
+        };
+        CategoryResult {
+            situation,
+            category,
+            description: None,
+        }
+    }
+}
+
+#[async_trait]
+impl CategoryUsecase for CategoryUsecaseImpl {
+    async fn get_categories(&self, owner: &str) -> Result<Vec<Category>> {
+        self.category_repository.find_all(owner).await
+    }
+
+    async fn add_category(&self, owner: &str, request: &CategoryRequest) -> Result<CategoryResult> {
+        if let Some(invalid) = self.validate(request) {
+            return Ok(invalid);
+        }
+        let write = self
+            .category_repository
+            .add(&self.to_category(owner, 0, request))
+            .await?;
+        Ok(self.written(write))
+    }
+
+    async fn update_category(
+        &self,
+        owner: &str,
+        id: i64,
+        request: &CategoryRequest,
+    ) -> Result<CategoryResult> {
+        if let Some(invalid) = self.validate(request) {
+            return Ok(invalid);
+        }
+        let write = self
+            .category_repository
+            .update(&self.to_category(owner, id, request))
+            .await?;
+        Ok(self.written(write))
+    }
+
+    async fn delete_category(&self, owner: &str, id: i64) -> Result<CategoryResult> {
+        let situation = if self.category_repository.delete(owner, id).await? {
+            CategorySituation::Succeeded
+        } else {
+            CategorySituation::NotFound
+        };
+        Ok(CategoryResult {
+            situation,
+            category: None,
+            description: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{CategoryUsecase, CategoryUsecaseImpl};
+    use crate::domain::categories::Category;
+    use crate::dto::{CategoryRequest, CategorySituation};
+    use crate::repositories::categories_repository::{CategoryWrite, MockCategoryRepository};
+
+    fn request(colour: &str) -> CategoryRequest {
+        CategoryRequest {
+            name: " Study ".to_owned(),
+            colour: colour.to_owned(),
+            icon: Some("📚".to_owned()),
+        }
+    }
+
+    #[actix_web::test]
+    async fn 名前を整えてカテゴリを登録する() {
+        let mut mock_repository = MockCategoryRepository::new();
+        mock_repository
+            .expect_add()
+            .withf(|category| {
+                category.owner == "test@example.com"
+                    && category.name == "Study"
+                    && category.colour == "#40c463"
+            })
+            .returning(|category| {
+                Ok(CategoryWrite::Saved(Category {
+                    id: 1,
+                    ..category.clone()
+                }))
+            });
+        let usecase = CategoryUsecaseImpl::new(Box::new(mock_repository));
+
+        let result = usecase
+            .add_category("test@example.com", &request("#40C463"))
+            .await
+            .unwrap();
+
+        assert_eq!(CategorySituation::Succeeded, result.situation);
+        assert_eq!(1, result.category.unwrap().id);
+    }
+
+    #[actix_web::test]
+    async fn 色の形式が正しくないとき登録しない() {
+        let usecase = CategoryUsecaseImpl::new(Box::new(MockCategoryRepository::new()));
+
+        let result = usecase
+            .add_category("test@example.com", &request("green"))
+            .await
+            .unwrap();
+
+        assert_eq!(CategorySituation::InvalidColour, result.situation);
+    }
+
+    #[actix_web::test]
+    async fn 同じ名前のカテゴリがあるとき更新しない() {
+        let mut mock_repository = MockCategoryRepository::new();
+        mock_repository
+            .expect_update()
+            .returning(|_| Ok(CategoryWrite::NameTaken));
+        let usecase = CategoryUsecaseImpl::new(Box::new(mock_repository));
+
+        let result = usecase
+            .update_category("test@example.com", 1, &request("#40c463"))
+            .await
+            .unwrap();
+
+        assert_eq!(CategorySituation::NameAlreadyUsed, result.situation);
+    }
+}
@@ -0,0 +1,565 @@
+use std::collections::HashSet;
+
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Datelike, Duration};
+use futures::{stream, stream::BoxStream, StreamExt};
+use mockall::automock;
+
+use crate::domain::efforts::{Effort, EffortFilter, ImportedEffort};
+use crate::domain::tags::normalize_tags;
+use crate::dto::{
+    EffortExportQuery, EffortRequest, EffortResult, EffortSituation, ImportReport, ImportRequest,
+    ImportResult, ImportRow, ImportRowStatus, ImportSituation,
+};
+use crate::helpers::effort_export::EffortExportWriter;
+use crate::helpers::effort_import::{preset_mapping, read_efforts};
+use crate::helpers::time_zones::{parse_time_zone, start_of_day};
+use crate::repositories::{
+    categories_repository::CategoryRepository, efforts_repository::EffortRepository,
+};
+
+#[automock]
+#[async_trait]
+pub trait EffortUsecase {
+    async fn get_efforts(&self, owner: &str, filter: &EffortFilter) -> Result<Vec<Effort>>;
+    async fn get_effort(&self, owner: &str, id: i64) -> Result<EffortResult>;
+    async fn add_effort(&self, owner: &str, request: &EffortRequest) -> Result<EffortResult>;
+    async fn update_effort(
+        &self,
+        owner: &str,
+        id: i64,
+        request: &EffortRequest,
+    ) -> Result<EffortResult>;
+    async fn delete_effort(&self, owner: &str, id: i64) -> Result<EffortResult>;
+    /// Imports the efforts of a CSV file, reading local times in `timezone`. Rows imported
+    /// before are skipped. A dry run only reports what would be imported.
+    async fn import_efforts(
+        &self,
+        owner: &str,
+        timezone: &str,
+        request: &ImportRequest,
+    ) -> Result<ImportResult>;
+    /// Streams the efforts in the format of `query`, with times in `timezone`. The days of
+    /// `query` are read in `timezone` too.
+    async fn export_efforts(
+        &self,
+        owner: &str,
+        timezone: &str,
+        query: &EffortExportQuery,
+    ) -> Result<BoxStream<'static, Result<Vec<u8>>>>;
+}
+
+/// The reason `request` can't be stored as an effort, if any.
+fn invalid_situation(request: &EffortRequest) -> Option<EffortSituation> {
+    if request.title.is_empty() {
+        Some(EffortSituation::TitleIsEmpty)
+    } else if request.ended_at < request.started_at {
+        Some(EffortSituation::InvalidPeriod)
+    } else if request.duration_seconds < 0
+        || request.duration_seconds > (request.ended_at - request.started_at).num_seconds()
+    {
+        Some(EffortSituation::InvalidDuration)
+    } else if request.tags.iter().any(|tag| tag.trim().is_empty()) {
+        Some(EffortSituation::TagIsEmpty)
+    } else {
+        None
+    }
+}
+
+fn invalid_import(situation: ImportSituation, description: String) -> ImportResult {
+    ImportResult {
+        situation,
+        report: None,
+        description: Some(description),
+        stored: vec![],
+    }
+}
+
+pub struct EffortUsecaseImpl {
+    effort_repository: Box<dyn EffortRepository + Send + Sync>,
+    category_repository: Box<dyn CategoryRepository + Send + Sync>,
+}
+
+impl EffortUsecaseImpl {
+    pub fn new(
+        effort_repository: Box<dyn EffortRepository + Send + Sync>,
+        category_repository: Box<dyn CategoryRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            effort_repository,
+            category_repository,
+        }
+    }
+
+    async fn validate(&self, owner: &str, request: &EffortRequest) -> Result<Option<EffortResult>> {
+        let mut situation = invalid_situation(request);
+        if let (None, Some(category_id)) = (&situation, request.category_id) {
+            if self
+                .category_repository
+                .find(owner, category_id)
+                .await?
+                .is_none()
+            {
+                situation = Some(EffortSituation::CategoryNotFound);
+            }
+        }
+        Ok(situation.map(|situation| EffortResult {
+            situation,
+            effort: None,
+            description: None,
+        }))
+    }
+
+    fn to_effort(&self, owner: &str, id: i64, request: &EffortRequest) -> Effort {
+        Effort {
+            id,
+            owner: owner.to_owned(),
+            title: request.title.to_owned(),
+            duration_seconds: request.duration_seconds,
+            started_at: request.started_at,
+            ended_at: request.ended_at,
+            notes: request.notes.to_owned(),
+            category_id: request.category_id,
+            tags: normalize_tags(&request.tags),
+            pomodoro: false,
+        }
+    }
+
+    fn found(&self, effort: Option<Effort>) -> EffortResult {
+        match effort {
+            Some(effort) => EffortResult {
+                situation: EffortSituation::Succeeded,
+                effort: Some(effort),
+                description: None,
+            },
+            None => EffortResult {
+                situation: EffortSituation::NotFound,
+                effort: None,
+                description: None,
+            },
+        }
+    }
+}
+
+#[async_trait]
+impl EffortUsecase for EffortUsecaseImpl {
+    async fn get_efforts(&self, owner: &str, filter: &EffortFilter) -> Result<Vec<Effort>> {
+        self.effort_repository.find_all(owner, filter).await
+    }
+
+    async fn get_effort(&self, owner: &str, id: i64) -> Result<EffortResult> {
+        let effort = self.effort_repository.find(owner, id).await?;
+        Ok(self.found(effort))
+    }
+
+    async fn add_effort(&self, owner: &str, request: &EffortRequest) -> Result<EffortResult> {
+        if let Some(invalid) = self.validate(owner, request).await? {
+            return Ok(invalid);
+        }
+        let effort = self
+            .effort_repository
+            .add(&self.to_effort(owner, 0, request))
+            .await?;
+        Ok(self.found(Some(effort)))
+    }
+
+    async fn update_effort(
+        &self,
+        owner: &str,
+        id: i64,
+        request: &EffortRequest,
+    ) -> Result<EffortResult> {
+        if let Some(invalid) = self.validate(owner, request).await? {
+            return Ok(invalid);
+        }
+        let effort = self
+            .effort_repository
+            .update(&self.to_effort(owner, id, request))
+            .await?;
+        Ok(self.found(effort))
+    }
+
+    async fn delete_effort(&self, owner: &str, id: i64) -> Result<EffortResult> {
+        let situation = if self.effort_repository.delete(owner, id).await? {
+            EffortSituation::Succeeded
+        } else {
+            EffortSituation::NotFound
+        };
+        Ok(EffortResult {
+            situation,
+            effort: None,
+            description: None,
+        })
+    }
+
+    async fn import_efforts(
+        &self,
+        owner: &str,
+        timezone: &str,
+        request: &ImportRequest,
+    ) -> Result<ImportResult> {
+        let mapping = match (request.preset, &request.mapping) {
+            (Some(preset), _) => preset_mapping(preset),
+            (None, Some(mapping)) => mapping.clone(),
+            (None, None) => {
+                return Ok(invalid_import(
+                    ImportSituation::InvalidMapping,
+                    "Give either a preset or a column mapping.".to_owned(),
+                ))
+            }
+        };
+        let timezone = parse_time_zone(timezone);
+        let parsed = match read_efforts(&request.data, &mapping, timezone) {
+            Ok(parsed) => parsed,
+            Err(description) => {
+                return Ok(invalid_import(ImportSituation::InvalidFile, description))
+            }
+        };
+
+        let mut rows = Vec::new();
+        let mut candidates = Vec::new();
+        for row in parsed {
+            let effort = row
+                .effort
+                .and_then(|effort| match invalid_situation(&effort) {
+                    Some(EffortSituation::TitleIsEmpty) => {
+                        Err(vec!["The title is empty.".to_owned()])
+                    }
+                    Some(EffortSituation::InvalidPeriod) => {
+                        Err(vec!["The effort ends before it starts.".to_owned()])
+                    }
+                    Some(EffortSituation::TagIsEmpty) => Err(vec!["A tag is empty.".to_owned()]),
+                    Some(_) => Err(vec![
+                        "The duration is longer than the effort lasts.".to_owned()
+                    ]),
+                    None => Ok(effort),
+                });
+            match effort {
+                Ok(effort) => {
+                    candidates.push(ImportedEffort {
+                        import_hash: row.import_hash,
+                        effort: self.to_effort(owner, 0, &effort),
+                    });
+                    rows.push(ImportRow {
+                        line: row.line,
+                        status: ImportRowStatus::New,
+                        effort: Some(effort),
+                        errors: vec![],
+                    });
+                }
+                Err(errors) => rows.push(ImportRow {
+                    line: row.line,
+                    status: ImportRowStatus::Invalid,
+                    effort: None,
+                    errors,
+                }),
+            }
+        }
+
+        let hashes: Vec<String> = candidates.iter().map(|c| c.import_hash.clone()).collect();
+        let mut seen: HashSet<String> = self
+            .effort_repository
+            .find_imported(owner, &hashes)
+            .await?
+            .into_iter()
+            .collect();
+        let mut new_efforts = Vec::new();
+        let valid_rows = rows
+            .iter_mut()
+            .filter(|row| row.status == ImportRowStatus::New);
+        for (row, candidate) in valid_rows.zip(candidates) {
+            if seen.insert(candidate.import_hash.clone()) {
+                new_efforts.push(candidate);
+            } else {
+                row.status = ImportRowStatus::Duplicate;
+            }
+        }
+
+        let (imported, stored) = if request.dry_run || new_efforts.is_empty() {
+            (new_efforts.len(), vec![])
+        } else {
+            let stored = self.effort_repository.add_imported(&new_efforts).await?;
+            (stored.len(), stored)
+        };
+        let count = |status| rows.iter().filter(|row| row.status == status).count();
+        Ok(ImportResult {
+            situation: ImportSituation::Succeeded,
+            report: Some(ImportReport {
+                dry_run: request.dry_run,
+                imported,
+                duplicates: count(ImportRowStatus::Duplicate),
+                invalid: count(ImportRowStatus::Invalid),
+                rows,
+            }),
+            description: None,
+            stored,
+        })
+    }
+
+    async fn export_efforts(
+        &self,
+        owner: &str,
+        timezone: &str,
+        query: &EffortExportQuery,
+    ) -> Result<BoxStream<'static, Result<Vec<u8>>>> {
+        let timezone = parse_time_zone(timezone);
+        let from = query.from.map(|from| start_of_day(from, timezone));
+        let to = query
+            .to
+            .map(|to| start_of_day(to + Duration::days(1), timezone));
+        let efforts = self.effort_repository.stream_all(owner, from, to).await?;
+
+        let writer = EffortExportWriter::new(query.format, timezone);
+        let header = writer.header(
+            query.from.map(|from| from.year()),
+            query.to.map(|to| to.year()),
+        );
+        let footer = writer.footer();
+        let body = efforts.map(move |effort| writer.effort(&effort?));
+        Ok(stream::once(async { header })
+            .chain(body)
+            .chain(stream::once(async { Ok(footer) }))
+            .boxed())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    mod add_effort {
+        use chrono::{TimeZone, Utc};
+
+        use crate::dto::{EffortRequest, EffortSituation};
+        use crate::repositories::categories_repository::MockCategoryRepository;
+        use crate::repositories::efforts_repository::MockEffortRepository;
+        use crate::usecases::effort_usecase::{EffortUsecase, EffortUsecaseImpl};
+
+        fn request(tags: &[&str]) -> EffortRequest {
+            EffortRequest {
+                title: "Rust book".to_owned(),
+                duration_seconds: 3600,
+                started_at: Utc.with_ymd_and_hms(2023, 5, 1, 9, 0, 0).unwrap(),
+                ended_at: Utc.with_ymd_and_hms(2023, 5, 1, 10, 0, 0).unwrap(),
+                notes: None,
+                category_id: Some(1),
+                tags: tags.iter().map(|tag| tag.to_string()).collect(),
+            }
+        }
+
+        #[actix_web::test]
+        async fn 他人のカテゴリを指定したとき登録しない() {
+            let mut mock_category_repository = MockCategoryRepository::new();
+            mock_category_repository
+                .expect_find()
+                .withf(|owner, id| owner == "test@example.com" && *id == 1)
+                .returning(|_, _| Ok(None));
+            let usecase = EffortUsecaseImpl::new(
+                Box::new(MockEffortRepository::new()),
+                Box::new(mock_category_repository),
+            );
+
+            let result = usecase
+                .add_effort("test@example.com", &request(&[]))
+                .await
+                .unwrap();
+
+            assert_eq!(EffortSituation::CategoryNotFound, result.situation);
+        }
+
+        #[actix_web::test]
+        async fn 空白のタグを指定したとき登録しない() {
+            let usecase = EffortUsecaseImpl::new(
+                Box::new(MockEffortRepository::new()),
+                Box::new(MockCategoryRepository::new()),
+            );
+
+            let result = usecase
+                .add_effort("test@example.com", &request(&["rust", " "]))
+                .await
+                .unwrap();
+
+            assert_eq!(EffortSituation::TagIsEmpty, result.situation);
+        }
+    }
+    mod import_efforts {
+        use crate::dto::{ImportPreset, ImportRequest, ImportRowStatus, ImportSituation};
+        use crate::repositories::categories_repository::MockCategoryRepository;
+        use crate::repositories::efforts_repository::MockEffortRepository;
+        use crate::usecases::effort_usecase::{EffortUsecase, EffortUsecaseImpl};
+
+        const TOGGL: &str =
+            "Project,Description,Start date,Start time,End date,End time,Duration,Tags
+Study,Rust book,2023-05-01,09:00:00,2023-05-01,10:30:00,01:30:00,rust
+Study,Rust book,2023-05-01,09:00:00,2023-05-01,10:30:00,01:30:00,rust
+Study,Old entry,2023-04-01,09:00:00,2023-04-01,10:00:00,01:00:00,
+Study,Too long,2023-05-02,09:00:00,2023-05-02,10:00:00,02:00:00,
+";
+
+        fn request(dry_run: bool) -> ImportRequest {
+            ImportRequest {
+                data: TOGGL.as_bytes().to_vec(),
+                preset: Some(ImportPreset::Toggl),
+                mapping: None,
+                dry_run,
+            }
+        }
+
+        fn mock_repository() -> MockEffortRepository {
+            let mut mock_repository = MockEffortRepository::new();
+            mock_repository
+                .expect_find_imported()
+                .returning(|_, hashes| Ok(vec![hashes[2].clone()]));
+            mock_repository
+        }
+
+        #[actix_web::test]
+        async fn 取込済みやファイル内で重複した行を除いて取り込む() {
+            let mut mock_repository = mock_repository();
+            mock_repository
+                .expect_add_imported()
+                .withf(|efforts| {
+                    efforts.len() == 1
+                        && efforts[0].effort.owner == "test@example.com"
+                        && efforts[0].effort.title == "Rust book"
+                        && efforts[0].effort.tags == vec!["rust".to_owned()]
+                        && efforts[0].import_hash.len() == 64
+                })
+                .times(1)
+                .returning(|efforts| {
+                    Ok(efforts
+                        .iter()
+                        .map(|imported| imported.effort.clone())
+                        .collect())
+                });
+            let usecase = EffortUsecaseImpl::new(
+                Box::new(mock_repository),
+                Box::new(MockCategoryRepository::new()),
+            );
+
+            let result = usecase
+                .import_efforts("test@example.com", "UTC", &request(false))
+                .await
+                .unwrap();
+
+            assert_eq!(ImportSituation::Succeeded, result.situation);
+            let report = result.report.unwrap();
+            assert_eq!(
+                (1, 2, 1),
+                (report.imported, report.duplicates, report.invalid)
+            );
+            let statuses: Vec<ImportRowStatus> = report.rows.iter().map(|row| row.status).collect();
+            assert_eq!(
+                vec![
+                    ImportRowStatus::New,
+                    ImportRowStatus::Duplicate,
+                    ImportRowStatus::Duplicate,
+                    ImportRowStatus::Invalid
+                ],
+                statuses
+            );
+            assert_eq!(5, report.rows[3].line);
+        }
+
+        #[actix_web::test]
+        async fn ドライランでは保存しない() {
+            let usecase = EffortUsecaseImpl::new(
+                Box::new(mock_repository()),
+                Box::new(MockCategoryRepository::new()),
+            );
+
+            let result = usecase
+                .import_efforts("test@example.com", "UTC", &request(true))
+                .await
+                .unwrap();
+
+            let report = result.report.unwrap();
+            assert!(report.dry_run);
+            assert_eq!(1, report.imported);
+        }
+
+        #[actix_web::test]
+        async fn プリセットも列の対応もないとき取り込まない() {
+            let usecase = EffortUsecaseImpl::new(
+                Box::new(MockEffortRepository::new()),
+                Box::new(MockCategoryRepository::new()),
+            );
+
+            let result = usecase
+                .import_efforts(
+                    "test@example.com",
+                    "UTC",
+                    &ImportRequest {
+                        preset: None,
+                        ..request(true)
+                    },
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(ImportSituation::InvalidMapping, result.situation);
+        }
+    }
+    mod export_efforts {
+        use chrono::{NaiveDate, TimeZone, Utc};
+        use futures::{stream, StreamExt, TryStreamExt};
+
+        use crate::domain::efforts::Effort;
+        use crate::dto::{EffortExportFormat, EffortExportQuery};
+        use crate::repositories::categories_repository::MockCategoryRepository;
+        use crate::repositories::efforts_repository::MockEffortRepository;
+        use crate::usecases::effort_usecase::{EffortUsecase, EffortUsecaseImpl};
+
+        #[actix_web::test]
+        async fn 期間をユーザのタイムゾーンの日付で絞り込んで書き出す() {
+            let mut mock_repository = MockEffortRepository::new();
+            mock_repository
+                .expect_stream_all()
+                .withf(|owner, from, to| {
+                    owner == "test@example.com"
+                        && *from == Some(Utc.with_ymd_and_hms(2023, 4, 30, 15, 0, 0).unwrap())
+                        && *to == Some(Utc.with_ymd_and_hms(2023, 5, 1, 15, 0, 0).unwrap())
+                })
+                .returning(|owner, _, _| {
+                    Ok(stream::iter(vec![Ok(Effort {
+                        id: 1,
+                        owner: owner.to_owned(),
+                        title: "Rust book".to_owned(),
+                        duration_seconds: 1800,
+                        started_at: Utc.with_ymd_and_hms(2023, 5, 1, 0, 0, 0).unwrap(),
+                        ended_at: Utc.with_ymd_and_hms(2023, 5, 1, 0, 30, 0).unwrap(),
+                        notes: None,
+                        category_id: None,
+                        tags: vec![],
+                        pomodoro: false,
+                    })])
+                    .boxed())
+                });
+            let usecase = EffortUsecaseImpl::new(
+                Box::new(mock_repository),
+                Box::new(MockCategoryRepository::new()),
+            );
+
+            let chunks: Vec<Vec<u8>> = usecase
+                .export_efforts(
+                    "test@example.com",
+                    "Asia/Tokyo",
+                    &EffortExportQuery {
+                        format: EffortExportFormat::Csv,
+                        from: NaiveDate::from_ymd_opt(2023, 5, 1),
+                        to: NaiveDate::from_ymd_opt(2023, 5, 1),
+                    },
+                )
+                .await
+                .unwrap()
+                .try_collect()
+                .await
+                .unwrap();
+
+            assert_eq!(
+                "id,title,started_at,ended_at,duration_seconds,notes,category_id,tags\n\
+                1,Rust book,2023-05-01T09:00:00+09:00,2023-05-01T09:30:00+09:00,1800,,,\n",
+                String::from_utf8(chunks.concat()).unwrap()
+            );
+        }
+    }
+}
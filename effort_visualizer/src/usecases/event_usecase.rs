@@ -0,0 +1,308 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, NaiveDate, Utc};
+use mockall::automock;
+
+use crate::domain::efforts::{Effort, EffortFilter};
+use crate::domain::events::{EventKind, MissedEvents, UserEvent};
+use crate::dto::{GoalProgress, TimerState};
+use crate::helpers::time_zones::{parse_time_zone, start_of_day};
+use crate::repositories::{
+    efforts_repository::EffortRepository, events_repository::EventRepository,
+    goals_repository::GoalRepository,
+};
+
+#[automock]
+#[async_trait]
+pub trait EventUsecase {
+    /// Pushes `effort.created` for each of `efforts`, and `goal.completed` for each goal they
+    /// complete a period of for the first time. Periods are counted in `timezone`.
+    async fn efforts_created(&self, owner: &str, timezone: &str, efforts: &[Effort]) -> Result<()>;
+    /// Pushes `goal.completed` for each goal the updated `effort` completes a period of for the
+    /// first time. Periods are counted in `timezone`.
+    async fn effort_updated(&self, owner: &str, timezone: &str, effort: &Effort) -> Result<()>;
+    /// Pushes `timer.tick` with the state of the owner's timer, or without one once it is
+    /// stopped.
+    async fn timer_changed(&self, owner: &str, timer: Option<TimerState>) -> Result<()>;
+    /// Pushes `session.ended`, so that connections opened with a session that ended close.
+    async fn session_ended(&self, owner: &str) -> Result<()>;
+    /// The events of the owner after the one with the id `after`, for a client to resume from.
+    async fn get_events_after(&self, owner: &str, after: i64) -> Result<MissedEvents>;
+}
+
+pub struct EventUsecaseImpl {
+    event_repository: Box<dyn EventRepository + Send + Sync>,
+    goal_repository: Box<dyn GoalRepository + Send + Sync>,
+    effort_repository: Box<dyn EffortRepository + Send + Sync>,
+}
+
+impl EventUsecaseImpl {
+    pub fn new(
+        event_repository: Box<dyn EventRepository + Send + Sync>,
+        goal_repository: Box<dyn GoalRepository + Send + Sync>,
+        effort_repository: Box<dyn EffortRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            event_repository,
+            goal_repository,
+            effort_repository,
+        }
+    }
+
+    async fn completed_goals(
+        &self,
+        owner: &str,
+        timezone: &str,
+        efforts: &[Effort],
+    ) -> Result<Vec<UserEvent>> {
+        let timezone = parse_time_zone(timezone);
+        let mut events = Vec::new();
+        for goal in self.goal_repository.find_all(owner).await? {
+            let mut periods: Vec<(NaiveDate, NaiveDate)> = efforts
+                .iter()
+                .filter(|effort| {
+                    goal.category_id.is_none() || goal.category_id == effort.category_id
+                })
+                .map(|effort| {
+                    goal.period_of(effort.started_at.with_timezone(&timezone).date_naive())
+                })
+                .filter(|(_, last_day)| *last_day >= goal.starts_on)
+                .map(|(first_day, last_day)| (first_day.max(goal.starts_on), last_day))
+                .collect();
+            periods.sort();
+            periods.dedup();
+            if periods.is_empty() {
+                continue;
+            }
+            let instants: Vec<(DateTime<Utc>, DateTime<Utc>)> = periods
+                .iter()
+                .map(|(first_day, last_day)| {
+                    (
+                        start_of_day(*first_day, timezone),
+                        start_of_day(*last_day + Duration::days(1), timezone),
+                    )
+                })
+                .collect();
+            let filter = EffortFilter {
+                category_ids: goal.category_id.into_iter().collect(),
+                ..EffortFilter::default()
+            };
+            let totals = self
+                .effort_repository
+                .totals(owner, &instants, timezone.name(), &filter)
+                .await?;
+            for ((first_day, last_day), totals) in periods.iter().zip(&totals) {
+                let progress = goal.progress(*first_day, *last_day, totals);
+                if progress.completed
+                    && self
+                        .goal_repository
+                        .mark_completed(goal.id, *first_day)
+                        .await?
+                {
+                    events.push(UserEvent::new(
+                        owner,
+                        EventKind::GoalCompleted,
+                        &GoalProgress {
+                            goal: goal.clone(),
+                            periods: vec![progress],
+                        },
+                    )?);
+                }
+            }
+        }
+        Ok(events)
+    }
+}
+
+#[async_trait]
+impl EventUsecase for EventUsecaseImpl {
+    async fn efforts_created(&self, owner: &str, timezone: &str, efforts: &[Effort]) -> Result<()> {
+        if efforts.is_empty() {
+            return Ok(());
+        }
+        let mut events = efforts
+            .iter()
+            .map(|effort| UserEvent::new(owner, EventKind::EffortCreated, effort))
+            .collect::<Result<Vec<_>, _>>()?;
+        events.extend(self.completed_goals(owner, timezone, efforts).await?);
+        self.event_repository.publish(&events).await
+    }
+
+    async fn effort_updated(&self, owner: &str, timezone: &str, effort: &Effort) -> Result<()> {
+        let events = self
+            .completed_goals(owner, timezone, std::slice::from_ref(effort))
+            .await?;
+        self.event_repository.publish(&events).await
+    }
+
+    async fn timer_changed(&self, owner: &str, timer: Option<TimerState>) -> Result<()> {
+        let event = UserEvent::new(owner, EventKind::TimerTick, &timer)?;
+        self.event_repository.publish(&[event]).await
+    }
+
+    async fn session_ended(&self, owner: &str) -> Result<()> {
+        let event = UserEvent::new(owner, EventKind::SessionEnded, &())?;
+        self.event_repository.notify(&[event]).await
+    }
+
+    async fn get_events_after(&self, owner: &str, after: i64) -> Result<MissedEvents> {
+        // Read before the oldest id, so that events dropped in between are not missed.
+        let logged = self.event_repository.find_after(owner, after).await?;
+        let oldest_id = self.event_repository.find_oldest_id(owner).await?;
+        match (oldest_id, logged.last().and_then(|event| event.id)) {
+            (Some(oldest_id), Some(last_id)) if after < oldest_id => {
+                Ok(MissedEvents::Dropped { last_id })
+            }
+            _ => Ok(MissedEvents::Logged(logged)),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{DateTime, Duration, NaiveDate, TimeZone, Utc};
+
+    use super::{EventUsecase, EventUsecaseImpl};
+    use crate::domain::efforts::Effort;
+    use crate::domain::events::{EventKind, MissedEvents, UserEvent};
+    use crate::domain::goals::{Goal, GoalMetric, GoalPeriod, PeriodTotals};
+    use crate::repositories::{
+        efforts_repository::MockEffortRepository, events_repository::MockEventRepository,
+        goals_repository::MockGoalRepository,
+    };
+
+    fn effort(started_at: DateTime<Utc>) -> Effort {
+        Effort {
+            id: 1,
+            owner: "test@example.com".to_owned(),
+            title: "Rust".to_owned(),
+            duration_seconds: 3600,
+            started_at,
+            ended_at: started_at + Duration::hours(1),
+            notes: None,
+            category_id: None,
+            tags: vec![],
+            pomodoro: false,
+        }
+    }
+
+    #[actix_web::test]
+    async fn 目標を達成した期間を一度だけ知らせる() {
+        let mut goal_repository = MockGoalRepository::new();
+        goal_repository.expect_find_all().returning(|owner| {
+            Ok(vec![Goal {
+                id: 1,
+                owner: owner.to_owned(),
+                title: "Rust".to_owned(),
+                metric: GoalMetric::Duration,
+                target: 3600,
+                period: GoalPeriod::Daily,
+                period_days: None,
+                starts_on: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+                category_id: None,
+            }])
+        });
+        // 2023-05-01 23:30 in Tokyo counts for the 1st, which is completed already.
+        goal_repository
+            .expect_mark_completed()
+            .withf(|id, first_day| {
+                *id == 1 && *first_day == NaiveDate::from_ymd_opt(2023, 5, 1).unwrap()
+            })
+            .returning(|_, _| Ok(false));
+        goal_repository
+            .expect_mark_completed()
+            .withf(|id, first_day| {
+                *id == 1 && *first_day == NaiveDate::from_ymd_opt(2023, 5, 2).unwrap()
+            })
+            .returning(|_, _| Ok(true));
+        let mut effort_repository = MockEffortRepository::new();
+        effort_repository
+            .expect_totals()
+            .withf(|_, periods, timezone, _| periods.len() == 2 && timezone == "Asia/Tokyo")
+            .returning(|_, periods, _, _| {
+                Ok(periods
+                    .iter()
+                    .map(|_| PeriodTotals {
+                        total_seconds: 3600,
+                        effort_count: 1,
+                        distinct_days: 1,
+                    })
+                    .collect())
+            });
+        let mut event_repository = MockEventRepository::new();
+        event_repository
+            .expect_publish()
+            .withf(|events| {
+                events.iter().map(|event| event.kind).collect::<Vec<_>>()
+                    == vec![
+                        EventKind::EffortCreated,
+                        EventKind::EffortCreated,
+                        EventKind::GoalCompleted,
+                    ]
+                    && events[2].data["periods"][0]["first_day"] == "2023-05-02"
+            })
+            .returning(|_| Ok(()));
+        let usecase = EventUsecaseImpl::new(
+            Box::new(event_repository),
+            Box::new(goal_repository),
+            Box::new(effort_repository),
+        );
+
+        usecase
+            .efforts_created(
+                "test@example.com",
+                "Asia/Tokyo",
+                &[
+                    effort(Utc.with_ymd_and_hms(2023, 5, 1, 14, 30, 0).unwrap()),
+                    effort(Utc.with_ymd_and_hms(2023, 5, 1, 15, 30, 0).unwrap()),
+                ],
+            )
+            .await
+            .unwrap();
+    }
+
+    fn logged(id: i64) -> UserEvent {
+        UserEvent {
+            id: Some(id),
+            owner: "test@example.com".to_owned(),
+            kind: EventKind::EffortCreated,
+            data: serde_json::Value::Null,
+        }
+    }
+
+    #[actix_web::test]
+    async fn 再開位置が記録より古ければ取り直させる() {
+        let mut event_repository = MockEventRepository::new();
+        event_repository
+            .expect_find_after()
+            .withf(|_, after| *after == 41)
+            .returning(|_, _| Ok(vec![logged(50), logged(60)]));
+        event_repository
+            .expect_find_after()
+            .withf(|_, after| *after == 50)
+            .returning(|_, _| Ok(vec![logged(60)]));
+        event_repository
+            .expect_find_oldest_id()
+            .returning(|_| Ok(Some(50)));
+        let usecase = EventUsecaseImpl::new(
+            Box::new(event_repository),
+            Box::new(MockGoalRepository::new()),
+            Box::new(MockEffortRepository::new()),
+        );
+
+        assert_eq!(
+            MissedEvents::Dropped { last_id: 60 },
+            usecase
+                .get_events_after("test@example.com", 41)
+                .await
+                .unwrap()
+        );
+        assert_eq!(
+            MissedEvents::Logged(vec![logged(60)]),
+            usecase
+                .get_events_after("test@example.com", 50)
+                .await
+                .unwrap()
+        );
+    }
+}
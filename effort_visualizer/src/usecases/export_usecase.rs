@@ -0,0 +1,406 @@
+use anyhow::{Context, Result};
+use async_trait::async_trait;
+use async_zip::{tokio::write::ZipFileWriter, Compression, ZipDateTime, ZipEntryBuilder};
+use chrono::{DateTime, Utc};
+use futures::{stream, AsyncWriteExt, Stream, StreamExt};
+use mockall::automock;
+use serde::Serialize;
+use tokio::io::AsyncWrite;
+
+use crate::repositories::{
+    categories_repository::CategoryRepository, efforts_repository::EffortRepository,
+    events_repository::EventRepository, goals_repository::GoalRepository,
+    identities_repository::UserIdentityRepository, pomodoros_repository::PomodoroRepository,
+    sessions_repository::SessionRepository, tags_repository::TagRepository,
+    timers_repository::TimerRepository, users_repository::UserRepository,
+};
+
+/// Version of the archive layout. Bump it whenever a file is added, removed or changes shape.
+pub const EXPORT_SCHEMA_VERSION: u32 = 7;
+
+pub type ExportWriter = Box<dyn AsyncWrite + Send + Unpin>;
+
+#[derive(Serialize)]
+struct Manifest<'a> {
+    schema_version: u32,
+    exported_at: DateTime<Utc>,
+    user_email: &'a str,
+    files: &'a [ManifestFile],
+}
+
+#[derive(Serialize)]
+struct ManifestFile {
+    name: &'static str,
+    records: usize,
+}
+
+#[automock]
+#[async_trait]
+pub trait ExportUsecase {
+    /// Writes a ZIP archive of everything stored about the user to `writer`. Each file is
+    /// compressed as it is written, so the archive is never held in memory as a whole.
+    async fn export(&self, user_email: &str, writer: ExportWriter) -> Result<()>;
+}
+
+pub struct ExportUsecaseImpl {
+    user_repository: Box<dyn UserRepository + Send + Sync>,
+    identity_repository: Box<dyn UserIdentityRepository + Send + Sync>,
+    category_repository: Box<dyn CategoryRepository + Send + Sync>,
+    tag_repository: Box<dyn TagRepository + Send + Sync>,
+    effort_repository: Box<dyn EffortRepository + Send + Sync>,
+    goal_repository: Box<dyn GoalRepository + Send + Sync>,
+    timer_repository: Box<dyn TimerRepository + Send + Sync>,
+    pomodoro_repository: Box<dyn PomodoroRepository + Send + Sync>,
+    session_repository: Box<dyn SessionRepository + Send + Sync>,
+    event_repository: Box<dyn EventRepository + Send + Sync>,
+}
+
+impl ExportUsecaseImpl {
+    #[allow(clippy::too_many_arguments)]
+    pub fn new(
+        user_repository: Box<dyn UserRepository + Send + Sync>,
+        identity_repository: Box<dyn UserIdentityRepository + Send + Sync>,
+        category_repository: Box<dyn CategoryRepository + Send + Sync>,
+        tag_repository: Box<dyn TagRepository + Send + Sync>,
+        effort_repository: Box<dyn EffortRepository + Send + Sync>,
+        goal_repository: Box<dyn GoalRepository + Send + Sync>,
+        timer_repository: Box<dyn TimerRepository + Send + Sync>,
+        pomodoro_repository: Box<dyn PomodoroRepository + Send + Sync>,
+        session_repository: Box<dyn SessionRepository + Send + Sync>,
+        event_repository: Box<dyn EventRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            user_repository,
+            identity_repository,
+            category_repository,
+            tag_repository,
+            effort_repository,
+            goal_repository,
+            timer_repository,
+            pomodoro_repository,
+            session_repository,
+            event_repository,
+        }
+    }
+}
+
+struct Archive {
+    zip: ZipFileWriter<ExportWriter>,
+    modified: ZipDateTime,
+    files: Vec<ManifestFile>,
+}
+
+impl Archive {
+    fn new(writer: ExportWriter, exported_at: &DateTime<Utc>) -> Self {
+        Self {
+            zip: ZipFileWriter::with_tokio(writer),
+            modified: ZipDateTime::from_chrono(exported_at),
+            files: Vec::new(),
+        }
+    }
+
+    fn entry(&self, name: &'static str) -> ZipEntryBuilder {
+        ZipEntryBuilder::new(name.into(), Compression::Deflate)
+            .last_modification_date(self.modified)
+    }
+
+    async fn write_object<T: Serialize>(&mut self, name: &'static str, value: &T) -> Result<()> {
+        let data = serde_json::to_vec_pretty(value)?;
+        self.zip.write_entry_whole(self.entry(name), &data).await?;
+        self.files.push(ManifestFile { name, records: 1 });
+        Ok(())
+    }
+
+    /// Writes `records` as a JSON array with one record per line.
+    async fn write_records<T: Serialize + Sync>(
+        &mut self,
+        name: &'static str,
+        records: &[T],
+    ) -> Result<()> {
+        self.write_record_stream(name, stream::iter(records.iter().map(Ok)))
+            .await
+    }
+
+    /// Writes `records` as a JSON array with one record per line, each as soon as it comes.
+    async fn write_record_stream<T: Serialize>(
+        &mut self,
+        name: &'static str,
+        mut records: impl Stream<Item = Result<T>> + Send + Unpin,
+    ) -> Result<()> {
+        let entry = self.entry(name);
+        let mut writer = self.zip.write_entry_stream(entry).await?;
+        writer.write_all(b"[").await?;
+        let mut count = 0;
+        while let Some(record) = records.next().await {
+            writer
+                .write_all(if count == 0 { b"\n" } else { b",\n" })
+                .await?;
+            writer.write_all(&serde_json::to_vec(&record?)?).await?;
+            count += 1;
+        }
+        writer.write_all(b"\n]\n").await?;
+        writer.close().await?;
+        self.files.push(ManifestFile {
+            name,
+            records: count,
+        });
+        Ok(())
+    }
+
+    async fn finish(mut self, user_email: &str, exported_at: DateTime<Utc>) -> Result<()> {
+        let manifest = serde_json::to_vec_pretty(&Manifest {
+            schema_version: EXPORT_SCHEMA_VERSION,
+            exported_at,
+            user_email,
+            files: &self.files,
+        })?;
+        self.zip
+            .write_entry_whole(self.entry("manifest.json"), &manifest)
+            .await?;
+        self.zip.close().await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ExportUsecase for ExportUsecaseImpl {
+    async fn export(&self, user_email: &str, writer: ExportWriter) -> Result<()> {
+        let user = self
+            .user_repository
+            .find(user_email)
+            .await?
+            .context("The user is not registered.")?;
+        let exported_at = Utc::now();
+        let mut archive = Archive::new(writer, &exported_at);
+
+        archive.write_object("user.json", &user).await?;
+        let identities = self.identity_repository.find_by_user(user_email).await?;
+        archive
+            .write_records("identities.json", &identities)
+            .await?;
+        let categories = self.category_repository.find_all(user_email).await?;
+        archive
+            .write_records("categories.json", &categories)
+            .await?;
+        let tags = self.tag_repository.find_all(user_email).await?;
+        archive.write_records("tags.json", &tags).await?;
+        let efforts = self
+            .effort_repository
+            .stream_all(user_email, None, None)
+            .await?;
+        archive.write_record_stream("efforts.json", efforts).await?;
+        let goals = self.goal_repository.find_all(user_email).await?;
+        archive.write_records("goals.json", &goals).await?;
+        let completions = self.goal_repository.find_completions(user_email).await?;
+        archive
+            .write_records("goal_completions.json", &completions)
+            .await?;
+        let timer = self.timer_repository.find(user_email).await?;
+        archive
+            .write_records("timers.json", timer.as_slice())
+            .await?;
+        let interruptions = self
+            .pomodoro_repository
+            .find_interruptions(user_email)
+            .await?;
+        archive
+            .write_records("pomodoro_interruptions.json", &interruptions)
+            .await?;
+        // Sessions kept in cookies are never stored, so they leave no history here.
+        let sessions = self.session_repository.find_history(user_email).await?;
+        archive.write_records("sessions.json", &sessions).await?;
+        // Only the latest events are logged, so older ones are not exported.
+        let events = self.event_repository.find_after(user_email, 0).await?;
+        archive.write_records("events.json", &events).await?;
+
+        archive.finish(user_email, exported_at).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use async_zip::base::read::mem::ZipFileReader;
+    use chrono::{NaiveDate, TimeZone, Utc};
+    use futures::{stream, StreamExt};
+    use tokio::io::AsyncReadExt;
+
+    use super::{ExportUsecase, ExportUsecaseImpl, EXPORT_SCHEMA_VERSION};
+    use crate::domain::{
+        efforts::Effort,
+        goals::{Goal, GoalMetric, GoalPeriod},
+        pomodoros::PomodoroInterruption,
+        tags::Tag,
+        users::User,
+    };
+    use crate::repositories::{
+        categories_repository::MockCategoryRepository, efforts_repository::MockEffortRepository,
+        events_repository::MockEventRepository, goals_repository::MockGoalRepository,
+        identities_repository::MockUserIdentityRepository,
+        pomodoros_repository::MockPomodoroRepository, sessions_repository::MockSessionRepository,
+        tags_repository::MockTagRepository, timers_repository::MockTimerRepository,
+        users_repository::MockUserRepository,
+    };
+
+    fn effort(id: i64) -> Effort {
+        Effort {
+            id,
+            owner: "test@example.com".to_owned(),
+            title: format!("effort {id}"),
+            duration_seconds: 1800,
+            started_at: Utc.with_ymd_and_hms(2023, 5, 1, 9, 0, 0).unwrap(),
+            ended_at: Utc.with_ymd_and_hms(2023, 5, 1, 9, 30, 0).unwrap(),
+            notes: None,
+            category_id: None,
+            tags: vec![],
+            pomodoro: false,
+        }
+    }
+
+    #[actix_web::test]
+    async fn ユーザの全データとマニフェストをzipに書き出す() {
+        let mut user_repository = MockUserRepository::new();
+        user_repository.expect_find().returning(|email| {
+            Ok(Some(User {
+                email: email.to_owned(),
+                user_name: "test".to_owned(),
+                avatar_url: None,
+                timezone: "UTC".to_owned(),
+                locale: "en_US".to_owned(),
+                registered_date: chrono::DateTime::UNIX_EPOCH,
+                updated_date: chrono::DateTime::UNIX_EPOCH,
+            }))
+        });
+        let mut identity_repository = MockUserIdentityRepository::new();
+        identity_repository
+            .expect_find_by_user()
+            .returning(|_| Ok(vec![]));
+        let mut effort_repository = MockEffortRepository::new();
+        effort_repository
+            .expect_stream_all()
+            .withf(|owner, from, to| owner == "test@example.com" && from.is_none() && to.is_none())
+            .returning(|_, _, _| Ok(stream::iter(vec![Ok(effort(1)), Ok(effort(2))]).boxed()));
+        let mut category_repository = MockCategoryRepository::new();
+        category_repository
+            .expect_find_all()
+            .returning(|_| Ok(vec![]));
+        let mut tag_repository = MockTagRepository::new();
+        tag_repository.expect_find_all().returning(|owner| {
+            Ok(vec![Tag {
+                id: 1,
+                owner: owner.to_owned(),
+                name: "rust".to_owned(),
+            }])
+        });
+        let mut goal_repository = MockGoalRepository::new();
+        goal_repository.expect_find_all().returning(|owner| {
+            Ok(vec![Goal {
+                id: 1,
+                owner: owner.to_owned(),
+                title: "Rust".to_owned(),
+                metric: GoalMetric::Duration,
+                target: 36000,
+                period: GoalPeriod::Weekly,
+                period_days: None,
+                starts_on: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+                category_id: None,
+            }])
+        });
+        goal_repository
+            .expect_find_completions()
+            .returning(|_| Ok(vec![]));
+        let mut timer_repository = MockTimerRepository::new();
+        timer_repository.expect_find().returning(|_| Ok(None));
+        let mut pomodoro_repository = MockPomodoroRepository::new();
+        pomodoro_repository
+            .expect_find_interruptions()
+            .returning(|owner| {
+                Ok(vec![PomodoroInterruption {
+                    owner: owner.to_owned(),
+                    title: "Rust".to_owned(),
+                    started_at: Utc.with_ymd_and_hms(2023, 5, 1, 10, 0, 0).unwrap(),
+                    interrupted_at: Utc.with_ymd_and_hms(2023, 5, 1, 10, 10, 0).unwrap(),
+                    worked_seconds: 600,
+                }])
+            });
+        let mut session_repository = MockSessionRepository::new();
+        session_repository
+            .expect_find_history()
+            .returning(|_| Ok(vec![]));
+        let mut event_repository = MockEventRepository::new();
+        event_repository
+            .expect_find_after()
+            .withf(|owner, after| owner == "test@example.com" && *after == 0)
+            .returning(|_, _| Ok(vec![]));
+        let usecase = ExportUsecaseImpl::new(
+            Box::new(user_repository),
+            Box::new(identity_repository),
+            Box::new(category_repository),
+            Box::new(tag_repository),
+            Box::new(effort_repository),
+            Box::new(goal_repository),
+            Box::new(timer_repository),
+            Box::new(pomodoro_repository),
+            Box::new(session_repository),
+            Box::new(event_repository),
+        );
+
+        // A small pipe makes the writer wait for the reader, as it does when streaming a response.
+        let (writer, mut reader) = tokio::io::duplex(256);
+        let mut archive = Vec::new();
+        let (result, _) = tokio::join!(
+            usecase.export("test@example.com", Box::new(writer)),
+            reader.read_to_end(&mut archive)
+        );
+        result.unwrap();
+
+        let zip = ZipFileReader::new(archive).await.unwrap();
+        let names: Vec<&str> = zip
+            .file()
+            .entries()
+            .iter()
+            .map(|entry| entry.filename().as_str().unwrap())
+            .collect();
+        assert_eq!(
+            vec![
+                "user.json",
+                "identities.json",
+                "categories.json",
+                "tags.json",
+                "efforts.json",
+                "goals.json",
+                "goal_completions.json",
+                "timers.json",
+                "pomodoro_interruptions.json",
+                "sessions.json",
+                "events.json",
+                "manifest.json"
+            ],
+            names
+        );
+
+        let mut efforts = String::new();
+        zip.reader_with_entry(4)
+            .await
+            .unwrap()
+            .read_to_string_checked(&mut efforts)
+            .await
+            .unwrap();
+        let efforts: Vec<Effort> = serde_json::from_str(&efforts).unwrap();
+        assert_eq!(vec![effort(1), effort(2)], efforts);
+
+        let mut manifest = String::new();
+        zip.reader_with_entry(11)
+            .await
+            .unwrap()
+            .read_to_string_checked(&mut manifest)
+            .await
+            .unwrap();
+        let manifest: serde_json::Value = serde_json::from_str(&manifest).unwrap();
+        assert_eq!(EXPORT_SCHEMA_VERSION, manifest["schema_version"]);
+        assert_eq!(1, manifest["files"][3]["records"]);
+        assert_eq!(2, manifest["files"][4]["records"]);
+        assert_eq!(1, manifest["files"][5]["records"]);
+        assert_eq!(0, manifest["files"][7]["records"]);
+        assert_eq!(1, manifest["files"][8]["records"]);
+    }
+}
@@ -0,0 +1,358 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Duration, Utc};
+use mockall::automock;
+
+use crate::domain::efforts::EffortFilter;
+use crate::domain::goals::{Goal, GoalPeriod};
+use crate::dto::{
+    GoalProgress, GoalProgressQuery, GoalProgressResult, GoalRequest, GoalResult, GoalSituation,
+};
+use crate::helpers::time_zones::{parse_time_zone, start_of_day};
+use crate::repositories::{
+    categories_repository::CategoryRepository, efforts_repository::EffortRepository,
+    goals_repository::GoalRepository,
+};
+
+const DEFAULT_PROGRESS_PERIODS: usize = 12;
+const MAX_PROGRESS_PERIODS: usize = 100;
+const MAX_PERIOD_DAYS: i32 = 366;
+
+#[automock]
+#[async_trait]
+pub trait GoalUsecase {
+    async fn get_goals(&self, owner: &str) -> Result<Vec<Goal>>;
+    async fn add_goal(&self, owner: &str, request: &GoalRequest) -> Result<GoalResult>;
+    async fn update_goal(&self, owner: &str, id: i64, request: &GoalRequest) -> Result<GoalResult>;
+    async fn delete_goal(&self, owner: &str, id: i64) -> Result<GoalResult>;
+    /// Measures the goal over the current period and the ones before it. Periods are made of
+    /// the days of `timezone`.
+    async fn get_progress(
+        &self,
+        owner: &str,
+        timezone: &str,
+        id: i64,
+        query: &GoalProgressQuery,
+    ) -> Result<GoalProgressResult>;
+}
+
+pub struct GoalUsecaseImpl {
+    goal_repository: Box<dyn GoalRepository + Send + Sync>,
+    category_repository: Box<dyn CategoryRepository + Send + Sync>,
+    effort_repository: Box<dyn EffortRepository + Send + Sync>,
+}
+
+impl GoalUsecaseImpl {
+    pub fn new(
+        goal_repository: Box<dyn GoalRepository + Send + Sync>,
+        category_repository: Box<dyn CategoryRepository + Send + Sync>,
+        effort_repository: Box<dyn EffortRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            goal_repository,
+            category_repository,
+            effort_repository,
+        }
+    }
+
+    fn result(&self, situation: GoalSituation, goal: Option<Goal>) -> GoalResult {
+        GoalResult {
+            situation,
+            goal,
+            description: None,
+        }
+    }
+
+    async fn validate(&self, owner: &str, request: &GoalRequest) -> Result<Option<GoalResult>> {
+        let custom = request.period == GoalPeriod::Custom;
+        let (situation, description) = if request.title.trim().is_empty() {
+            (GoalSituation::TitleIsEmpty, None)
+        } else if request.target <= 0 {
+            (
+                GoalSituation::InvalidTarget,
+                Some("The target must be positive.".to_owned()),
+            )
+        } else if custom != request.period_days.is_some()
+            || request
+                .period_days
+                .is_some_and(|days| !(1..=MAX_PERIOD_DAYS).contains(&days))
+        {
+            (
+                GoalSituation::InvalidPeriod,
+                Some(format!(
+                    "`period_days` must be given for `custom` periods only, from 1 to {MAX_PERIOD_DAYS}."
+                )),
+            )
+        } else {
+            match request.category_id {
+                Some(category_id)
+                    if self
+                        .category_repository
+                        .find(owner, category_id)
+                        .await?
+                        .is_none() =>
+                {
+                    (GoalSituation::CategoryNotFound, None)
+                }
+                _ => return Ok(None),
+            }
+        };
+        Ok(Some(GoalResult {
+            situation,
+            goal: None,
+            description,
+        }))
+    }
+
+    fn to_goal(&self, owner: &str, id: i64, request: &GoalRequest) -> Goal {
+        Goal {
+            id,
+            owner: owner.to_owned(),
+            title: request.title.trim().to_owned(),
+            metric: request.metric,
+            target: request.target,
+            period: request.period,
+            period_days: request.period_days,
+            starts_on: request.starts_on,
+            category_id: request.category_id,
+        }
+    }
+}
+
+#[async_trait]
+impl GoalUsecase for GoalUsecaseImpl {
+    async fn get_goals(&self, owner: &str) -> Result<Vec<Goal>> {
+        self.goal_repository.find_all(owner).await
+    }
+
+    async fn add_goal(&self, owner: &str, request: &GoalRequest) -> Result<GoalResult> {
+        if let Some(invalid) = self.validate(owner, request).await? {
+            return Ok(invalid);
+        }
+        let goal = self
+            .goal_repository
+            .add(&self.to_goal(owner, 0, request))
+            .await?;
+        Ok(self.result(GoalSituation::Succeeded, Some(goal)))
+    }
+
+    async fn update_goal(&self, owner: &str, id: i64, request: &GoalRequest) -> Result<GoalResult> {
+        if let Some(invalid) = self.validate(owner, request).await? {
+            return Ok(invalid);
+        }
+        Ok(
+            match self
+                .goal_repository
+                .update(&self.to_goal(owner, id, request))
+                .await?
+            {
+                Some(goal) => self.result(GoalSituation::Succeeded, Some(goal)),
+                None => self.result(GoalSituation::NotFound, None),
+            },
+        )
+    }
+
+    async fn delete_goal(&self, owner: &str, id: i64) -> Result<GoalResult> {
+        let situation = if self.goal_repository.delete(owner, id).await? {
+            GoalSituation::Succeeded
+        } else {
+            GoalSituation::NotFound
+        };
+        Ok(self.result(situation, None))
+    }
+
+    async fn get_progress(
+        &self,
+        owner: &str,
+        timezone: &str,
+        id: i64,
+        query: &GoalProgressQuery,
+    ) -> Result<GoalProgressResult> {
+        let count = query.periods.unwrap_or(DEFAULT_PROGRESS_PERIODS);
+        if !(1..=MAX_PROGRESS_PERIODS).contains(&count) {
+            return Ok(GoalProgressResult {
+                situation: GoalSituation::InvalidRange,
+                progress: None,
+                description: Some(format!(
+                    "`periods` must be from 1 to {MAX_PROGRESS_PERIODS}."
+                )),
+            });
+        }
+        let goal = match self.goal_repository.find(owner, id).await? {
+            Some(goal) => goal,
+            None => {
+                return Ok(GoalProgressResult {
+                    situation: GoalSituation::NotFound,
+                    progress: None,
+                    description: None,
+                })
+            }
+        };
+
+        let timezone = parse_time_zone(timezone);
+        let today = Utc::now().with_timezone(&timezone).date_naive();
+        let periods = goal.periods(today, count);
+        let instants: Vec<(DateTime<Utc>, DateTime<Utc>)> = periods
+            .iter()
+            .map(|(first_day, last_day)| {
+                (
+                    start_of_day(*first_day, timezone),
+                    start_of_day(*last_day + Duration::days(1), timezone),
+                )
+            })
+            .collect();
+        let filter = EffortFilter {
+            category_ids: goal.category_id.into_iter().collect(),
+            ..EffortFilter::default()
+        };
+        let totals = self
+            .effort_repository
+            .totals(owner, &instants, timezone.name(), &filter)
+            .await?;
+        let periods = periods
+            .iter()
+            .zip(&totals)
+            .map(|((first_day, last_day), totals)| goal.progress(*first_day, *last_day, totals))
+            .collect();
+        Ok(GoalProgressResult {
+            situation: GoalSituation::Succeeded,
+            progress: Some(GoalProgress { goal, periods }),
+            description: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, Timelike};
+
+    use super::{GoalUsecase, GoalUsecaseImpl};
+    use crate::domain::goals::{Goal, GoalMetric, GoalPeriod, PeriodTotals};
+    use crate::dto::{GoalProgressQuery, GoalRequest, GoalSituation};
+    use crate::repositories::{
+        categories_repository::MockCategoryRepository, efforts_repository::MockEffortRepository,
+        goals_repository::MockGoalRepository,
+    };
+
+    fn request(period: GoalPeriod, period_days: Option<i32>) -> GoalRequest {
+        GoalRequest {
+            title: "Rust".to_owned(),
+            metric: GoalMetric::Duration,
+            target: 36000,
+            period,
+            period_days,
+            starts_on: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+            category_id: Some(2),
+        }
+    }
+
+    #[actix_web::test]
+    async fn 日数のない任意の期間は登録しない() {
+        let usecase = GoalUsecaseImpl::new(
+            Box::new(MockGoalRepository::new()),
+            Box::new(MockCategoryRepository::new()),
+            Box::new(MockEffortRepository::new()),
+        );
+
+        let result = usecase
+            .add_goal("test@example.com", &request(GoalPeriod::Custom, None))
+            .await
+            .unwrap();
+
+        assert_eq!(GoalSituation::InvalidPeriod, result.situation);
+    }
+
+    #[actix_web::test]
+    async fn 他人のカテゴリの目標は登録しない() {
+        let mut mock_category_repository = MockCategoryRepository::new();
+        mock_category_repository
+            .expect_find()
+            .withf(|owner, id| owner == "test@example.com" && *id == 2)
+            .returning(|_, _| Ok(None));
+        let usecase = GoalUsecaseImpl::new(
+            Box::new(MockGoalRepository::new()),
+            Box::new(mock_category_repository),
+            Box::new(MockEffortRepository::new()),
+        );
+
+        let result = usecase
+            .add_goal("test@example.com", &request(GoalPeriod::Weekly, None))
+            .await
+            .unwrap();
+
+        assert_eq!(GoalSituation::CategoryNotFound, result.situation);
+    }
+
+    #[actix_web::test]
+    async fn ユーザのタイムゾーンの日付で期間ごとに集計する() {
+        let mut mock_goal_repository = MockGoalRepository::new();
+        mock_goal_repository
+            .expect_find()
+            .withf(|owner, id| owner == "test@example.com" && *id == 1)
+            .returning(|owner, id| {
+                Ok(Some(Goal {
+                    id,
+                    owner: owner.to_owned(),
+                    title: "Rust".to_owned(),
+                    metric: GoalMetric::Duration,
+                    target: 3600,
+                    period: GoalPeriod::Daily,
+                    period_days: None,
+                    starts_on: NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+                    category_id: Some(2),
+                }))
+            });
+        let mut mock_effort_repository = MockEffortRepository::new();
+        mock_effort_repository
+            .expect_totals()
+            .withf(|owner, periods, timezone, filter| {
+                owner == "test@example.com"
+                    && timezone == "Asia/Tokyo"
+                    && filter.category_ids == vec![2]
+                    && periods.len() == 2
+                    // Days in Tokyo start at 15:00 UTC.
+                    && periods[0].0.hour() == 15
+                    && periods[0].1 - periods[0].0 == Duration::days(1)
+                    && periods[1].1 == periods[0].0
+            })
+            .returning(|_, _, _, _| {
+                Ok(vec![
+                    PeriodTotals {
+                        total_seconds: 1800,
+                        effort_count: 1,
+                        distinct_days: 1,
+                    },
+                    PeriodTotals {
+                        total_seconds: 5400,
+                        effort_count: 2,
+                        distinct_days: 1,
+                    },
+                ])
+            });
+        let usecase = GoalUsecaseImpl::new(
+            Box::new(mock_goal_repository),
+            Box::new(MockCategoryRepository::new()),
+            Box::new(mock_effort_repository),
+        );
+
+        let result = usecase
+            .get_progress(
+                "test@example.com",
+                "Asia/Tokyo",
+                1,
+                &GoalProgressQuery { periods: Some(2) },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(GoalSituation::Succeeded, result.situation);
+        let completed: Vec<bool> = result
+            .progress
+            .unwrap()
+            .periods
+            .iter()
+            .map(|period| period.completed)
+            .collect();
+        assert_eq!(vec![false, true], completed);
+    }
+}
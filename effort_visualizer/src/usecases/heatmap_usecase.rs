@@ -0,0 +1,114 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use mockall::automock;
+
+use crate::domain::efforts::EffortFilter;
+use crate::domain::heatmap::{intensity_level, HeatmapBucket};
+use crate::dto::{Heatmap, HeatmapCell, HeatmapQuery, HeatmapResult, HeatmapSituation};
+use crate::helpers::time_zones::parse_time_zone;
+use crate::repositories::{efforts_repository::EffortRepository, users_repository::UserRepository};
+
+const DEFAULT_RANGE_DAYS: i64 = 52 * 7;
+const MAX_RANGE_DAYS: i64 = 5 * 366;
+
+#[automock]
+#[async_trait]
+pub trait HeatmapUsecase {
+    async fn get_heatmap(&self, user_id: &str, query: &HeatmapQuery) -> Result<HeatmapResult>;
+}
+
+pub struct HeatmapUsecaseImpl {
+    user_repository: Box<dyn UserRepository + Send + Sync>,
+    effort_repository: Box<dyn EffortRepository + Send + Sync>,
+}
+
+impl HeatmapUsecaseImpl {
+    pub fn new(
+        user_repository: Box<dyn UserRepository + Send + Sync>,
+        effort_repository: Box<dyn EffortRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            user_repository,
+            effort_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl HeatmapUsecase for HeatmapUsecaseImpl {
+    async fn get_heatmap(&self, user_id: &str, query: &HeatmapQuery) -> Result<HeatmapResult> {
+        let user = match self.user_repository.find(user_id).await? {
+            Some(user) => user,
+            None => {
+                return Ok(HeatmapResult {
+                    situation: HeatmapSituation::UserNotFound,
+                    heatmap: None,
+                    description: None,
+                })
+            }
+        };
+        let timezone = parse_time_zone(&user.timezone);
+
+        let to = query
+            .to
+            .unwrap_or_else(|| Utc::now().with_timezone(&timezone).date_naive());
+        let from = query
+            .from
+            .unwrap_or_else(|| to - Duration::days(DEFAULT_RANGE_DAYS));
+        if from > to || (to - from).num_days() > MAX_RANGE_DAYS {
+            return Ok(HeatmapResult {
+                situation: HeatmapSituation::InvalidRange,
+                heatmap: None,
+                description: Some(format!(
+                    "`from` must not be after `to` and the range must be within {MAX_RANGE_DAYS} days."
+                )),
+            });
+        }
+
+        let filter = match EffortFilter::parse(
+            query.category.as_deref(),
+            query.tag.as_deref(),
+            query.match_mode,
+        ) {
+            Ok(filter) => filter,
+            Err(description) => {
+                return Ok(HeatmapResult {
+                    situation: HeatmapSituation::InvalidFilter,
+                    heatmap: None,
+                    description: Some(description),
+                })
+            }
+        };
+
+        let bucket = query.bucket.unwrap_or(HeatmapBucket::Day);
+        let quartiles = self
+            .effort_repository
+            .quartiles(user_id, bucket, timezone.name(), &filter)
+            .await?;
+        let cells = self
+            .effort_repository
+            .aggregate(user_id, from, to, bucket, timezone.name(), &filter)
+            .await?
+            .into_iter()
+            .map(|aggregate| HeatmapCell {
+                date: aggregate.bucket_start,
+                total_seconds: aggregate.total_seconds,
+                effort_count: aggregate.effort_count,
+                level: intensity_level(aggregate.total_seconds, &quartiles),
+            })
+            .collect();
+        Ok(HeatmapResult {
+            situation: HeatmapSituation::Succeeded,
+            heatmap: Some(Heatmap {
+                from,
+                to,
+                bucket,
+                timezone: user.timezone,
+                locale: user.locale,
+                cells,
+            }),
+            description: None,
+        })
+    }
+}
@@ -1 +1,13 @@
-pub mod authentication_usecase;
\ No newline at end of file
+pub mod authentication_usecase;
+pub mod category_usecase;
+pub mod effort_usecase;
+pub mod event_usecase;
+pub mod export_usecase;
+pub mod goal_usecase;
+pub mod heatmap_usecase;
+pub mod pomodoro_usecase;
+pub mod session_usecase;
+pub mod streak_usecase;
+pub mod tag_usecase;
+pub mod timer_usecase;
+pub mod user_usecase;
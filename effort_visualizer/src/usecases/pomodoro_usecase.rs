@@ -0,0 +1,140 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{Duration, Utc};
+use mockall::automock;
+
+use crate::dto::{PomodoroStatsQuery, PomodoroStatsResult, PomodoroStatsSituation};
+use crate::helpers::time_zones::parse_time_zone;
+use crate::repositories::pomodoros_repository::PomodoroRepository;
+
+const DEFAULT_STATS_DAYS: i64 = 30;
+const MAX_STATS_DAYS: i64 = 366;
+
+#[automock]
+#[async_trait]
+pub trait PomodoroUsecase {
+    /// Counts the completed and abandoned pomodoros per day of `timezone`.
+    async fn get_stats(
+        &self,
+        owner: &str,
+        timezone: &str,
+        query: &PomodoroStatsQuery,
+    ) -> Result<PomodoroStatsResult>;
+}
+
+pub struct PomodoroUsecaseImpl {
+    pomodoro_repository: Box<dyn PomodoroRepository + Send + Sync>,
+}
+
+impl PomodoroUsecaseImpl {
+    pub fn new(pomodoro_repository: Box<dyn PomodoroRepository + Send + Sync>) -> Self {
+        Self {
+            pomodoro_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl PomodoroUsecase for PomodoroUsecaseImpl {
+    async fn get_stats(
+        &self,
+        owner: &str,
+        timezone: &str,
+        query: &PomodoroStatsQuery,
+    ) -> Result<PomodoroStatsResult> {
+        let timezone = parse_time_zone(timezone);
+        let to = query
+            .to
+            .unwrap_or_else(|| Utc::now().with_timezone(&timezone).date_naive());
+        let from = query
+            .from
+            .unwrap_or(to - Duration::days(DEFAULT_STATS_DAYS - 1));
+        if !(0..MAX_STATS_DAYS).contains(&(to - from).num_days()) {
+            return Ok(PomodoroStatsResult {
+                situation: PomodoroStatsSituation::InvalidRange,
+                days: None,
+                description: Some(format!(
+                    "`from` must not be after `to`, and at most {MAX_STATS_DAYS} days can be reported."
+                )),
+            });
+        }
+        let days = self
+            .pomodoro_repository
+            .daily_counts(owner, from, to, timezone.name())
+            .await?;
+        Ok(PomodoroStatsResult {
+            situation: PomodoroStatsSituation::Succeeded,
+            days: Some(days),
+            description: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::NaiveDate;
+
+    use super::{PomodoroUsecase, PomodoroUsecaseImpl};
+    use crate::domain::pomodoros::PomodoroDay;
+    use crate::dto::{PomodoroStatsQuery, PomodoroStatsSituation};
+    use crate::repositories::pomodoros_repository::MockPomodoroRepository;
+
+    fn date(day: u32) -> NaiveDate {
+        NaiveDate::from_ymd_opt(2023, 5, day).unwrap()
+    }
+
+    #[actix_web::test]
+    async fn 指定がなければ最後の日までの30日間を数える() {
+        let mut mock_repository = MockPomodoroRepository::new();
+        mock_repository
+            .expect_daily_counts()
+            .withf(|owner, from, to, timezone| {
+                owner == "test@example.com"
+                    && *from == date(2)
+                    && *to == date(31)
+                    && timezone == "Asia/Tokyo"
+            })
+            .returning(|_, _, to, _| {
+                Ok(vec![PomodoroDay {
+                    date: to,
+                    completed: 4,
+                    abandoned: 1,
+                }])
+            });
+        let usecase = PomodoroUsecaseImpl::new(Box::new(mock_repository));
+
+        let result = usecase
+            .get_stats(
+                "test@example.com",
+                "Asia/Tokyo",
+                &PomodoroStatsQuery {
+                    from: None,
+                    to: Some(date(31)),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(PomodoroStatsSituation::Succeeded, result.situation);
+        assert_eq!(1, result.days.unwrap()[0].abandoned);
+    }
+
+    #[actix_web::test]
+    async fn 開始日が終了日より後なら数えない() {
+        let usecase = PomodoroUsecaseImpl::new(Box::new(MockPomodoroRepository::new()));
+
+        let result = usecase
+            .get_stats(
+                "test@example.com",
+                "UTC",
+                &PomodoroStatsQuery {
+                    from: Some(date(2)),
+                    to: Some(date(1)),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(PomodoroStatsSituation::InvalidRange, result.situation);
+    }
+}
@@ -0,0 +1,57 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use mockall::automock;
+
+use crate::domain::sessions::UserSession;
+use crate::helpers::environments::SessionStoreKind;
+use crate::repositories::sessions_repository::SessionRepository;
+
+#[automock]
+#[async_trait]
+pub trait SessionUsecase {
+    /// Returns `None` when sessions are only kept in cookies, where the server cannot see them.
+    async fn get_sessions(&self, user_email: &str) -> Result<Option<Vec<UserSession>>>;
+    /// Returns `Some(false)` when the user has no session with the id, and `None` when sessions
+    /// are only kept in cookies.
+    async fn revoke_session(&self, user_email: &str, id: i64) -> Result<Option<bool>>;
+}
+
+pub struct SessionUsecaseImpl {
+    session_store: SessionStoreKind,
+    session_repository: Box<dyn SessionRepository + Send + Sync>,
+}
+
+impl SessionUsecaseImpl {
+    pub fn new(
+        session_store: SessionStoreKind,
+        session_repository: Box<dyn SessionRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            session_store,
+            session_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl SessionUsecase for SessionUsecaseImpl {
+    async fn get_sessions(&self, user_email: &str) -> Result<Option<Vec<UserSession>>> {
+        if self.session_store == SessionStoreKind::Cookie {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.session_repository.find_by_user(user_email).await?,
+        ))
+    }
+
+    async fn revoke_session(&self, user_email: &str, id: i64) -> Result<Option<bool>> {
+        if self.session_store == SessionStoreKind::Cookie {
+            return Ok(None);
+        }
+        Ok(Some(
+            self.session_repository
+                .delete_by_user(user_email, id)
+                .await?,
+        ))
+    }
+}
@@ -0,0 +1,174 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::Utc;
+use mockall::automock;
+
+use crate::domain::streaks::{current_streak, longest_streak, parse_rest_days, streaks};
+use crate::dto::{StreakQuery, StreakResult, StreakSituation, Streaks};
+use crate::helpers::time_zones::parse_time_zone;
+use crate::repositories::{efforts_repository::EffortRepository, users_repository::UserRepository};
+
+#[automock]
+#[async_trait]
+pub trait StreakUsecase {
+    /// Finds the streaks of the user over the days of the user's time zone.
+    async fn get_streaks(&self, user_id: &str, query: &StreakQuery) -> Result<StreakResult>;
+}
+
+pub struct StreakUsecaseImpl {
+    user_repository: Box<dyn UserRepository + Send + Sync>,
+    effort_repository: Box<dyn EffortRepository + Send + Sync>,
+}
+
+impl StreakUsecaseImpl {
+    pub fn new(
+        user_repository: Box<dyn UserRepository + Send + Sync>,
+        effort_repository: Box<dyn EffortRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            user_repository,
+            effort_repository,
+        }
+    }
+}
+
+#[async_trait]
+impl StreakUsecase for StreakUsecaseImpl {
+    async fn get_streaks(&self, user_id: &str, query: &StreakQuery) -> Result<StreakResult> {
+        let rest_days = match parse_rest_days(query.rest_days.as_deref()) {
+            Ok(rest_days) => rest_days,
+            Err(description) => {
+                return Ok(StreakResult {
+                    situation: StreakSituation::InvalidRestDays,
+                    streaks: None,
+                    description: Some(description),
+                })
+            }
+        };
+        let user = match self.user_repository.find(user_id).await? {
+            Some(user) => user,
+            None => {
+                return Ok(StreakResult {
+                    situation: StreakSituation::UserNotFound,
+                    streaks: None,
+                    description: None,
+                })
+            }
+        };
+
+        let timezone = parse_time_zone(&user.timezone);
+        let days = self
+            .effort_repository
+            .find_effort_days(user_id, timezone.name())
+            .await?;
+        let history = streaks(&days, &rest_days);
+        let today = Utc::now().with_timezone(&timezone).date_naive();
+        Ok(StreakResult {
+            situation: StreakSituation::Succeeded,
+            streaks: Some(Streaks {
+                current: current_streak(&history, today, &rest_days),
+                longest: longest_streak(&history),
+                history: history.into_iter().rev().collect(),
+            }),
+            description: None,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, NaiveDate, Utc};
+
+    use super::{StreakUsecase, StreakUsecaseImpl};
+    use crate::domain::users::User;
+    use crate::dto::{StreakQuery, StreakSituation};
+    use crate::repositories::{
+        efforts_repository::MockEffortRepository, users_repository::MockUserRepository,
+    };
+
+    fn user_repository(timezone: &'static str) -> MockUserRepository {
+        let mut user_repository = MockUserRepository::new();
+        user_repository.expect_find().returning(move |email| {
+            Ok(Some(User {
+                email: email.to_owned(),
+                user_name: "test".to_owned(),
+                avatar_url: None,
+                timezone: timezone.to_owned(),
+                locale: "en_US".to_owned(),
+                registered_date: chrono::DateTime::UNIX_EPOCH,
+                updated_date: chrono::DateTime::UNIX_EPOCH,
+            }))
+        });
+        user_repository
+    }
+
+    #[actix_web::test]
+    async fn ユーザのタイムゾーンの日付で連続記録を数える() {
+        let mut effort_repository = MockEffortRepository::new();
+        effort_repository
+            .expect_find_effort_days()
+            .withf(|owner, timezone| owner == "test@example.com" && timezone == "Asia/Tokyo")
+            .returning(|_, _| {
+                Ok(vec![
+                    NaiveDate::from_ymd_opt(2023, 5, 1).unwrap(),
+                    NaiveDate::from_ymd_opt(2023, 5, 2).unwrap(),
+                ])
+            });
+        let usecase = StreakUsecaseImpl::new(
+            Box::new(user_repository("Asia/Tokyo")),
+            Box::new(effort_repository),
+        );
+
+        let result = usecase
+            .get_streaks("test@example.com", &StreakQuery { rest_days: None })
+            .await
+            .unwrap();
+
+        assert_eq!(StreakSituation::Succeeded, result.situation);
+        let streaks = result.streaks.unwrap();
+        assert_eq!(2, streaks.longest.unwrap().active_days);
+        assert_eq!(None, streaks.current);
+    }
+
+    #[actix_web::test]
+    async fn 昨日まで続いた連続記録を現在の連続記録とする() {
+        let mut effort_repository = MockEffortRepository::new();
+        effort_repository
+            .expect_find_effort_days()
+            .returning(|_, _| {
+                let yesterday = (Utc::now() - Duration::days(1)).date_naive();
+                Ok(vec![yesterday - Duration::days(1), yesterday])
+            });
+        let usecase = StreakUsecaseImpl::new(
+            Box::new(user_repository("UTC")),
+            Box::new(effort_repository),
+        );
+
+        let result = usecase
+            .get_streaks("test@example.com", &StreakQuery { rest_days: None })
+            .await
+            .unwrap();
+
+        assert_eq!(2, result.streaks.unwrap().current.unwrap().active_days);
+    }
+
+    #[actix_web::test]
+    async fn 曜日でない休息日は受け付けない() {
+        let usecase = StreakUsecaseImpl::new(
+            Box::new(MockUserRepository::new()),
+            Box::new(MockEffortRepository::new()),
+        );
+
+        let result = usecase
+            .get_streaks(
+                "test@example.com",
+                &StreakQuery {
+                    rest_days: Some("sat,holiday".to_owned()),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(StreakSituation::InvalidRestDays, result.situation);
+    }
+}
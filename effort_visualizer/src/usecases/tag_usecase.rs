@@ -0,0 +1,169 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use mockall::automock;
+
+use crate::domain::tags::Tag;
+use crate::dto::{TagMergeRequest, TagRequest, TagResult, TagSituation};
+use crate::repositories::tags_repository::{TagRepository, TagWrite};
+
+#[automock]
+#[async_trait]
+pub trait TagUsecase {
+    async fn get_tags(&self, owner: &str) -> Result<Vec<Tag>>;
+    async fn add_tag(&self, owner: &str, request: &TagRequest) -> Result<TagResult>;
+    async fn rename_tag(&self, owner: &str, id: i64, request: &TagRequest) -> Result<TagResult>;
+    /// Moves the efforts of tag `id` to the tag the request names and deletes tag `id`.
+    async fn merge_tag(&self, owner: &str, id: i64, request: &TagMergeRequest)
+        -> Result<TagResult>;
+    async fn delete_tag(&self, owner: &str, id: i64) -> Result<TagResult>;
+}
+
+pub struct TagUsecaseImpl {
+    tag_repository: Box<dyn TagRepository + Send + Sync>,
+}
+
+impl TagUsecaseImpl {
+    pub fn new(tag_repository: Box<dyn TagRepository + Send + Sync>) -> Self {
+        Self { tag_repository }
+    }
+
+    fn result(&self, situation: TagSituation, tag: Option<Tag>) -> TagResult {
+        TagResult {
+            situation,
+            tag,
+            description: None,
+        }
+    }
+
+    fn written(&self, write: TagWrite) -> TagResult {
+        match write {
+            TagWrite::Saved(tag) => self.result(TagSituation::Succeeded, Some(tag)),
+            TagWrite::NotFound => self.result(TagSituation::NotFound, None),
+            TagWrite::NameTaken => self.result(TagSituation::NameAlreadyUsed, None),
+        }
+    }
+}
+
+#[async_trait]
+impl TagUsecase for TagUsecaseImpl {
+    async fn get_tags(&self, owner: &str) -> Result<Vec<Tag>> {
+        self.tag_repository.find_all(owner).await
+    }
+
+    async fn add_tag(&self, owner: &str, request: &TagRequest) -> Result<TagResult> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Ok(self.result(TagSituation::NameIsEmpty, None));
+        }
+        let write = self
+            .tag_repository
+            .add(&Tag {
+                id: 0,
+                owner: owner.to_owned(),
+                name: name.to_owned(),
+            })
+            .await?;
+        Ok(self.written(write))
+    }
+
+    async fn rename_tag(&self, owner: &str, id: i64, request: &TagRequest) -> Result<TagResult> {
+        let name = request.name.trim();
+        if name.is_empty() {
+            return Ok(self.result(TagSituation::NameIsEmpty, None));
+        }
+        let write = self.tag_repository.rename(owner, id, name).await?;
+        Ok(self.written(write))
+    }
+
+    async fn merge_tag(
+        &self,
+        owner: &str,
+        id: i64,
+        request: &TagMergeRequest,
+    ) -> Result<TagResult> {
+        if id == request.into {
+            return Ok(self.result(TagSituation::SameTag, None));
+        }
+        Ok(
+            match self.tag_repository.merge(owner, id, request.into).await? {
+                Some(tag) => self.result(TagSituation::Succeeded, Some(tag)),
+                None => self.result(TagSituation::NotFound, None),
+            },
+        )
+    }
+
+    async fn delete_tag(&self, owner: &str, id: i64) -> Result<TagResult> {
+        let situation = if self.tag_repository.delete(owner, id).await? {
+            TagSituation::Succeeded
+        } else {
+            TagSituation::NotFound
+        };
+        Ok(self.result(situation, None))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{TagUsecase, TagUsecaseImpl};
+    use crate::domain::tags::Tag;
+    use crate::dto::{TagMergeRequest, TagRequest, TagSituation};
+    use crate::repositories::tags_repository::{MockTagRepository, TagWrite};
+
+    #[actix_web::test]
+    async fn 前後の空白を除いた名前に変更する() {
+        let mut mock_repository = MockTagRepository::new();
+        mock_repository
+            .expect_rename()
+            .withf(|owner, id, name| owner == "test@example.com" && *id == 1 && name == "rust")
+            .returning(|owner, id, name| {
+                Ok(TagWrite::Saved(Tag {
+                    id,
+                    owner: owner.to_owned(),
+                    name: name.to_owned(),
+                }))
+            });
+        let usecase = TagUsecaseImpl::new(Box::new(mock_repository));
+
+        let result = usecase
+            .rename_tag(
+                "test@example.com",
+                1,
+                &TagRequest {
+                    name: " rust ".to_owned(),
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(TagSituation::Succeeded, result.situation);
+    }
+
+    #[actix_web::test]
+    async fn 自分自身には統合しない() {
+        let usecase = TagUsecaseImpl::new(Box::new(MockTagRepository::new()));
+
+        let result = usecase
+            .merge_tag("test@example.com", 1, &TagMergeRequest { into: 1 })
+            .await
+            .unwrap();
+
+        assert_eq!(TagSituation::SameTag, result.situation);
+    }
+
+    #[actix_web::test]
+    async fn 統合先がないときnot_foundを返す() {
+        let mut mock_repository = MockTagRepository::new();
+        mock_repository
+            .expect_merge()
+            .withf(|_, source, target| *source == 1 && *target == 2)
+            .returning(|_, _, _| Ok(None));
+        let usecase = TagUsecaseImpl::new(Box::new(mock_repository));
+
+        let result = usecase
+            .merge_tag("test@example.com", 1, &TagMergeRequest { into: 2 })
+            .await
+            .unwrap();
+
+        assert_eq!(TagSituation::NotFound, result.situation);
+    }
+}
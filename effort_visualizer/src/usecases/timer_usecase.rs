@@ -0,0 +1,384 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono::{DateTime, Utc};
+use mockall::automock;
+
+use crate::domain::efforts::Effort;
+use crate::domain::pomodoros::PomodoroSettings;
+use crate::domain::tags::normalize_tags;
+use crate::domain::timers::Timer;
+use crate::dto::{TimerRequest, TimerResult, TimerSituation, TimerState};
+use crate::repositories::{
+    categories_repository::CategoryRepository, timers_repository::TimerRepository,
+};
+
+const MIN_POMODORO_WORK_SECONDS: i32 = 60;
+const MAX_POMODORO_INTERVAL_SECONDS: i32 = 4 * 60 * 60;
+const MAX_POMODORO_CYCLES: i32 = 12;
+
+#[automock]
+#[async_trait]
+pub trait TimerUsecase {
+    /// Returns the timer, recording the pomodoros it has completed since the last request.
+    async fn get_timer(&self, owner: &str) -> Result<TimerResult>;
+    async fn start_timer(&self, owner: &str, request: &TimerRequest) -> Result<TimerResult>;
+    async fn pause_timer(&self, owner: &str) -> Result<TimerResult>;
+    async fn resume_timer(&self, owner: &str) -> Result<TimerResult>;
+    /// Removes the timer and records the time it counted as an effort. A pomodoro timer
+    /// records its completed work intervals instead, and counts an unfinished one as abandoned.
+    async fn stop_timer(&self, owner: &str) -> Result<TimerResult>;
+}
+
+pub struct TimerUsecaseImpl {
+    timer_repository: Box<dyn TimerRepository + Send + Sync>,
+    category_repository: Box<dyn CategoryRepository + Send + Sync>,
+}
+
+fn result(situation: TimerSituation, timer: Option<TimerState>) -> TimerResult {
+    TimerResult {
+        situation,
+        timer,
+        efforts: Vec::new(),
+        description: None,
+    }
+}
+
+fn valid_pomodoro(settings: &PomodoroSettings) -> bool {
+    let intervals = 0..=MAX_POMODORO_INTERVAL_SECONDS;
+    settings.work_seconds >= MIN_POMODORO_WORK_SECONDS
+        && intervals.contains(&settings.work_seconds)
+        && intervals.contains(&settings.short_break_seconds)
+        && intervals.contains(&settings.long_break_seconds)
+        && (1..=MAX_POMODORO_CYCLES).contains(&settings.cycles)
+}
+
+impl TimerUsecaseImpl {
+    pub fn new(
+        timer_repository: Box<dyn TimerRepository + Send + Sync>,
+        category_repository: Box<dyn CategoryRepository + Send + Sync>,
+    ) -> Self {
+        Self {
+            timer_repository,
+            category_repository,
+        }
+    }
+
+    async fn validate(&self, owner: &str, request: &TimerRequest) -> Result<Option<TimerResult>> {
+        let situation = if request.title.is_empty() {
+            TimerSituation::TitleIsEmpty
+        } else if request.tags.iter().any(|tag| tag.trim().is_empty()) {
+            TimerSituation::TagIsEmpty
+        } else if request
+            .pomodoro
+            .is_some_and(|settings| !valid_pomodoro(&settings))
+        {
+            return Ok(Some(TimerResult {
+                description: Some(format!(
+                    "Work intervals must be from {MIN_POMODORO_WORK_SECONDS} seconds and breaks \
+                     from 0 seconds, both up to {MAX_POMODORO_INTERVAL_SECONDS} seconds, with 1 \
+                     to {MAX_POMODORO_CYCLES} cycles."
+                )),
+                ..result(TimerSituation::InvalidPomodoro, None)
+            }));
+        } else {
+            match request.category_id {
+                Some(category_id)
+                    if self
+                        .category_repository
+                        .find(owner, category_id)
+                        .await?
+                        .is_none() =>
+                {
+                    TimerSituation::CategoryNotFound
+                }
+                _ => return Ok(None),
+            }
+        };
+        Ok(Some(result(situation, None)))
+    }
+
+    /// Applies `change` to the owner's timer unless `applies` rejects it with a situation.
+    /// The pomodoros completed before the change are recorded along with it.
+    async fn change(
+        &self,
+        owner: &str,
+        applies: impl Fn(&Timer) -> Option<TimerSituation> + Send,
+        change: impl Fn(&mut Timer, DateTime<Utc>) + Send,
+    ) -> Result<TimerResult> {
+        let mut timer = match self.timer_repository.find(owner).await? {
+            Some(timer) => timer,
+            None => return Ok(result(TimerSituation::NotFound, None)),
+        };
+        let now = Utc::now();
+        if let Some(situation) = applies(&timer) {
+            return Ok(result(situation, Some(TimerState::new(timer, now))));
+        }
+        let completed = timer.settle(now);
+        change(&mut timer, now);
+        Ok(
+            match self.timer_repository.update(&timer, &completed).await? {
+                Some((timer, efforts)) => TimerResult {
+                    efforts,
+                    ..result(TimerSituation::Succeeded, Some(TimerState::new(timer, now)))
+                },
+                None => result(TimerSituation::Conflict, None),
+            },
+        )
+    }
+}
+
+#[async_trait]
+impl TimerUsecase for TimerUsecaseImpl {
+    async fn get_timer(&self, owner: &str) -> Result<TimerResult> {
+        let mut timer = match self.timer_repository.find(owner).await? {
+            Some(timer) => timer,
+            None => return Ok(result(TimerSituation::NotFound, None)),
+        };
+        let now = Utc::now();
+        let completed = timer.settle(now);
+        if completed.is_empty() {
+            return Ok(result(
+                TimerSituation::Succeeded,
+                Some(TimerState::new(timer, now)),
+            ));
+        }
+        Ok(
+            match self.timer_repository.update(&timer, &completed).await? {
+                Some((timer, efforts)) => TimerResult {
+                    efforts,
+                    ..result(TimerSituation::Succeeded, Some(TimerState::new(timer, now)))
+                },
+                // Another request has recorded the pomodoros in the meantime.
+                None => match self.timer_repository.find(owner).await? {
+                    Some(timer) => {
+                        result(TimerSituation::Succeeded, Some(TimerState::new(timer, now)))
+                    }
+                    None => result(TimerSituation::NotFound, None),
+                },
+            },
+        )
+    }
+
+    async fn start_timer(&self, owner: &str, request: &TimerRequest) -> Result<TimerResult> {
+        if let Some(invalid) = self.validate(owner, request).await? {
+            return Ok(invalid);
+        }
+        let now = Utc::now();
+        let timer = Timer {
+            owner: owner.to_owned(),
+            title: request.title.to_owned(),
+            notes: request.notes.to_owned(),
+            category_id: request.category_id,
+            tags: normalize_tags(&request.tags),
+            started_at: now,
+            running_since: Some(now),
+            accumulated_seconds: 0,
+            pomodoro: request.pomodoro,
+            settled_seconds: 0,
+            work_started_at: request.pomodoro.map(|_| now),
+            revision: 0,
+        };
+        Ok(match self.timer_repository.add(&timer).await? {
+            Some(timer) => result(TimerSituation::Succeeded, Some(TimerState::new(timer, now))),
+            None => {
+                let running = self.timer_repository.find(owner).await?;
+                result(
+                    TimerSituation::AlreadyStarted,
+                    running.map(|timer| TimerState::new(timer, now)),
+                )
+            }
+        })
+    }
+
+    async fn pause_timer(&self, owner: &str) -> Result<TimerResult> {
+        self.change(
+            owner,
+            |timer| (!timer.is_running()).then_some(TimerSituation::AlreadyPaused),
+            Timer::pause,
+        )
+        .await
+    }
+
+    async fn resume_timer(&self, owner: &str) -> Result<TimerResult> {
+        self.change(
+            owner,
+            |timer| timer.is_running().then_some(TimerSituation::AlreadyRunning),
+            Timer::resume,
+        )
+        .await
+    }
+
+    async fn stop_timer(&self, owner: &str) -> Result<TimerResult> {
+        let mut timer = match self.timer_repository.find(owner).await? {
+            Some(timer) => timer,
+            None => return Ok(result(TimerSituation::NotFound, None)),
+        };
+        let now = Utc::now();
+        let (efforts, interruption): (Vec<Effort>, _) = if timer.pomodoro.is_some() {
+            (timer.settle(now), timer.interruption(now))
+        } else {
+            (vec![timer.to_effort(now)], None)
+        };
+        Ok(
+            match self
+                .timer_repository
+                .finish(&timer, &efforts, interruption)
+                .await?
+            {
+                Some(efforts) => TimerResult {
+                    efforts,
+                    ..result(TimerSituation::Succeeded, None)
+                },
+                None => result(TimerSituation::Conflict, None),
+            },
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::{Duration, Utc};
+
+    use super::{TimerUsecase, TimerUsecaseImpl};
+    use crate::domain::pomodoros::PomodoroSettings;
+    use crate::domain::timers::Timer;
+    use crate::dto::{TimerRequest, TimerSituation};
+    use crate::repositories::{
+        categories_repository::MockCategoryRepository, timers_repository::MockTimerRepository,
+    };
+
+    fn timer() -> Timer {
+        let started_at = Utc::now() - Duration::minutes(30);
+        Timer {
+            owner: "test@example.com".to_owned(),
+            title: "Rust".to_owned(),
+            notes: None,
+            category_id: None,
+            tags: vec!["book".to_owned()],
+            started_at,
+            running_since: None,
+            accumulated_seconds: 600,
+            pomodoro: None,
+            settled_seconds: 0,
+            work_started_at: None,
+            revision: 3,
+        }
+    }
+
+    #[actix_web::test]
+    async fn 既にタイマーがあれば開始しない() {
+        let mut mock_repository = MockTimerRepository::new();
+        mock_repository.expect_add().returning(|_| Ok(None));
+        mock_repository
+            .expect_find()
+            .returning(|_| Ok(Some(timer())));
+        let usecase = TimerUsecaseImpl::new(
+            Box::new(mock_repository),
+            Box::new(MockCategoryRepository::new()),
+        );
+
+        let result = usecase
+            .start_timer(
+                "test@example.com",
+                &TimerRequest {
+                    title: "Rust".to_owned(),
+                    ..Default::default()
+                },
+            )
+            .await
+            .unwrap();
+
+        assert_eq!(TimerSituation::AlreadyStarted, result.situation);
+        assert_eq!(600, result.timer.unwrap().elapsed_seconds);
+    }
+
+    #[actix_web::test]
+    async fn 停止すると一時停止中を除いた時間の努力を記録する() {
+        let mut mock_repository = MockTimerRepository::new();
+        mock_repository
+            .expect_find()
+            .returning(|_| Ok(Some(timer())));
+        mock_repository
+            .expect_finish()
+            .withf(|timer, efforts, interruption| {
+                timer.revision == 3
+                    && interruption.is_none()
+                    && efforts.len() == 1
+                    && efforts[0].duration_seconds == 600
+                    && efforts[0].started_at == timer.started_at
+                    && efforts[0].ended_at - efforts[0].started_at >= Duration::minutes(30)
+                    && efforts[0].tags == vec!["book".to_owned()]
+                    && !efforts[0].pomodoro
+            })
+            .returning(|_, efforts, _| Ok(Some(efforts.to_vec())));
+        let usecase = TimerUsecaseImpl::new(
+            Box::new(mock_repository),
+            Box::new(MockCategoryRepository::new()),
+        );
+
+        let result = usecase.stop_timer("test@example.com").await.unwrap();
+
+        assert_eq!(TimerSituation::Succeeded, result.situation);
+        assert_eq!(600, result.efforts[0].duration_seconds);
+    }
+
+    #[actix_web::test]
+    async fn 同時に変更されたタイマーは更新しない() {
+        let mut mock_repository = MockTimerRepository::new();
+        mock_repository
+            .expect_find()
+            .returning(|_| Ok(Some(timer())));
+        mock_repository
+            .expect_update()
+            .withf(|timer, completed| {
+                timer.revision == 3 && timer.running_since.is_some() && completed.is_empty()
+            })
+            .returning(|_, _| Ok(None));
+        let usecase = TimerUsecaseImpl::new(
+            Box::new(mock_repository),
+            Box::new(MockCategoryRepository::new()),
+        );
+
+        let result = usecase.resume_timer("test@example.com").await.unwrap();
+        assert_eq!(TimerSituation::Conflict, result.situation);
+
+        let result = usecase.pause_timer("test@example.com").await.unwrap();
+        assert_eq!(TimerSituation::AlreadyPaused, result.situation);
+    }
+
+    #[actix_web::test]
+    async fn 途中で停止したポモドーロは中断として数える() {
+        let mut mock_repository = MockTimerRepository::new();
+        mock_repository.expect_find().returning(|_| {
+            let now = Utc::now();
+            Ok(Some(Timer {
+                started_at: now - Duration::minutes(40),
+                running_since: Some(now - Duration::minutes(40)),
+                accumulated_seconds: 0,
+                pomodoro: Some(PomodoroSettings::default()),
+                work_started_at: Some(now - Duration::minutes(40)),
+                ..timer()
+            }))
+        });
+        mock_repository
+            .expect_finish()
+            .withf(|_, efforts, interruption| {
+                // 25 minutes of work and a 5 minute break, then 10 minutes of the next interval.
+                efforts.len() == 1
+                    && efforts[0].pomodoro
+                    && efforts[0].duration_seconds == 1500
+                    && interruption.as_ref().is_some_and(|interruption| {
+                        (600..610).contains(&interruption.worked_seconds)
+                    })
+            })
+            .returning(|_, efforts, _| Ok(Some(efforts.to_vec())));
+        let usecase = TimerUsecaseImpl::new(
+            Box::new(mock_repository),
+            Box::new(MockCategoryRepository::new()),
+        );
+
+        let result = usecase.stop_timer("test@example.com").await.unwrap();
+
+        assert_eq!(TimerSituation::Succeeded, result.situation);
+    }
+}
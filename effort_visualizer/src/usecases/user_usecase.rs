@@ -0,0 +1,306 @@
+use anyhow::Result;
+use async_trait::async_trait;
+use chrono_tz::Tz;
+use mockall::automock;
+use rand::{distributions::Alphanumeric, Rng};
+use url::Url;
+
+use crate::dto::{ProfileRequest, ProfileResult, ProfileSituation, PublicCalendar};
+use crate::helpers::locales::parse_locale;
+use crate::repositories::users_repository::UserRepository;
+
+#[automock]
+#[async_trait]
+pub trait UserUsecase {
+    async fn update_profile(
+        &self,
+        user_email: &str,
+        request: &ProfileRequest,
+    ) -> Result<ProfileResult>;
+    /// Removes the user and everything they own. Returns `false` when the user is not registered.
+    async fn delete_account(&self, user_email: &str) -> Result<bool>;
+    /// Makes the user's calendar public under a new slug, so that the previous one stops
+    /// working. Returns `None` when the user is not registered.
+    async fn publish_calendar(&self, user_email: &str) -> Result<Option<PublicCalendar>>;
+    /// Makes the user's calendar private again. Returns `false` when the user is not registered.
+    async fn unpublish_calendar(&self, user_email: &str) -> Result<bool>;
+    /// Returns the email of the user whose calendar is public under `slug`.
+    async fn find_public_calendar_owner(&self, slug: &str) -> Result<Option<String>>;
+}
+
+const PUBLIC_SLUG_LENGTH: usize = 32;
+
+pub struct UserUsecaseImpl {
+    user_repository: Box<dyn UserRepository + Send + Sync>,
+}
+
+impl UserUsecaseImpl {
+    pub fn new(user_repository: Box<dyn UserRepository + Send + Sync>) -> Self {
+        Self { user_repository }
+    }
+}
+
+fn invalid(situation: ProfileSituation, description: String) -> ProfileResult {
+    ProfileResult {
+        situation,
+        user: None,
+        description: Some(description),
+    }
+}
+
+#[async_trait]
+impl UserUsecase for UserUsecaseImpl {
+    async fn update_profile(
+        &self,
+        user_email: &str,
+        request: &ProfileRequest,
+    ) -> Result<ProfileResult> {
+        if matches!(&request.user_name, Some(user_name) if user_name.is_empty()) {
+            return Ok(ProfileResult {
+                situation: ProfileSituation::UserNameIsEmpty,
+                user: None,
+                description: None,
+            });
+        }
+        if let Some(avatar_url) = request.avatar_url.as_deref().filter(|url| !url.is_empty()) {
+            let valid = Url::parse(avatar_url)
+                .map(|url| url.scheme() == "https" || url.scheme() == "http")
+                .unwrap_or(false);
+            if !valid {
+                return Ok(invalid(
+                    ProfileSituation::InvalidAvatarUrl,
+                    format!("`{avatar_url}` is not an http(s) URL."),
+                ));
+            }
+        }
+        if let Some(timezone) = &request.timezone {
+            if timezone.parse::<Tz>().is_err() {
+                return Ok(invalid(
+                    ProfileSituation::InvalidTimezone,
+                    format!("`{timezone}` is not an IANA time zone."),
+                ));
+            }
+        }
+        if let Some(locale) = &request.locale {
+            if parse_locale(locale).is_none() {
+                return Ok(invalid(
+                    ProfileSituation::InvalidLocale,
+                    format!("`{locale}` is not a supported locale."),
+                ));
+            }
+        }
+
+        let mut user = match self.user_repository.find(user_email).await? {
+            Some(user) => user,
+            None => {
+                return Ok(ProfileResult {
+                    situation: ProfileSituation::NotFound,
+                    user: None,
+                    description: None,
+                })
+            }
+        };
+        if let Some(user_name) = &request.user_name {
+            user.user_name = user_name.to_owned();
+        }
+        if let Some(avatar_url) = &request.avatar_url {
+            user.avatar_url = Some(avatar_url.to_owned()).filter(|url| !url.is_empty());
+        }
+        if let Some(timezone) = &request.timezone {
+            user.timezone = timezone.to_owned();
+        }
+        if let Some(locale) = &request.locale {
+            user.locale = locale.to_owned();
+        }
+        user.updated_date = chrono::Utc::now();
+
+        if !self.user_repository.update(&user).await? {
+            return Ok(ProfileResult {
+                situation: ProfileSituation::NotFound,
+                user: None,
+                description: None,
+            });
+        }
+        Ok(ProfileResult {
+            situation: ProfileSituation::Succeeded,
+            user: Some(user),
+            description: None,
+        })
+    }
+
+    async fn delete_account(&self, user_email: &str) -> Result<bool> {
+        self.user_repository.delete(user_email).await
+    }
+
+    async fn publish_calendar(&self, user_email: &str) -> Result<Option<PublicCalendar>> {
+        let slug: String = rand::thread_rng()
+            .sample_iter(&Alphanumeric)
+            .take(PUBLIC_SLUG_LENGTH)
+            .map(char::from)
+            .collect();
+        if !self
+            .user_repository
+            .update_public_slug(user_email, Some(slug.to_owned()))
+            .await?
+        {
+            return Ok(None);
+        }
+        Ok(Some(PublicCalendar { slug }))
+    }
+
+    async fn unpublish_calendar(&self, user_email: &str) -> Result<bool> {
+        self.user_repository
+            .update_public_slug(user_email, None)
+            .await
+    }
+
+    async fn find_public_calendar_owner(&self, slug: &str) -> Result<Option<String>> {
+        self.user_repository.find_email_by_public_slug(slug).await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use chrono::DateTime;
+
+    use super::{UserUsecase, UserUsecaseImpl};
+    use crate::domain::users::User;
+    use crate::dto::{ProfileRequest, ProfileSituation};
+    use crate::repositories::users_repository::MockUserRepository;
+
+    fn user() -> User {
+        User {
+            email: "test@example.com".to_owned(),
+            user_name: "test".to_owned(),
+            avatar_url: Some("https://example.com/avatar.png".to_owned()),
+            timezone: "UTC".to_owned(),
+            locale: "en_US".to_owned(),
+            registered_date: DateTime::UNIX_EPOCH,
+            updated_date: DateTime::UNIX_EPOCH,
+        }
+    }
+
+    mod update_profile {
+        use super::*;
+
+        #[actix_web::test]
+        async fn 指定した項目だけを更新し更新日時を進める() {
+            let mut mock_repository = MockUserRepository::new();
+            mock_repository
+                .expect_find()
+                .withf(|email| email == "test@example.com")
+                .returning(|_| Ok(Some(user())));
+            mock_repository
+                .expect_update()
+                .withf(|user| {
+                    user.user_name == "renamed"
+                        && user.avatar_url.is_none()
+                        && user.timezone == "UTC"
+                        && user.locale == "ja_JP"
+                        && user.updated_date > DateTime::UNIX_EPOCH + chrono::Duration::seconds(1)
+                })
+                .returning(|_| Ok(true));
+            let usecase = UserUsecaseImpl::new(Box::new(mock_repository));
+
+            let result = usecase
+                .update_profile(
+                    "test@example.com",
+                    &ProfileRequest {
+                        user_name: Some("renamed".to_owned()),
+                        avatar_url: Some("".to_owned()),
+                        timezone: None,
+                        locale: Some("ja_JP".to_owned()),
+                    },
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(ProfileSituation::Succeeded, result.situation);
+            assert_eq!("renamed", result.user.unwrap().user_name);
+        }
+
+        #[actix_web::test]
+        async fn 空のユーザ名は更新しない() {
+            let usecase = UserUsecaseImpl::new(Box::new(MockUserRepository::new()));
+
+            let result = usecase
+                .update_profile(
+                    "test@example.com",
+                    &ProfileRequest {
+                        user_name: Some("".to_owned()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+
+            assert_eq!(ProfileSituation::UserNameIsEmpty, result.situation);
+        }
+
+        #[actix_web::test]
+        async fn 不正なタイムゾーンやアバターurlやロケールは更新しない() {
+            let usecase = UserUsecaseImpl::new(Box::new(MockUserRepository::new()));
+
+            let result = usecase
+                .update_profile(
+                    "test@example.com",
+                    &ProfileRequest {
+                        timezone: Some("Mars/Olympus_Mons".to_owned()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(ProfileSituation::InvalidTimezone, result.situation);
+
+            let result = usecase
+                .update_profile(
+                    "test@example.com",
+                    &ProfileRequest {
+                        avatar_url: Some("javascript:alert(1)".to_owned()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(ProfileSituation::InvalidAvatarUrl, result.situation);
+
+            let result = usecase
+                .update_profile(
+                    "test@example.com",
+                    &ProfileRequest {
+                        locale: Some("xx_YY".to_owned()),
+                        ..Default::default()
+                    },
+                )
+                .await
+                .unwrap();
+            assert_eq!(ProfileSituation::InvalidLocale, result.situation);
+        }
+    }
+
+    mod publish_calendar {
+        use super::*;
+
+        #[actix_web::test]
+        async fn 公開するたびに新しいランダムなスラッグを割り当てる() {
+            let mut mock_repository = MockUserRepository::new();
+            mock_repository
+                .expect_update_public_slug()
+                .withf(|email, slug| {
+                    email == "test@example.com"
+                        && slug.as_ref().is_some_and(|slug| {
+                            slug.len() == 32 && slug.chars().all(|c| c.is_ascii_alphanumeric())
+                        })
+                })
+                .times(2)
+                .returning(|_, _| Ok(true));
+            let usecase = UserUsecaseImpl::new(Box::new(mock_repository));
+
+            let first = usecase.publish_calendar("test@example.com").await.unwrap();
+            let second = usecase.publish_calendar("test@example.com").await.unwrap();
+
+            assert_ne!(first.unwrap().slug, second.unwrap().slug);
+        }
+    }
+}
@@ -1,9 +1,4 @@
 set client_encoding = 'UTF8';
 
-create table users (
-  email varchar primary key,
-  external_id varchar not null,
-  user_name varchar not null,
-  registered_date TIMESTAMP not null,
-  updated_date TIMESTAMP not null
-);
\ No newline at end of file
+-- The schema is managed by the migrations embedded in the effort_visualizer binary.
+-- Run `effort_visualizer migrate up` or set DB_MIGRATE_ON_STARTUP=true to create it.